@@ -38,20 +38,21 @@ pub fn clean(cache: bool, all: bool, unused: bool) -> Result<()> {
     }
 
     if unused {
-        if let Ok(config) = super::load_config() {
-            let mut keep_deps = Vec::new();
-            if let Some(deps) = config.dependencies {
-                for (name, _) in deps {
-                    keep_deps.push(name);
-                }
-            }
-            crate::cache::prune_unused(&keep_deps)?;
-            cleaned = true;
-        } else {
+        // cx.lock holds every source and revision the project uses
+        if super::load_config().is_err() {
             println!(
                 "{} Could not load cx.toml to determine unused packages.",
                 "!".yellow()
             );
+        } else if !Path::new("cx.lock").exists() {
+            println!(
+                "{} No cx.lock found; run 'cx build' first so unused packages can be determined.",
+                "!".yellow()
+            );
+        } else {
+            let lock = crate::lock::LockFile::load()?;
+            crate::cache::prune_unused(&lock)?;
+            cleaned = true;
         }
     }
 
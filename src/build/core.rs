@@ -10,16 +10,31 @@
 //! - Compile commands JSON generation for IDE integration
 //! - Chrome trace profiling output
 //! - LTO and sanitizer support
-
-use super::utils::{get_compiler, get_std_flag_gcc, get_std_flag_msvc, load_config, run_script};
-use crate::config::CxConfig;
+//! - Cross-compilation with `--target` (per-target build directories)
+
+use super::dep_libs;
+use super::diagnostics::{self, MessageFormat};
+use super::feedback::FeedbackAnalyzer;
+use super::fingerprint::{self, FingerprintStore};
+use super::modules::{self, CompilerFamily, ModuleGraph, ModuleUnit};
+use super::object_cache::ObjectCache;
+use super::unity;
+use super::utils::{
+    get_archiver, get_artifact_name, get_compiler, get_object_path, get_profile_flags,
+    get_std_flag_gcc, get_std_flag_msvc, load_config, run_script,
+};
+use crate::config::{ArtifactKind, CxConfig, Profile, TargetSettings};
 use crate::deps;
+use crate::status;
+use crate::toolchain::c_compiler_for;
+use crate::toolchain::cross::{CrossTarget, TargetOs};
 use crate::ui;
 use anyhow::{Context, Result};
 use colored::*;
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
 use rayon::prelude::*;
 use serde_json::json;
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
@@ -49,10 +64,292 @@ pub struct BuildOptions {
     pub sanitize: Option<String>,
     /// Named profile for cross-compilation (e.g., "esp32", "linux-arm64")
     pub profile: Option<String>,
+    /// Only build this `[[bin]]` target
+    pub bin: Option<String>,
+    /// Max parallel compile jobs (overrides `CX_JOBS` and `[build] jobs`)
+    pub jobs: Option<usize>,
+    /// Compile every file and report all failures instead of stopping at the first
+    pub keep_going: bool,
+    /// Report diagnostics and artifacts as human text or JSON lines
+    pub message_format: MessageFormat,
+    /// Unity build (overrides `[build] unity`)
+    pub unity: bool,
+    /// Cross-compilation target name or triple (overrides the profile's `target`)
+    pub target: Option<String>,
+    /// Toolchain for `target` instead of `[targets.<name>]` (set by the parent
+    /// build when building a path dependency)
+    pub target_settings: Option<TargetSettings>,
+}
+
+/// Scan sources for C++20 module declarations and build the module graph,
+/// adding the toolchain's standard library modules when `import std;` is used
+fn scan_modules(
+    source_files: &mut Vec<PathBuf>,
+    base_args: &dyn Fn(&Path, &Path) -> Vec<String>,
+    family: CompilerFamily,
+    compiler: &str,
+    build_dir: &Path,
+    is_msvc: bool,
+    toolchain_env: &HashMap<String, String>,
+) -> Result<ModuleGraph> {
+    let obj_dir = build_dir.join("obj");
+    let ddi_dir = build_dir.join("modules");
+    let mut units = Vec::new();
+    for src in source_files.iter().filter(|src| !is_c_source(src)) {
+        let obj = get_object_path(&obj_dir, src, is_msvc);
+        let ddi = get_object_path(&ddi_dir, src, false).with_extension("ddi");
+        let args = base_args(src, &obj);
+        let unit = modules::scan(family, &args, src, &obj, &ddi, toolchain_env)?;
+        units.push((src.clone(), unit));
+    }
+
+    let wants_std = units
+        .iter()
+        .flat_map(|(_, unit)| &unit.requires)
+        .any(|name| name == "std" || name == "std.compat");
+    if wants_std {
+        for (name, path) in modules::std_library_modules(family, compiler, toolchain_env) {
+            let requires = if name == "std" {
+                Vec::new()
+            } else {
+                vec!["std".to_string()]
+            };
+            source_files.push(path.clone());
+            units.push((
+                path,
+                ModuleUnit {
+                    provides: Some(name),
+                    requires,
+                },
+            ));
+        }
+    }
+
+    ModuleGraph::new(family, build_dir.join("bmi"), units)
+}
+
+/// Objects compiled by the last build, in `.cx/build/<profile>/`
+pub(super) const OBJECT_LIST: &str = "objects.json";
+
+/// A single artifact produced by the link phase
+struct LinkTarget {
+    output: PathBuf,
+    objects: Vec<PathBuf>,
+    kind: ArtifactKind,
+}
+
+/// Resolve the number of parallel compile jobs.
+/// Priority: `--jobs` > `CX_JOBS` > `[build] jobs`; `None` means one per core.
+pub(super) fn resolve_jobs(cli: Option<usize>, config: &CxConfig) -> Result<Option<usize>> {
+    jobs_from(cli, std::env::var("CX_JOBS").ok().as_deref(), config)
+}
+
+/// [`resolve_jobs`] with the value of `CX_JOBS` passed in
+fn jobs_from(cli: Option<usize>, env: Option<&str>, config: &CxConfig) -> Result<Option<usize>> {
+    let jobs = match (cli, env) {
+        (Some(jobs), _) => Some(jobs),
+        (None, Some(env)) if !env.trim().is_empty() => Some(
+            env.trim()
+                .parse::<usize>()
+                .with_context(|| format!("Invalid CX_JOBS value '{}'", env))?,
+        ),
+        _ => config.build.as_ref().and_then(|b| b.jobs),
+    };
+    Ok(jobs.filter(|&j| j > 0))
+}
+
+/// Why [`compile_waves`] stopped
+enum CompileFailure<'a> {
+    /// The first error (without `--keep-going`)
+    Error(anyhow::Error),
+    /// Every file that failed, and those skipped because they import a module
+    /// one of them provides (`--keep-going`)
+    Files {
+        failed: Vec<&'a PathBuf>,
+        skipped: Vec<&'a PathBuf>,
+    },
+}
+
+/// Compile `waves` one after another: later ones import BMIs from earlier
+/// ones. Without `keep_going` scheduling stops after the first failure; with
+/// it every file is compiled except those that `depends_on` (source,
+/// unusable file) a file that failed or was skipped, and all failures are
+/// collected.
+fn compile_waves<'a, T: Send>(
+    waves: &'a [Vec<PathBuf>],
+    keep_going: bool,
+    depends_on: impl Fn(&Path, &Path) -> bool,
+    compile: impl Fn(&'a PathBuf) -> Result<T> + Sync + Send,
+) -> Result<Vec<T>, CompileFailure<'a>> {
+    let mut compiled = Vec::new();
+    let mut failed = Vec::new();
+    let mut skipped = Vec::new();
+    for wave in waves {
+        if !keep_going {
+            let results: Result<Vec<_>> = wave.par_iter().map(&compile).collect();
+            compiled.extend(results.map_err(CompileFailure::Error)?);
+            continue;
+        }
+
+        let (blocked, ready): (Vec<&PathBuf>, Vec<&PathBuf>) = wave.iter().partition(|src| {
+            failed
+                .iter()
+                .chain(&skipped)
+                .any(|unusable: &&PathBuf| depends_on(src, unusable))
+        });
+        skipped.extend(blocked);
+
+        let outcomes: Vec<_> = ready.par_iter().map(|src| compile(src)).collect();
+        for (src_path, outcome) in ready.into_iter().zip(outcomes) {
+            match outcome {
+                Ok(result) => compiled.push(result),
+                Err(_) => failed.push(src_path),
+            }
+        }
+    }
+    if failed.is_empty() {
+        Ok(compiled)
+    } else {
+        Err(CompileFailure::Files { failed, skipped })
+    }
+}
+
+/// Name of the profile being built: the named one, else `release` or `debug`
+fn profile_name(profile: Option<&str>, release: bool) -> &str {
+    profile.unwrap_or(if release { "release" } else { "debug" })
+}
+
+/// Output directory of a build, `.cx/build[/<target>]/<profile>`. Named
+/// profiles get their own, so switching between them never reuses
+/// incompatible objects.
+fn profile_build_dir(profile: &str, target: Option<&str>) -> PathBuf {
+    let mut dir = Path::new(".cx").join("build");
+    if let Some(target) = target {
+        dir.push(target);
+    }
+    dir.join(profile)
+}
+
+/// Check whether a source file is C (compiled with the C driver and C standard)
+fn is_c_source(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "c")
+}
+
+/// Strip `./` components so `./src/a.cpp` and `src/a.cpp` compare equal
+fn normalize_source_path(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|c| !matches!(c, std::path::Component::CurDir))
+        .collect()
+}
+
+/// Resolve the executables declared with `[[bin]]` and the files each one owns.
+///
+/// When `[[bin]]` tables are present, `src/main.{cpp,cc,cxx,c}` becomes the
+/// package's own binary (named after `bin_basename`) instead of a shared object.
+/// Returns an empty list for single-binary projects.
+pub(crate) fn resolve_bin_targets(
+    config: &CxConfig,
+    bin_basename: &str,
+) -> Vec<(String, Vec<PathBuf>)> {
+    let Some(bins) = config.bins.as_ref().filter(|b| !b.is_empty()) else {
+        return Vec::new();
+    };
+
+    let mut targets: Vec<(String, Vec<PathBuf>)> = bins
+        .iter()
+        .map(|b| {
+            let files = b
+                .owned_sources()
+                .iter()
+                .map(|f| normalize_source_path(Path::new(f)))
+                .collect();
+            (b.name.clone(), files)
+        })
+        .collect();
+
+    let kind = config
+        .build
+        .as_ref()
+        .map(|b| b.artifact_kind())
+        .unwrap_or_default();
+
+    if kind == ArtifactKind::Bin && !targets.iter().any(|(name, _)| name == bin_basename) {
+        let package_main = ["cpp", "cc", "cxx", "c"]
+            .iter()
+            .map(|ext| PathBuf::from("src").join(format!("main.{}", ext)))
+            .find(|p| p.exists());
+        if let Some(main) = package_main
+            && !targets.iter().any(|(_, files)| files.contains(&main))
+        {
+            targets.insert(0, (bin_basename.to_string(), vec![main]));
+        }
+    }
+
+    targets
+}
+
+/// Split compiled objects into per-artifact link inputs.
+///
+/// Objects whose source is owned by a `[[bin]]` target only go into that
+/// binary; everything else is shared by all binaries (and forms the library
+/// when `kind` is a library).
+fn plan_link_targets(
+    source_files: &[PathBuf],
+    object_files: &[PathBuf],
+    bins: &[(PathBuf, Vec<PathBuf>)],
+    output: &Path,
+    kind: ArtifactKind,
+) -> Vec<LinkTarget> {
+    if bins.is_empty() {
+        return vec![LinkTarget {
+            output: output.to_path_buf(),
+            objects: object_files.to_vec(),
+            kind,
+        }];
+    }
+
+    let owner = |src: &Path| {
+        let src = normalize_source_path(src);
+        bins.iter().position(|(_, files)| files.contains(&src))
+    };
+
+    let shared: Vec<PathBuf> = source_files
+        .iter()
+        .zip(object_files)
+        .filter(|(src, _)| owner(src).is_none())
+        .map(|(_, obj)| obj.clone())
+        .collect();
+
+    let mut targets = Vec::new();
+    if kind.is_library() {
+        targets.push(LinkTarget {
+            output: output.to_path_buf(),
+            objects: shared.clone(),
+            kind,
+        });
+    }
+
+    for (i, (bin_output, _)) in bins.iter().enumerate() {
+        let mut objects = shared.clone();
+        objects.extend(
+            source_files
+                .iter()
+                .zip(object_files)
+                .filter(|(src, _)| owner(src) == Some(i))
+                .map(|(_, obj)| obj.clone()),
+        );
+        targets.push(LinkTarget {
+            output: bin_output.clone(),
+            objects,
+            kind: ArtifactKind::Bin,
+        });
+    }
+
+    targets
 }
 
 // --- Helper: Check Dependencies (.d file or .json for MSVC) ---
-fn check_dependencies(obj_path: &Path, src_path: &Path) -> Result<bool> {
+pub(super) fn check_dependencies(obj_path: &Path, src_path: &Path) -> Result<bool> {
     // 1. Check for MSVC JSON dependencies first
     // Actually typically /sourceDependencies foo.json -> foo.json.
     // We will name it <obj>.json explicitly.
@@ -127,97 +424,94 @@ fn check_dependencies(obj_path: &Path, src_path: &Path) -> Result<bool> {
 
 // --- CORE: Build Project ---
 pub fn build_project(config: &CxConfig, options: &BuildOptions) -> Result<bool> {
-    let release = options.release;
+    let mut release = options.release;
     let verbose = options.verbose;
     let dry_run = options.dry_run;
     let enable_profile = options.enable_profile;
-    let wasm = options.wasm;
-    let lto = options.lto;
-    let sanitize = options.sanitize.clone();
+    let mut wasm = options.wasm;
+    let keep_going = options.keep_going;
+    let json_output = options.message_format.is_json();
+    diagnostics::set_message_format(options.message_format);
     let start_time = Instant::now();
 
     // --- Profile Resolution with Inheritance ---
     // Clone config for potential modification based on selected profile
     let mut effective_config = config.clone();
+    let mut resolved = Profile::default();
 
     if let Some(profile_name) = &options.profile {
         // Look up the profile in config.profiles
         if let Some(profile) = config.profiles.get(profile_name) {
-            println!(
+            status!(
                 "   {} Using profile: {}",
                 "🎯".magenta(),
                 profile_name.cyan().bold()
             );
 
             // Resolve base profile first (inheritance)
-            let mut resolved_flags: Vec<String> = Vec::new();
-            let mut resolved_libs: Vec<String> = Vec::new();
-            let mut resolved_compiler: Option<String> = None;
-
-            if let Some(base_name) = &profile.base {
-                // Handle built-in profiles (release/debug) or user-defined profiles
-                if base_name == "release" {
-                    // Release implies optimizations, but we handle that via options.release
-                    // Just note it for verbosity
-                    if verbose {
-                        println!("      {} Inheriting from: {}", "└─".dimmed(), base_name);
-                    }
-                } else if base_name == "debug" {
-                    // Debug is default, nothing special needed
-                    if verbose {
-                        println!("      {} Inheriting from: {}", "└─".dimmed(), base_name);
-                    }
-                } else if let Some(base_profile) = config.profiles.get(base_name) {
-                    // Inherit from another user-defined profile
-                    if verbose {
-                        println!("      {} Inheriting from: {}", "└─".dimmed(), base_name);
-                    }
-                    if let Some(ref flags) = base_profile.flags {
-                        resolved_flags.extend(flags.clone());
-                    }
-                    if let Some(ref libs) = base_profile.libs {
-                        resolved_libs.extend(libs.clone());
-                    }
-                    if let Some(ref compiler) = base_profile.compiler {
-                        resolved_compiler = Some(compiler.clone());
-                    }
+            let mut base = Profile::default();
+            let mut seen = vec![profile_name];
+            let mut next = profile.base.as_ref();
+            while let Some(name) = next {
+                if verbose {
+                    status!("      {} Inheriting from: {}", "└─".dimmed(), name);
+                }
+                // Built-in profiles: release turns on optimizations, debug is the default
+                if name == "release" {
+                    release = true;
+                    break;
+                } else if name == "debug" {
+                    break;
                 }
+                if seen.contains(&name) {
+                    anyhow::bail!(
+                        "Profile '{}' has an inheritance cycle through '{}'",
+                        profile_name,
+                        name
+                    );
+                }
+                let Some(base_profile) = config.profiles.get(name) else {
+                    anyhow::bail!(
+                        "Profile '{}' inherits from unknown profile '{}'",
+                        profile_name,
+                        name
+                    );
+                };
+                seen.push(name);
+                base = base.inherit(base_profile);
+                next = base_profile.base.as_ref();
             }
 
             // Apply this profile's settings (override base)
-            if let Some(ref flags) = profile.flags {
-                resolved_flags.extend(flags.clone());
-            }
-            if let Some(ref libs) = profile.libs {
-                resolved_libs.extend(libs.clone());
-            }
-            if let Some(ref compiler) = profile.compiler {
-                resolved_compiler = Some(compiler.clone());
-            }
+            resolved = profile.inherit(&base);
 
             // Apply resolved values to effective_config
             let build_cfg = effective_config.build.get_or_insert_with(Default::default);
 
             // Merge flags into build config
-            if !resolved_flags.is_empty() {
-                let existing = build_cfg.flags.get_or_insert_with(Vec::new);
-                existing.extend(resolved_flags);
+            if let Some(flags) = resolved.flags.take() {
+                build_cfg.flags.get_or_insert_with(Vec::new).extend(flags);
             }
 
-            // Override libs
-            if !resolved_libs.is_empty() {
-                let existing = build_cfg.libs.get_or_insert_with(Vec::new);
-                existing.extend(resolved_libs);
+            // Merge libs and linker flags
+            if let Some(libs) = resolved.libs.take() {
+                build_cfg.libs.get_or_insert_with(Vec::new).extend(libs);
+            }
+            if let Some(ldflags) = resolved.ldflags.take() {
+                build_cfg
+                    .ldflags
+                    .get_or_insert_with(Vec::new)
+                    .extend(ldflags);
             }
 
             // Override compiler
-            if let Some(compiler) = resolved_compiler {
+            if let Some(compiler) = resolved.compiler.take() {
                 build_cfg.compiler = Some(compiler);
             }
 
             // Apply bin name override
-            if let Some(ref bin) = profile.bin {
-                build_cfg.bin = Some(bin.clone());
+            if let Some(bin) = resolved.bin.take() {
+                build_cfg.bin = Some(bin);
             }
         } else {
             return Err(anyhow::anyhow!(
@@ -228,9 +522,69 @@ pub fn build_project(config: &CxConfig, options: &BuildOptions) -> Result<bool>
         }
     }
 
+    // CLI switches win over the profile's declarative settings
+    let lto = options.lto || resolved.lto.unwrap_or(false);
+    let sanitize = options.sanitize.clone().or(resolved.sanitize.clone());
+    let profile_target = resolved.target.clone();
+
+    // --- Features ---
+    let features = crate::features::apply_project(&mut effective_config)?;
+    if !features.features.is_empty() {
+        status!(
+            "   {} Features: {}",
+            "🧩".magenta(),
+            features.features.join(", ").cyan()
+        );
+    }
+    resolved
+        .defines
+        .get_or_insert_with(Vec::new)
+        .extend(features.defines.iter().cloned());
+
     // Use effective_config from now on
     let config = &effective_config;
+
+    // --- Cross-compilation Target ---
+    let mut cross = match options.target.as_ref().or(profile_target.as_ref()) {
+        Some(name) => Some(match &options.target_settings {
+            Some(settings) => CrossTarget::with_settings(name, settings.clone())?,
+            None => CrossTarget::resolve(name, config.targets.as_ref())?,
+        }),
+        None => None,
+    };
+    if let Some(target) = cross.as_mut() {
+        status!(
+            "   {} Target: {} ({})",
+            "🎯".magenta(),
+            target.name.cyan().bold(),
+            target.triple
+        );
+        if target.is_wasm() {
+            wasm = true;
+        } else if !target.is_native() {
+            let tc = target.detect_toolchain()?;
+            status!(
+                "   {} Cross toolchain: {} ({})",
+                "🔧".cyan(),
+                tc.cxx_path.display(),
+                tc.version
+            );
+        }
+    }
+    let target_os = match &cross {
+        _ if wasm => TargetOs::Wasm,
+        Some(target) => target.os,
+        None => TargetOs::host(),
+    };
+    // Deps and pkg-config only need redirecting when a cross toolchain is used
+    let dep_target = cross.as_ref().filter(|t| t.toolchain.is_some());
     let current_dir = std::env::current_dir()?;
+    let jobs = resolve_jobs(options.jobs, config)?;
+    // Every compile (dependency sources included) runs in this pool
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs.unwrap_or(0)) // 0 = one thread per core
+        .build()
+        .context("Failed to create compile thread pool")?;
 
     // Dry-run or Verbose header with modern box styling
     let show_details = verbose || dry_run;
@@ -241,7 +595,11 @@ pub fn build_project(config: &CxConfig, options: &BuildOptions) -> Result<bool>
         } else {
             &config.package.edition
         };
-        let profile_str = if release { "release" } else { "debug" };
+        let profile_str =
+            options
+                .profile
+                .as_deref()
+                .unwrap_or(if release { "release" } else { "debug" });
         let compiler_str = if wasm {
             "em++"
         } else {
@@ -253,14 +611,14 @@ pub fn build_project(config: &CxConfig, options: &BuildOptions) -> Result<bool>
                 .unwrap_or("auto")
         };
 
-        println!();
+        status!();
         let mode = if dry_run {
             "DRY RUN".yellow().bold()
         } else {
             "BUILD".cyan().bold()
         };
         let icon = if dry_run { "🔍" } else { "🔧" };
-        println!("  {} {}", icon, mode);
+        status!("  {} {}", icon, mode);
 
         let mut table = ui::Table::new(&["Setting", "Value"]);
 
@@ -288,7 +646,14 @@ pub fn build_project(config: &CxConfig, options: &BuildOptions) -> Result<bool>
             "Compiler".dimmed().to_string(),
             compiler_str.cyan().to_string(),
         ]);
-        if wasm {
+        if let Some(target) = &cross {
+            table.add_row(vec![
+                "Target".dimmed().to_string(),
+                format!("{} ({})", target.name, target.triple)
+                    .magenta()
+                    .to_string(),
+            ]);
+        } else if wasm {
             table.add_row(vec![
                 "Target".dimmed().to_string(),
                 "WASM (Emscripten)".magenta().to_string(),
@@ -306,9 +671,12 @@ pub fn build_project(config: &CxConfig, options: &BuildOptions) -> Result<bool>
                 san.yellow().bold().to_string(),
             ]);
         }
+        if let Some(jobs) = jobs {
+            table.add_row(vec!["Jobs".dimmed().to_string(), jobs.to_string()]);
+        }
 
         table.print();
-        println!();
+        status!();
     }
 
     // 1. Pre-build Script
@@ -316,17 +684,17 @@ pub fn build_project(config: &CxConfig, options: &BuildOptions) -> Result<bool>
         && let Some(pre) = &scripts.pre_build
     {
         if verbose {
-            println!("{} Running pre-build script: {}", "→".blue(), pre);
+            status!("{} Running pre-build script: {}", "→".blue(), pre);
         }
         if let Err(e) = run_script(pre, &current_dir) {
-            println!("{} Pre-build script failed: {}", "x".red(), e);
+            status!("{} Pre-build script failed: {}", "x".red(), e);
             return Ok(false);
         }
     }
 
     // 2. Setup Directories
-    let profile = if release { "release" } else { "debug" };
-    let build_dir = Path::new(".cx").join("build").join(profile);
+    let profile = profile_name(options.profile.as_deref(), release);
+    let build_dir = profile_build_dir(profile, cross.as_ref().map(|t| t.name.as_str()));
     let obj_dir = build_dir.join("obj");
     fs::create_dir_all(&obj_dir)?;
 
@@ -336,35 +704,48 @@ pub fn build_project(config: &CxConfig, options: &BuildOptions) -> Result<bool>
         config.package.name.clone()
     };
 
-    let bin_name = if wasm {
-        format!("{}.html", bin_basename)
-    } else if cfg!(target_os = "windows") {
-        format!("{}.exe", bin_basename)
-    } else {
-        bin_basename
-    };
-
-    let output_bin = build_dir.join(&bin_name);
+    let kind = config
+        .build
+        .as_ref()
+        .map(|b| b.artifact_kind())
+        .unwrap_or_default();
 
-    if verbose {
-        println!("{}", "Paths:".bold());
-        println!("  Output: {}", output_bin.display().to_string().cyan());
-        println!("  Objects: {}", obj_dir.display().to_string().dimmed());
-        println!();
+    if wasm && kind == ArtifactKind::Sharedlib {
+        status!(
+            "{} Shared libraries are not supported for WebAssembly builds.",
+            "x".red()
+        );
+        return Ok(false);
     }
 
     // 3. Fetch Dependencies
     let mut include_paths = Vec::new();
     let mut extra_cflags = Vec::new();
     let mut dep_libs = Vec::new();
+    let mut dep_lib_names = Vec::new();
+    let mut dep_sources = Vec::new();
+    let mut dep_external = Vec::new();
+    let mut dep_defines = Vec::new();
+    let mut dep_link_dirs = Vec::new();
 
     if let Some(deps) = &config.dependencies
         && !deps.is_empty()
     {
-        let (paths, cflags, libs) = deps::fetch_dependencies(deps)?;
-        include_paths = paths;
-        extra_cflags = cflags;
-        dep_libs = libs;
+        let dep_build = deps::DepBuild {
+            target: dep_target,
+            release,
+            profile: options.profile.as_deref(),
+            jobs,
+        };
+        let resolved = deps::fetch_dependencies(deps, &dep_build)?;
+        include_paths = resolved.include_paths;
+        extra_cflags = resolved.cflags;
+        dep_libs = resolved.link_flags;
+        dep_lib_names = resolved.libs;
+        dep_sources = resolved.sources;
+        dep_external = resolved.external;
+        dep_defines = resolved.defines;
+        dep_link_dirs = resolved.link_dirs;
     }
 
     // 4. Collect Source Files
@@ -385,7 +766,7 @@ pub fn build_project(config: &CxConfig, options: &BuildOptions) -> Result<bool>
                     source_files.push(path.to_owned());
                 }
             } else {
-                println!("{} Source file not found: {}", "!".yellow(), src);
+                status!("{} Source file not found: {}", "!".yellow(), src);
             }
         }
     } else {
@@ -393,7 +774,9 @@ pub fn build_project(config: &CxConfig, options: &BuildOptions) -> Result<bool>
             let path = entry.path();
             if let Some(ext) = path.extension() {
                 let s = ext.to_string_lossy();
-                if ["cpp", "cc", "cxx", "c"].contains(&s.as_ref()) {
+                if ["cpp", "cc", "cxx", "c"].contains(&s.as_ref())
+                    || modules::is_interface_unit(path)
+                {
                     if s != "c" {
                         has_cpp = true;
                     }
@@ -403,14 +786,106 @@ pub fn build_project(config: &CxConfig, options: &BuildOptions) -> Result<bool>
         }
     }
 
+    // Features: leave out the sources of disabled ones, add the enabled ones
+    source_files.retain(|p| !features.is_excluded(p));
+    for entry in &features.sources {
+        let path = Path::new(entry);
+        let files: Vec<PathBuf> = if path.is_dir() {
+            WalkDir::new(path)
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .map(|e| e.into_path())
+                .filter(|p| {
+                    p.extension().is_some_and(|ext| {
+                        ["cpp", "cc", "cxx", "c"].contains(&ext.to_string_lossy().as_ref())
+                    }) || modules::is_interface_unit(p)
+                })
+                .collect()
+        } else if path.exists() {
+            vec![path.to_owned()]
+        } else {
+            status!("{} Source file not found: {}", "!".yellow(), entry);
+            continue;
+        };
+        for file in files {
+            let normalized = normalize_source_path(&file);
+            if source_files
+                .iter()
+                .any(|p| normalize_source_path(p) == normalized)
+            {
+                continue;
+            }
+            if file.extension().is_some_and(|e| e != "c") {
+                has_cpp = true;
+            }
+            source_files.push(file);
+        }
+    }
+
+    // [[bin]] targets: make sure every owned file is compiled, then drop the
+    // ones that belong to binaries not selected with --bin
+    let mut bin_targets = resolve_bin_targets(config, &bin_basename);
+    if let Some(selected) = &options.bin
+        && !bin_targets.iter().any(|(name, _)| name == selected)
+    {
+        let names: Vec<&str> = bin_targets.iter().map(|(n, _)| n.as_str()).collect();
+        anyhow::bail!(
+            "No binary target named '{}'. Available: {}",
+            selected,
+            if names.is_empty() {
+                "(none, add [[bin]] tables to cx.toml)".to_string()
+            } else {
+                names.join(", ")
+            }
+        );
+    }
+
+    if !bin_targets.is_empty() {
+        let known: Vec<PathBuf> = source_files
+            .iter()
+            .map(|p| normalize_source_path(p))
+            .collect();
+        for (name, files) in &bin_targets {
+            for file in files {
+                if !file.exists() {
+                    status!(
+                        "{} Source file not found for bin '{}': {}",
+                        "x".red(),
+                        name,
+                        file.display()
+                    );
+                    return Ok(false);
+                }
+                if !known.contains(file) {
+                    if file.extension().is_some_and(|e| e != "c") {
+                        has_cpp = true;
+                    }
+                    source_files.push(file.clone());
+                }
+            }
+        }
+
+        if let Some(selected) = &options.bin {
+            let skipped: Vec<PathBuf> = bin_targets
+                .iter()
+                .filter(|(name, _)| name != selected)
+                .flat_map(|(_, files)| files.clone())
+                .collect();
+            source_files.retain(|p| !skipped.contains(&normalize_source_path(p)));
+            bin_targets.retain(|(name, _)| name == selected);
+        }
+    }
+
     if source_files.is_empty() {
-        println!("{} No source files found.", "!".yellow());
+        status!("{} No source files found.", "!".yellow());
         return Ok(false);
     }
 
     // Get toolchain with environment variables
     let toolchain = if wasm {
         None
+    } else if let Some(tc) = dep_target.and_then(|t| t.toolchain.clone()) {
+        Some(tc)
     } else {
         super::utils::get_toolchain(config, has_cpp).ok()
     };
@@ -423,6 +898,21 @@ pub fn build_project(config: &CxConfig, options: &BuildOptions) -> Result<bool>
         get_compiler(config, has_cpp)
     };
 
+    // `.c` files go through the matching C driver; the link uses the C++ driver
+    // whenever the project has any C++
+    let c_compiler = match &toolchain {
+        Some(tc) => tc.get_cc_compiler().to_string_lossy().to_string(),
+        None => c_compiler_for(Path::new(&compiler))
+            .to_string_lossy()
+            .to_string(),
+    };
+    let link_compiler = if has_cpp {
+        compiler.clone()
+    } else {
+        c_compiler.clone()
+    };
+    let c_edition = config.package.c_edition();
+
     // Helper to check for CCache
     let ccache_prefix = if !wasm {
         if Command::new("ccache").arg("--version").output().is_ok() {
@@ -439,26 +929,90 @@ pub fn build_project(config: &CxConfig, options: &BuildOptions) -> Result<bool>
     if wasm {
         // Simple check if em++ exists
         if Command::new(&compiler).arg("--version").output().is_err() {
-            println!("{} Emscripten (em++) not found in PATH.", "x".red());
-            println!("   Please install Emscripten SDK.");
+            status!("{} Emscripten (em++) not found in PATH.", "x".red());
+            status!("   Please install Emscripten SDK.");
             return Ok(false);
         }
     }
 
     let is_msvc = compiler.contains("cl.exe") || compiler == "cl";
+    let module_family = CompilerFamily::detect(&compiler);
     // let is_clang = compiler.contains("clang");
     // let is_gcc = compiler.contains("g++") || compiler.contains("gcc");
 
     let current_dir_str = current_dir.to_string_lossy().to_string();
 
+    // Unity build: swap batched sources for generated TUs. Entry points,
+    // [[bin]] files and module units are always compiled on their own.
+    let unity_dir = build_dir.join("unity");
+    let unity_obj_dir = get_object_path(&obj_dir, &unity_dir.join("unity"), is_msvc)
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| obj_dir.clone());
+    let unity_enabled =
+        options.unity || config.build.as_ref().and_then(|b| b.unity).unwrap_or(false);
+    if unity_enabled {
+        let build_cfg = config.build.clone().unwrap_or_default();
+        let exclude = build_cfg.unity_exclude.unwrap_or_default();
+        let bin_files: Vec<&PathBuf> = bin_targets.iter().flat_map(|(_, files)| files).collect();
+        let plan = unity::plan(
+            &source_files,
+            &unity_dir,
+            build_cfg
+                .unity_batch_size
+                .unwrap_or(unity::DEFAULT_BATCH_SIZE),
+            |src| {
+                src.file_stem().is_some_and(|s| s == "main")
+                    || bin_files.contains(&&normalize_source_path(src))
+                    || unity::is_excluded(src, &exclude)
+                    || (!is_c_source(src) && modules::uses_modules(src))
+            },
+        );
+        if !dry_run {
+            unity::write(&plan, &unity_dir)?;
+        }
+        if verbose {
+            status!(
+                "   {} Unity build: {} file(s) in {} batch(es)",
+                "🧱".blue(),
+                plan.batched().count(),
+                plan.batches.len()
+            );
+        }
+        source_files = plan.sources();
+    } else if !dry_run && unity_obj_dir.exists() {
+        fs::remove_dir_all(&unity_obj_dir)?;
+    }
+
+    let output_bin = build_dir.join(get_artifact_name(&bin_basename, kind, is_msvc, target_os));
+    let bin_outputs: Vec<(PathBuf, Vec<PathBuf>)> = bin_targets
+        .iter()
+        .map(|(name, files)| {
+            let output = build_dir.join(get_artifact_name(
+                name,
+                ArtifactKind::Bin,
+                is_msvc,
+                target_os,
+            ));
+            (output, files.clone())
+        })
+        .collect();
+
+    if verbose {
+        status!("{}", "Paths:".bold());
+        status!("  Output: {}", output_bin.display().to_string().cyan());
+        status!("  Objects: {}", obj_dir.display().to_string().dimmed());
+        status!();
+    }
+
     // Verbose: Show toolchain info
     if verbose {
-        println!("{}", "Toolchain:".bold());
-        println!("  Compiler: {}", compiler.cyan());
+        status!("{}", "Toolchain:".bold());
+        status!("  Compiler: {}", compiler.cyan());
         if let Some(cc) = ccache_prefix {
-            println!("  Wrapper: {}", cc.yellow().bold());
+            status!("  Wrapper: {}", cc.yellow().bold());
         }
-        println!(
+        status!(
             "  Type: {}",
             if is_msvc {
                 "MSVC".yellow()
@@ -467,22 +1021,85 @@ pub fn build_project(config: &CxConfig, options: &BuildOptions) -> Result<bool>
             }
         );
         if let Some(ref tc) = toolchain {
-            println!("  Source: Detected via vswhere/explicit config");
+            status!("  Source: Detected via vswhere/explicit config");
             if !tc.env_vars.is_empty() {
-                println!("  Env vars: {} injected", tc.env_vars.len());
+                status!("  Env vars: {} injected", tc.env_vars.len());
             }
         } else {
-            println!("  Source: PATH fallback");
+            status!("  Source: PATH fallback");
         }
-        println!();
+        status!();
     }
 
     // Clone env_vars for use in parallel compilation
-    let toolchain_env: std::collections::HashMap<String, String> = toolchain
+    let toolchain_env: HashMap<String, String> = toolchain
         .as_ref()
         .map(|tc| tc.env_vars.clone())
         .unwrap_or_default();
 
+    // Optimization, debug info, warnings and defines from the profile
+    let profile_flags = get_profile_flags(&resolved, release, is_msvc);
+
+    // Macros dependencies declare for their headers
+    let define = if is_msvc { "/D" } else { "-D" };
+    extra_cflags.extend(dep_defines.iter().map(|d| format!("{}{}", define, d)));
+
+    // Cross-compilation: --target=/--sysroot= and [targets.<name>] flags
+    let target_args = dep_target.map(|t| t.toolchain_args()).unwrap_or_default();
+    let mut target_flags = target_args.clone();
+    if let Some(target) = &cross {
+        target_flags.extend(target.flags.iter().cloned());
+    }
+
+    // 4a. Dependency Libraries (built once per toolchain and profile), first
+    // so the headers CMake installs are on the include path
+    let toolchain_fingerprint = toolchain
+        .as_ref()
+        .map(|tc| tc.fingerprint())
+        .unwrap_or_else(|| compiler.clone());
+    if !dry_run && (!dep_sources.is_empty() || !dep_external.is_empty()) {
+        let mut flags: Vec<String> = profile_flags
+            .iter()
+            .filter(|f| !["-Wall", "/W4", "-Werror", "/WX"].contains(&f.as_str()))
+            .cloned()
+            .collect();
+        // Position independent, so the same library links into executables
+        // and shared libraries alike
+        if !is_msvc && !wasm && target_os != TargetOs::Windows {
+            flags.push("-fPIC".to_string());
+        }
+        let lib_toolchain = dep_libs::LibToolchain {
+            compiler: &compiler,
+            c_compiler: &c_compiler,
+            is_msvc,
+            fingerprint: &toolchain_fingerprint,
+            env: &toolchain_env,
+            profile,
+            release,
+            jobs: pool.current_num_threads(),
+            flags,
+            target_flags: target_flags.clone(),
+            triple: cross.as_ref().map(|t| t.triple.as_str()),
+            edition: &config.package.edition,
+            c_edition: &c_edition,
+            target_os,
+        };
+        let built = pool.install(|| {
+            dep_libs::build_all(
+                &dep_external,
+                &dep_sources,
+                &include_paths,
+                &extra_cflags,
+                &lib_toolchain,
+            )
+        })?;
+        include_paths.extend(built.include_dirs);
+        extra_cflags.extend(built.cflags);
+        // Before pkg-config libraries they may depend on
+        dep_libs.splice(0..0, built.link_flags);
+        dep_lib_names.extend(built.libs);
+    }
+
     // Prepare Common Flags (Includes)
     let mut common_flags = Vec::new();
     for path in &include_paths {
@@ -514,32 +1131,40 @@ pub fn build_project(config: &CxConfig, options: &BuildOptions) -> Result<bool>
         }
     }
 
+    // Position Independent Code for libraries (MSVC and Windows don't need it)
+    if kind.is_library() && !is_msvc && !wasm && target_os != TargetOs::Windows {
+        common_flags.push("-fPIC".to_string());
+    }
+
+    common_flags.extend(target_flags);
+
     common_flags.extend(extra_cflags.clone());
 
     // Verbose: Show include paths and flags
     if verbose && !include_paths.is_empty() {
-        println!("{}", "Include Paths:".bold());
+        status!("{}", "Include Paths:".bold());
         for path in &include_paths {
-            println!("  -I {}", path.display().to_string().dimmed());
+            status!("  -I {}", path.display().to_string().dimmed());
         }
-        println!();
+        status!();
     }
 
     // 5. Dry-run: Show compile commands that would be executed
     if dry_run {
-        println!("{}", "Compile:".bold());
+        status!("{}", "Compile:".bold());
+        let mut planned_objects = Vec::new();
         for src_path in &source_files {
-            let stem = src_path
-                .file_stem()
-                .unwrap_or(src_path.as_os_str())
-                .to_string_lossy();
-            let obj_ext = if is_msvc { "obj" } else { "o" };
-            let obj_path = obj_dir.join(format!("{}.{}", stem, obj_ext));
+            let obj_path = get_object_path(&obj_dir, src_path, is_msvc);
 
             // Shorter, cleaner format
-            let short_compiler = Path::new(&compiler)
+            let src_compiler = if is_c_source(src_path) {
+                &c_compiler
+            } else {
+                &compiler
+            };
+            let short_compiler = Path::new(src_compiler)
                 .file_name()
-                .unwrap_or(compiler.as_ref())
+                .unwrap_or(src_compiler.as_ref())
                 .to_string_lossy();
 
             let prefix = ccache_prefix.map(|c| format!("{} ", c)).unwrap_or_default();
@@ -550,34 +1175,49 @@ pub fn build_project(config: &CxConfig, options: &BuildOptions) -> Result<bool>
                 short_compiler,
                 src_path.display(),
                 obj_path
-                    .file_name()
-                    .unwrap_or(obj_path.as_os_str())
-                    .to_string_lossy()
+                    .strip_prefix(&obj_dir)
+                    .unwrap_or(&obj_path)
+                    .display()
             );
-            println!("{}", cmd.dimmed());
+            status!("{}", cmd.dimmed());
+            planned_objects.push(obj_path);
         }
 
-        // Show link command
-        println!("\n{}", "Link:".bold());
-        let short_compiler = Path::new(&compiler)
-            .file_name()
-            .unwrap_or(compiler.as_ref())
-            .to_string_lossy();
-        let obj_count = source_files.len();
-        let bin_name = output_bin
-            .file_name()
-            .unwrap_or(output_bin.as_os_str())
-            .to_string_lossy();
-        println!(
-            "  → {} [{} object(s)] → {}",
-            short_compiler,
-            obj_count,
-            bin_name.cyan()
+        // Show link (or archive) commands
+        let link_targets = plan_link_targets(
+            &source_files,
+            &planned_objects,
+            &bin_outputs,
+            &output_bin,
+            kind,
         );
+        status!("\n{}", "Link:".bold());
+        for target in &link_targets {
+            let link_tool = if target.kind == ArtifactKind::Staticlib {
+                get_archiver(&compiler, is_msvc)
+            } else {
+                link_compiler.clone()
+            };
+            let short_compiler = Path::new(&link_tool)
+                .file_name()
+                .unwrap_or(link_tool.as_ref())
+                .to_string_lossy();
+            let bin_name = target
+                .output
+                .file_name()
+                .unwrap_or(target.output.as_os_str())
+                .to_string_lossy();
+            status!(
+                "  → {} [{} object(s)] → {}",
+                short_compiler,
+                target.objects.len(),
+                bin_name.cyan()
+            );
+        }
 
-        println!();
-        println!("  {} {}", "✓".green(), "Dry run complete".bold());
-        println!("  {}", "No commands were executed.".dimmed());
+        status!();
+        status!("  {} {}", "✓".green(), "Dry run complete".bold());
+        status!("  {}", "No commands were executed.".dimmed());
         return Ok(true);
     }
 
@@ -588,7 +1228,7 @@ pub fn build_project(config: &CxConfig, options: &BuildOptions) -> Result<bool>
     {
         let pch_source = Path::new(pch_str);
         if !pch_source.exists() {
-            println!("{} PCH file not found: {}", "!".yellow(), pch_str);
+            status!("{} PCH file not found: {}", "!".yellow(), pch_str);
         } else {
             let pch_name = pch_source.file_name().unwrap_or_default().to_string_lossy();
 
@@ -606,7 +1246,7 @@ pub fn build_project(config: &CxConfig, options: &BuildOptions) -> Result<bool>
                             .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
 
                 if need_pch {
-                    println!("{} Compiling PCH (MSVC)...", "⚙".cyan());
+                    status!("{} Compiling PCH (MSVC)...", "⚙".cyan());
                     let mut cmd = Command::new(&compiler);
                     cmd.args(["/nologo", "/c", "/EHsc"]);
                     cmd.arg("/Yc"); // Create PCH
@@ -658,7 +1298,7 @@ pub fn build_project(config: &CxConfig, options: &BuildOptions) -> Result<bool>
                             .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
 
                 if need_pch {
-                    println!("{} Compiling PCH (GCC/Clang)...", "⚙".cyan());
+                    status!("{} Compiling PCH (GCC/Clang)...", "⚙".cyan());
 
                     // Handle ccache prefix for PCH? Usually safe.
                     let mut cmd = if let Some(wrapper) = ccache_prefix {
@@ -707,202 +1347,383 @@ pub fn build_project(config: &CxConfig, options: &BuildOptions) -> Result<bool>
         .tick_chars("⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏")
         .progress_chars("█▓░");
 
-    let pb = ProgressBar::new(source_files.len() as u64);
-    pb.set_style(spinner_style);
-    pb.set_message("Compiling...");
-
-    let results: Vec<(PathBuf, serde_json::Value)> = source_files
-        .par_iter()
-        .map(|src_path| -> Result<(PathBuf, serde_json::Value)> {
-            let stem = src_path
-                .file_stem()
-                .unwrap_or(src_path.as_os_str())
-                .to_string_lossy();
-            let obj_ext = if is_msvc { "obj" } else { "o" };
-            let obj_path = obj_dir.join(format!("{}.{}", stem, obj_ext));
+    // Fingerprints of previous builds (argv + toolchain + env)
+    let fingerprints = Mutex::new(FingerprintStore::load(
+        &Path::new(".cx").join("build").join("fingerprints.json"),
+    ));
+    let object_cache = ObjectCache::open();
 
-            // Construct Arguments
-            let mut args = Vec::new();
+    // Compile command for one source (without the ccache wrapper or module flags)
+    let base_args = |src_path: &Path, obj_path: &Path| -> Vec<String> {
+        let mut args = Vec::new();
 
-            // CCache injection
-            if let Some(wrapper) = ccache_prefix {
-                args.push(wrapper.to_string());
-            }
-            args.push(compiler.clone());
+        let is_c = is_c_source(src_path);
+        let edition = if is_c {
+            &c_edition
+        } else {
+            &config.package.edition
+        };
+        args.push(if is_c { &c_compiler } else { &compiler }.clone());
 
-            if is_msvc {
-                // MSVC Flags
-                args.push("/nologo".to_string()); // Suppress copyright
-                args.push("/c".to_string());
+        if is_msvc {
+            // MSVC Flags
+            args.push("/nologo".to_string()); // Suppress copyright
+            args.push("/c".to_string());
+            if !is_c {
                 args.push("/EHsc".to_string()); // Standard C++ exceptions
-                args.push(src_path.to_string_lossy().to_string());
-                args.push(format!("/Fo{}", obj_path.to_string_lossy()));
-                args.push(get_std_flag_msvc(&config.package.edition));
-
-                // Recursive Header Tracking for MSVC
-                // /sourceDependencies <file> available in VS 2019+
-                args.push("/sourceDependencies".to_string());
-                args.push(format!("{}.json", obj_path.display()));
-            } else {
-                // GCC/Clang Flags
-                args.push("-fdiagnostics-color=always".to_string());
-                args.push("-c".to_string());
-                args.push(src_path.to_string_lossy().to_string());
-                args.push("-o".to_string());
-                args.push(obj_path.to_string_lossy().to_string());
-                args.push(get_std_flag_gcc(&config.package.edition));
-
-                // Generate Dependency File
-                args.push("-MMD".to_string());
-                args.push("-MF".to_string());
-                args.push(obj_path.with_extension("d").to_string_lossy().to_string());
             }
+            args.extend(modules::language_args(module_family, src_path));
+            args.push(src_path.to_string_lossy().to_string());
+            args.push(format!("/Fo{}", obj_path.to_string_lossy()));
+            args.push(get_std_flag_msvc(edition));
+
+            // Recursive Header Tracking for MSVC
+            // /sourceDependencies <file> available in VS 2019+
+            args.push("/sourceDependencies".to_string());
+            args.push(format!("{}.json", obj_path.display()));
+        } else {
+            // GCC/Clang Flags
+            args.push("-fdiagnostics-color=always".to_string());
+            args.push("-c".to_string());
+            args.extend(modules::language_args(module_family, src_path));
+            args.push(src_path.to_string_lossy().to_string());
+            args.push("-o".to_string());
+            args.push(obj_path.to_string_lossy().to_string());
+            args.push(get_std_flag_gcc(edition));
+
+            // Generate Dependency File
+            args.push("-MMD".to_string());
+            args.push("-MF".to_string());
+            args.push(obj_path.with_extension("d").to_string_lossy().to_string());
+        }
 
-            if release {
-                if is_msvc {
-                    args.push("/O2".to_string());
-                } else {
-                    args.push("-O3".to_string());
-                }
-            } else if is_msvc {
-                args.push("/Z7".to_string()); // Debug info
-                args.push("/W4".to_string());
-            } else {
-                args.push("-g".to_string());
-                args.push("-Wall".to_string());
-            }
+        args.extend(profile_flags.iter().cloned());
 
-            if let Some(build_cfg) = &config.build
-                && let Some(flags) = &build_cfg.cflags
-            {
-                for flag in flags {
-                    // Translate MSVC-style flags for GCC/Clang
-                    let translated = if !is_msvc && flag.starts_with("/D") {
-                        format!("-D{}", &flag[2..])
-                    } else if !is_msvc && flag.starts_with("/I") {
-                        format!("-I{}", &flag[2..])
-                    } else if is_msvc && flag.starts_with("-D") {
-                        format!("/D{}", &flag[2..])
-                    } else if is_msvc && flag.starts_with("-I") {
-                        format!("/I{}", &flag[2..])
-                    } else {
-                        flag.clone()
-                    };
-                    args.push(translated);
-                }
+        if let Some(build_cfg) = &config.build
+            && let Some(flags) = build_cfg.get_flags()
+        {
+            for flag in flags {
+                // Translate MSVC-style flags for GCC/Clang
+                let translated = if !is_msvc && flag.starts_with("/D") {
+                    format!("-D{}", &flag[2..])
+                } else if !is_msvc && flag.starts_with("/I") {
+                    format!("-I{}", &flag[2..])
+                } else if is_msvc && flag.starts_with("-D") {
+                    format!("/D{}", &flag[2..])
+                } else if is_msvc && flag.starts_with("-I") {
+                    format!("/I{}", &flag[2..])
+                } else {
+                    flag.clone()
+                };
+                args.push(translated);
             }
-            args.extend(common_flags.iter().cloned());
+        }
+        args.extend(common_flags.iter().cloned());
+        // The PCH is built as C++, so C sources can't use it
+        if !is_c {
             args.extend(pch_args.iter().cloned());
+        }
 
-            // Prepare JSON entry for Intellisense
-            let entry = json!({
-                "directory": current_dir_str,
-                "command": args.join(" "),
-                "file": src_path.to_string_lossy()
-            });
+        args
+    };
 
-            // Incremental Check
-            let needs_compile = if !obj_path.exists() {
-                true
-            } else {
-                check_dependencies(&obj_path, src_path).unwrap_or(true)
-            };
+    // C++20 modules: scan what every source provides/imports, then compile in
+    // dependency order so BMIs exist before their importers
+    let module_enabled = match config.build.as_ref().and_then(|b| b.modules) {
+        Some(enabled) => enabled,
+        None => source_files
+            .iter()
+            .any(|src| !is_c_source(src) && modules::uses_modules(src)),
+    };
+    let module_graph = if module_enabled {
+        let graph = scan_modules(
+            &mut source_files,
+            &base_args,
+            module_family,
+            &compiler,
+            &build_dir,
+            is_msvc,
+            &toolchain_env,
+        )?;
+        graph.prepare()?;
+        Some(graph)
+    } else {
+        None
+    };
+    let waves = match &module_graph {
+        Some(graph) => graph.waves(&source_files)?,
+        None => vec![source_files.clone()],
+    };
+    // Objects come back wave by wave; keep sources in the same order
+    let source_files: Vec<PathBuf> = waves.concat();
+    if verbose && waves.len() > 1 {
+        status!(
+            "   {} Modules: {} compile wave(s)",
+            "🧩".blue(),
+            waves.len()
+        );
+    }
 
-            // Profiling Start
-            let compile_start = Instant::now();
-            if needs_compile {
-                pb.set_message(format!("Compiling {}", stem));
-                let mut cmd = Command::new(&args[0]);
-                cmd.args(&args[1..]);
+    let pb = ProgressBar::new(source_files.len() as u64);
+    pb.set_style(spinner_style);
+    pb.set_message("Compiling...");
+    if json_output {
+        pb.set_draw_target(ProgressDrawTarget::hidden());
+    }
 
-                // Apply toolchain environment variables (INCLUDE, LIB, etc.)
-                if !toolchain_env.is_empty() {
-                    cmd.envs(&toolchain_env);
-                }
+    let compile_one = |src_path: &PathBuf| -> Result<(PathBuf, serde_json::Value)> {
+        let stem = src_path
+            .file_stem()
+            .unwrap_or(src_path.as_os_str())
+            .to_string_lossy();
+        let obj_path = get_object_path(&obj_dir, src_path, is_msvc);
+        if let Some(parent) = obj_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
 
-                let output = cmd.output().context("Failed to execute compiler")?;
+        let is_module_unit = module_graph
+            .as_ref()
+            .is_some_and(|graph| graph.is_module_unit(src_path));
+
+        // Construct Arguments (ccache doesn't understand module BMIs)
+        let wrapper = ccache_prefix.filter(|_| !is_module_unit);
+        let mut args = Vec::new();
+        if let Some(wrapper) = wrapper {
+            args.push(wrapper.to_string());
+        }
+        args.extend(base_args(src_path, &obj_path));
+        if let Some(graph) = &module_graph {
+            args.extend(graph.compile_args(src_path));
+        }
 
-                if !output.status.success() {
-                    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-                    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        // Prepare JSON entry for Intellisense
+        let entry = json!({
+            "directory": current_dir_str,
+            "command": args.join(" "),
+            "file": src_path.to_string_lossy()
+        });
+
+        // Incremental Check (the ccache wrapper doesn't affect the output)
+        let compiler_args = if wrapper.is_some() {
+            &args[1..]
+        } else {
+            &args[..]
+        };
+        let fingerprint =
+            fingerprint::compute(compiler_args, &toolchain_fingerprint, &toolchain_env);
+        let is_fresh = fingerprints
+            .lock()
+            .map(|store| store.is_fresh(&obj_path, &fingerprint))
+            .unwrap_or(false);
+        let needs_compile = if !obj_path.exists() || !is_fresh {
+            true
+        } else {
+            check_dependencies(&obj_path, src_path).unwrap_or(true)
+                || module_graph
+                    .as_ref()
+                    .is_some_and(|graph| graph.needs_rebuild(src_path, &obj_path))
+        };
 
+        // Object cache lookup (keyed by the source and its headers, which
+        // doesn't capture imported BMIs)
+        let cache_key = match &object_cache {
+            Some(cache) if needs_compile && !is_module_unit => cache.key(
+                src_path,
+                compiler_args,
+                &toolchain_fingerprint,
+                &toolchain_env,
+            ),
+            _ => None,
+        };
+        let cache_hit = match (&object_cache, &cache_key) {
+            (Some(cache), Some(key)) => cache.fetch(key, &obj_path, is_msvc),
+            _ => false,
+        };
+
+        // Profiling Start
+        let compile_start = Instant::now();
+        if needs_compile && !cache_hit {
+            pb.set_message(format!("Compiling {}", stem));
+            let mut cmd = Command::new(&args[0]);
+            cmd.args(&args[1..]);
+
+            // Apply toolchain environment variables (INCLUDE, LIB, etc.)
+            if !toolchain_env.is_empty() {
+                cmd.envs(&toolchain_env);
+            }
+
+            let output = cmd.output().context("Failed to execute compiler")?;
+
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+                let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+                let diags = diagnostics::parse(&format!("{}{}", stdout, stderr));
+
+                if json_output {
+                    for diag in &diags {
+                        diagnostics::emit_diagnostic(src_path, diag);
+                    }
+                } else {
                     let error_msg = format!(
                         "Error compiling {}:\n{}{}",
                         src_path.display(),
                         stdout,
                         stderr
                     );
-                    pb.println(format!("{} {}", "x".red(), error_msg));
+                    pb.suspend(|| status!("{} {}", "x".red(), error_msg));
 
                     // Educational Feedback
-                    if let Some(suggestion) = super::feedback::FeedbackAnalyzer::analyze(&stderr) {
-                        pb.println(format!(
-                            "\n{} {}\n",
-                            "💡 Suggestion:".bold().yellow(),
-                            suggestion
-                        ));
+                    if let Some(suggestion) = FeedbackAnalyzer::analyze(&diags) {
+                        pb.suspend(|| {
+                            status!("\n{} {}\n", "💡 Suggestion:".bold().yellow(), suggestion)
+                        });
                     }
+                }
 
-                    return Err(anyhow::anyhow!("Compilation failed"));
-                } else {
-                    // Print warnings if any (buffered)
-                    let stderr = String::from_utf8_lossy(&output.stderr);
-                    let stdout = String::from_utf8_lossy(&output.stdout);
-                    if !stderr.is_empty() {
-                        pb.println(format!(
+                return Err(anyhow::anyhow!("Compilation failed"));
+            } else {
+                // Print warnings if any (buffered)
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                if json_output {
+                    for diag in diagnostics::parse(&format!("{}{}", stdout, stderr)) {
+                        diagnostics::emit_diagnostic(src_path, &diag);
+                    }
+                } else if !stderr.is_empty() {
+                    pb.suspend(|| {
+                        status!(
                             "{} Warning in {}:\n{}",
                             "!".yellow(),
                             src_path.display(),
                             stderr
-                        ));
-                    }
-                    // Some compilers print warnings to stdout too
-                    if !stdout.is_empty() {
-                        pb.println(format!(
+                        )
+                    });
+                }
+                // Some compilers print warnings to stdout too (MSVC also
+                // echoes the file name, which would break the JSON stream)
+                if !json_output && !stdout.is_empty() {
+                    pb.suspend(|| {
+                        status!(
                             "{} Output in {}:\n{}",
                             "!".cyan(),
                             src_path.display(),
                             stdout
-                        ));
-                    }
+                        )
+                    });
                 }
-            }
 
-            // Profiling End
-            if let Some(events) = &trace_events {
-                let duration = compile_start.elapsed();
-                let ts = compile_start
-                    .checked_duration_since(build_start_time)
-                    .unwrap_or_default()
-                    .as_micros();
-                let dur = duration.as_micros();
-                // Capture thread ID (best effort)
-                let tid = rayon::current_thread_index().unwrap_or(0);
-
-                if let Ok(mut lock) = events.lock() {
-                    lock.push(TraceEvent {
-                        name: src_path
-                            .file_name()
-                            .unwrap_or_default()
-                            .to_string_lossy()
-                            .to_string(),
-                        cat: "compilation".to_string(),
-                        ph: "X".to_string(),
-                        ts,
-                        dur,
-                        pid: 1,
-                        tid,
-                    });
+                if let (Some(cache), Some(key)) = (&object_cache, &cache_key) {
+                    cache.store(key, &obj_path, is_msvc);
                 }
             }
+        }
+
+        // Profiling End
+        if let Some(events) = &trace_events {
+            let duration = compile_start.elapsed();
+            let ts = compile_start
+                .checked_duration_since(build_start_time)
+                .unwrap_or_default()
+                .as_micros();
+            let dur = duration.as_micros();
+            // Capture thread ID (best effort)
+            let tid = rayon::current_thread_index().unwrap_or(0);
+
+            if let Ok(mut lock) = events.lock() {
+                lock.push(TraceEvent {
+                    name: src_path
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .to_string(),
+                    cat: "compilation".to_string(),
+                    ph: "X".to_string(),
+                    ts,
+                    dur,
+                    pid: 1,
+                    tid,
+                });
+            }
+        }
 
-            pb.inc(1);
-            Ok((obj_path, entry))
-        })
-        .collect::<Result<Vec<_>>>()?; // Collects errors if any
+        if needs_compile && let Ok(mut store) = fingerprints.lock() {
+            store.record(&obj_path, fingerprint);
+        }
+
+        pb.inc(1);
+        Ok((obj_path, entry))
+    };
+
+    // A file can't build once a module it imports failed
+    let depends_on = |src: &Path, other: &Path| {
+        module_graph
+            .as_ref()
+            .is_some_and(|graph| graph.depends_on(src, other))
+    };
+    let results: Result<Vec<(PathBuf, serde_json::Value)>> =
+        match pool.install(|| compile_waves(&waves, keep_going, depends_on, compile_one)) {
+            Ok(compiled) => Ok(compiled),
+            Err(CompileFailure::Error(e)) => Err(e),
+            Err(CompileFailure::Files { failed, skipped }) => {
+                pb.suspend(|| {
+                    status!(
+                        "\n{} {} file(s) failed to compile:",
+                        "x".red(),
+                        failed.len()
+                    );
+                    for src_path in &failed {
+                        status!("   - {}", src_path.display());
+                    }
+                    if !skipped.is_empty() {
+                        status!(
+                            "{} {} file(s) skipped because they import a failed module:",
+                            "!".yellow(),
+                            skipped.len()
+                        );
+                        for src_path in &skipped {
+                            status!("   - {}", src_path.display());
+                        }
+                    }
+                });
+                Err(anyhow::anyhow!(
+                    "Compilation failed ({} of {} files)",
+                    failed.len(),
+                    source_files.len()
+                ))
+            }
+        };
+
+    // Keep fingerprints of the objects that did compile, even if others failed
+    let mut fingerprints = fingerprints.into_inner().unwrap_or_else(|e| e.into_inner());
+    fingerprints.save()?;
+    let results = results?; // Collects errors if any
 
     pb.finish_with_message("Compilation complete");
 
+    // Keep the object cache within its size cap once enough new objects went in
+    if let Some(cache) = &object_cache {
+        let evicted = cache.prune_if_due(super::object_cache::max_size());
+        if verbose && evicted > 0 {
+            status!(
+                "   {} Object cache: evicted {} least recently used object(s)",
+                "📦".blue(),
+                evicted
+            );
+        }
+    }
+
+    if verbose
+        && let Some(cache) = &object_cache
+        && cache.hits() + cache.misses() > 0
+    {
+        status!(
+            "   {} Object cache: {} hit(s), {} miss(es)",
+            "📦".blue(),
+            cache.hits().to_string().green(),
+            cache.misses().to_string().yellow()
+        );
+    }
+
     // Profiling Dump
     if let Some(events) = trace_events
         && let Ok(locked) = events.lock()
@@ -913,7 +1734,7 @@ pub fn build_project(config: &CxConfig, options: &BuildOptions) -> Result<bool>
             fs::create_dir_all(parent)?;
         }
         fs::write(&trace_path, json)?;
-        println!(
+        status!(
             "   {} Build trace saved to {} (Chrome Tracing)",
             "📊".blue(),
             trace_path.display()
@@ -931,183 +1752,302 @@ pub fn build_project(config: &CxConfig, options: &BuildOptions) -> Result<bool>
         fs::create_dir_all(parent)?;
     }
     fs::write(&compile_commands_path, json_str)?;
-
-    // 7. Linking
-    let mut needs_link = !output_bin.exists();
-    if !needs_link {
-        let bin_time = fs::metadata(&output_bin)?.modified()?;
-        for obj in &object_files {
-            if fs::metadata(obj)?.modified()? > bin_time {
+    // `cx test` links exactly these, not whatever else is left in obj/
+    fs::write(
+        build_dir.join(OBJECT_LIST),
+        serde_json::to_string_pretty(&object_files)?,
+    )?;
+
+    // 7. Linking (one artifact per target)
+    let link_targets = plan_link_targets(
+        &source_files,
+        &object_files,
+        &bin_outputs,
+        &output_bin,
+        kind,
+    );
+    // Check if we have MSVC .lib files in dependencies (requires MSVC-compatible linker)
+    let has_msvc_libs = dep_libs.iter().any(|lib| lib.ends_with(".lib"));
+    let is_windows = target_os == TargetOs::Windows;
+    let is_mingw_clang = !is_msvc && is_windows && compiler.contains("clang");
+
+    // Use clang-cl if we have MinGW clang but need to link MSVC libs
+    let effective_compiler = if is_mingw_clang && has_msvc_libs {
+        "clang-cl".to_string()
+    } else {
+        link_compiler.clone()
+    };
+    let use_clang_cl = effective_compiler == "clang-cl";
+
+    let mut linked_any = false;
+
+    for target in &link_targets {
+        let mut needs_link = !target.output.exists();
+        if !needs_link {
+            let bin_time = fs::metadata(&target.output)?.modified()?;
+            for obj in &target.objects {
+                if fs::metadata(obj)?.modified()? > bin_time {
+                    needs_link = true;
+                    break;
+                }
+            }
+            // Libraries built from dependencies (e.g. path dependencies)
+            if target.kind != ArtifactKind::Staticlib
+                && dep_libs.iter().any(|lib| {
+                    fs::metadata(lib)
+                        .and_then(|m| m.modified())
+                        .is_ok_and(|t| t > bin_time)
+                })
+            {
                 needs_link = true;
-                break;
             }
         }
-    }
 
-    if needs_link {
-        println!("   {} Linking...", "🔗".cyan());
+        let target_name = target
+            .output
+            .file_name()
+            .unwrap_or(target.output.as_os_str())
+            .to_string_lossy();
+        let is_archive = target.kind == ArtifactKind::Staticlib;
 
-        // Check if we have MSVC .lib files in dependencies (requires MSVC-compatible linker)
-        let has_msvc_libs = dep_libs.iter().any(|lib| lib.ends_with(".lib"));
-        let is_windows = cfg!(target_os = "windows");
-        let is_mingw_clang = !is_msvc && is_windows && compiler.contains("clang");
+        let (mut cmd, tool) = if is_archive {
+            let archiver = get_archiver(&compiler, is_msvc);
+            let mut cmd = Command::new(&archiver);
 
-        // Use clang-cl if we have MinGW clang but need to link MSVC libs
-        let effective_compiler = if is_mingw_clang && has_msvc_libs {
-            println!(
-                "   {} Using clang-cl for MSVC library compatibility",
-                "⚡".yellow()
-            );
-            "clang-cl".to_string()
+            if is_msvc {
+                cmd.arg("/NOLOGO");
+                cmd.arg(format!("/OUT:{}", target.output.to_string_lossy()));
+            } else {
+                cmd.arg("rcs").arg(&target.output);
+            }
+            cmd.args(&target.objects);
+            (cmd, archiver)
         } else {
-            compiler.clone()
-        };
-        let use_clang_cl = effective_compiler == "clang-cl";
+            let mut cmd = Command::new(&effective_compiler);
 
-        let mut cmd = Command::new(&effective_compiler);
+            if target.kind == ArtifactKind::Sharedlib {
+                if is_msvc || use_clang_cl {
+                    cmd.arg("/LD");
+                } else {
+                    cmd.arg("-shared");
+                }
+            }
+            cmd.args(&target_args);
 
-        // Link Flags for LTO
-        if lto {
-            if is_msvc {
-                cmd.arg("/LTCG");
-            } else {
-                cmd.arg("-flto");
+            // Link Flags for LTO
+            if lto {
+                if is_msvc {
+                    cmd.arg("/LTCG");
+                } else {
+                    cmd.arg("-flto");
+                }
             }
-        }
 
-        // Link Flags for Sanitizers
-        if let Some(checks) = &sanitize
-            && !is_msvc
-        {
-            cmd.arg(format!("-fsanitize={}", checks));
-        }
+            // Link Flags for Sanitizers
+            if let Some(checks) = &sanitize
+                && !is_msvc
+            {
+                cmd.arg(format!("-fsanitize={}", checks));
+            }
 
-        cmd.args(&object_files);
-
-        // Add include paths for source files in dep_libs (e.g., GLAD's gl.c)
-        // When .c/.cpp files are passed to the linker, MSVC compiles them on the fly
-        // and needs include paths to find headers like <glad/gl.h>
-        let has_source_files = dep_libs.iter().any(|lib| {
-            let lower = lib.to_lowercase();
-            lower.ends_with(".c")
-                || lower.ends_with(".cpp")
-                || lower.ends_with(".cc")
-                || lower.ends_with(".cxx")
-        });
+            cmd.args(&target.objects);
+
+            // Add include paths for source files in dep_libs (e.g., GLAD's gl.c)
+            // When .c/.cpp files are passed to the linker, MSVC compiles them on the fly
+            // and needs include paths to find headers like <glad/gl.h>
+            let has_source_files = dep_libs.iter().any(|lib| {
+                let lower = lib.to_lowercase();
+                lower.ends_with(".c")
+                    || lower.ends_with(".cpp")
+                    || lower.ends_with(".cc")
+                    || lower.ends_with(".cxx")
+            });
 
-        if has_source_files {
-            for path in &include_paths {
-                if is_msvc || use_clang_cl {
-                    cmd.arg(format!("/I{}", path.display()));
-                } else {
-                    cmd.arg(format!("-I{}", path.display()));
+            if has_source_files {
+                for path in &include_paths {
+                    if is_msvc || use_clang_cl {
+                        cmd.arg(format!("/I{}", path.display()));
+                    } else {
+                        cmd.arg(format!("-I{}", path.display()));
+                    }
                 }
+                cmd.args(&extra_cflags);
             }
-            cmd.args(&extra_cflags);
-        }
 
-        if is_msvc || use_clang_cl {
-            // Use to_string_lossy and quote the path to handle spaces and special chars
-            let output_path = output_bin.to_string_lossy();
-            cmd.arg(format!("/Fe:{}", output_path));
-            cmd.arg(format!("/Fo:{}", obj_dir.to_string_lossy()));
-        } else {
-            cmd.arg("-o").arg(&output_bin);
-        }
+            if is_msvc || use_clang_cl {
+                // Use to_string_lossy and quote the path to handle spaces and special chars
+                let output_path = target.output.to_string_lossy();
+                cmd.arg(format!("/Fe:{}", output_path));
+                cmd.arg(format!("/Fo:{}", obj_dir.to_string_lossy()));
+            } else {
+                cmd.arg("-o").arg(&target.output);
+            }
 
-        for lib in &dep_libs {
-            cmd.arg(lib);
-        }
+            for lib in &dep_libs {
+                cmd.arg(lib);
+            }
 
-        // Extract library search paths from dep_libs for user-specified libs
-        // This allows libs = ["glfw3"] to find glfw3.lib in dependency directories
-        let mut lib_search_paths = std::collections::HashSet::new();
-        for lib in &dep_libs {
-            let lib_path = Path::new(lib);
-            if lib_path
-                .extension()
-                .map(|e| e == "lib" || e == "a")
-                .unwrap_or(false)
-                && let Some(parent) = lib_path.parent()
-            {
-                lib_search_paths.insert(parent.to_path_buf());
+            // Extract library search paths from dep_libs for user-specified libs
+            // This allows libs = ["glfw3"] to find glfw3.lib in dependency directories
+            let mut lib_search_paths: std::collections::BTreeSet<PathBuf> =
+                dep_link_dirs.iter().cloned().collect();
+            for lib in &dep_libs {
+                let lib_path = Path::new(lib);
+                if lib_path
+                    .extension()
+                    .map(|e| e == "lib" || e == "a")
+                    .unwrap_or(false)
+                    && let Some(parent) = lib_path.parent()
+                {
+                    lib_search_paths.insert(parent.to_path_buf());
+                }
             }
-        }
 
-        // For GCC/Clang, add -L flags before the libs
-        if !is_msvc && !use_clang_cl {
-            for search_path in &lib_search_paths {
-                cmd.arg(format!("-L{}", search_path.display()));
+            // For GCC/Clang, add -L flags before the libs
+            if !is_msvc && !use_clang_cl {
+                for search_path in &lib_search_paths {
+                    cmd.arg(format!("-L{}", search_path.display()));
+                }
             }
-        }
 
-        if let Some(build_cfg) = &config.build
-            && let Some(libs) = &build_cfg.libs
-        {
-            for lib in libs {
+            // Project libs, then those required by caxe-project dependencies
+            let project_libs = config.build.as_ref().and_then(|b| b.libs.as_ref());
+            for lib in project_libs.into_iter().flatten().chain(&dep_lib_names) {
                 if is_msvc || use_clang_cl {
                     cmd.arg(format!("{}.lib", lib));
                 } else {
                     cmd.arg(format!("-l{}", lib));
                 }
             }
-        }
 
-        // For MSVC, pass /LIBPATH: flags via /link at the end
-        // Also ensure dynamic CRT (/MD) for compatibility with prebuilt libs like GLFW
-        if (is_msvc || use_clang_cl) && !lib_search_paths.is_empty() {
-            cmd.arg("/MD"); // Use dynamic CRT to match prebuilt dependencies
-            cmd.arg("/link");
-            for search_path in &lib_search_paths {
-                cmd.arg(format!("/LIBPATH:{}", search_path.display()));
+            // For MSVC, pass /LIBPATH: flags via /link at the end
+            // Also ensure dynamic CRT (/MD) for compatibility with prebuilt libs like GLFW
+            let msvc_link_section = (is_msvc || use_clang_cl) && !lib_search_paths.is_empty();
+            if msvc_link_section {
+                cmd.arg("/MD"); // Use dynamic CRT to match prebuilt dependencies
+                cmd.arg("/link");
+                for search_path in &lib_search_paths {
+                    cmd.arg(format!("/LIBPATH:{}", search_path.display()));
+                }
+                // Add subsystem flag if specified (e.g., for SDL2 with SDL2main.lib)
+                if let Some(build_cfg) = &config.build
+                    && let Some(subsystem) = &build_cfg.subsystem
+                {
+                    let subsystem_flag = match subsystem.to_lowercase().as_str() {
+                        "windows" => "/SUBSYSTEM:WINDOWS",
+                        "console" => "/SUBSYSTEM:CONSOLE",
+                        _ => "/SUBSYSTEM:CONSOLE",
+                    };
+                    cmd.arg(subsystem_flag);
+                }
             }
-            // Add subsystem flag if specified (e.g., for SDL2 with SDL2main.lib)
+
+            // [build] / profile ldflags (MSVC linker options go after /link)
             if let Some(build_cfg) = &config.build
-                && let Some(subsystem) = &build_cfg.subsystem
+                && let Some(ldflags) = &build_cfg.ldflags
+                && !ldflags.is_empty()
             {
-                let subsystem_flag = match subsystem.to_lowercase().as_str() {
-                    "windows" => "/SUBSYSTEM:WINDOWS",
-                    "console" => "/SUBSYSTEM:CONSOLE",
-                    _ => "/SUBSYSTEM:CONSOLE",
-                };
-                cmd.arg(subsystem_flag);
+                if (is_msvc || use_clang_cl) && !msvc_link_section {
+                    cmd.arg("/link");
+                }
+                cmd.args(ldflags);
             }
-        }
+
+            (cmd, effective_compiler.clone())
+        };
 
         // Apply toolchain environment variables (LIB, LIBPATH, etc.)
         if !toolchain_env.is_empty() {
             cmd.envs(&toolchain_env);
         }
 
-        let output = cmd.output()?;
+        // Relink when the command line or toolchain changed, not only the objects
+        let link_args: Vec<String> = std::iter::once(cmd.get_program())
+            .chain(cmd.get_args())
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect();
+        let link_fingerprint =
+            fingerprint::compute(&link_args, &toolchain_fingerprint, &toolchain_env);
+        if !fingerprints.is_fresh(&target.output, &link_fingerprint) {
+            needs_link = true;
+        }
+
+        if !needs_link {
+            if json_output {
+                diagnostics::emit_artifact(target.kind, &target.output, true);
+            }
+            continue;
+        }
+        linked_any = true;
+
+        if is_archive {
+            status!("   {} Archiving {}...", "📚".cyan(), target_name);
+            // `ar r` replaces members in place, so start fresh to drop stale objects
+            if !is_msvc && target.output.exists() {
+                fs::remove_file(&target.output)?;
+            }
+        } else {
+            status!("   {} Linking {}...", "🔗".cyan(), target_name);
+            if use_clang_cl {
+                status!(
+                    "   {} Using clang-cl for MSVC library compatibility",
+                    "⚡".yellow()
+                );
+            }
+        }
+
+        let output = cmd
+            .output()
+            .with_context(|| format!("Failed to execute '{}'", tool))?;
         if !output.status.success() {
-            println!("{}", String::from_utf8_lossy(&output.stdout));
+            let stdout = String::from_utf8_lossy(&output.stdout);
             let stderr = String::from_utf8_lossy(&output.stderr);
-            println!("{}", stderr);
-            println!("{} Linking failed", "x".red());
+            let diags = diagnostics::parse(&format!("{}{}", stdout, stderr));
 
-            if let Some(suggestion) = super::feedback::FeedbackAnalyzer::analyze(&stderr) {
-                println!("\n{} {}\n", "💡 Suggestion:".bold().yellow(), suggestion);
+            if json_output {
+                for diag in &diags {
+                    diagnostics::emit_diagnostic(&target.output, diag);
+                }
+            } else {
+                status!("{}", stdout);
+                status!("{}", stderr);
+            }
+
+            if is_archive {
+                status!("{} Archiving failed", "x".red());
+            } else {
+                status!("{} Linking failed", "x".red());
+                if !json_output && let Some(suggestion) = FeedbackAnalyzer::analyze(&diags) {
+                    status!("\n{} {}\n", "💡 Suggestion:".bold().yellow(), suggestion);
+                }
             }
 
             return Ok(false);
         }
 
+        fingerprints.record(&target.output, link_fingerprint);
+        fingerprints.save()?;
+        if json_output {
+            diagnostics::emit_artifact(target.kind, &target.output, false);
+        }
+    }
+
+    if linked_any {
         // 8. Post-build Script
         if let Some(scripts) = &config.scripts
             && let Some(post) = &scripts.post_build
             && let Err(e) = run_script(post, &current_dir)
         {
-            println!("{} Post-build script failed: {}", "x".red(), e);
+            status!("{} Post-build script failed: {}", "x".red(), e);
         }
 
-        println!(
+        status!(
             "{} Build finished in {:.2?}",
             "✓".green(),
             start_time.elapsed()
         );
     } else {
-        println!("{} Up to date", "⚡".green());
+        status!("{} Up to date", "⚡".green());
     }
 
     Ok(true)
@@ -1118,6 +2058,7 @@ pub fn build_and_run(
     release: bool,
     verbose: bool,
     dry_run: bool,
+    bin: Option<String>,
     run_args: Vec<String>,
     script_path: Option<String>,
 ) -> Result<()> {
@@ -1159,6 +2100,7 @@ pub fn build_and_run(
                     eprintln!("{} Using cx.toml settings for script build", "📦".cyan());
                 }
                 // Override sources to build only this file, but keep everything else
+                project_cfg.bins = None;
                 if let Some(build_cfg) = &mut project_cfg.build {
                     build_cfg.sources = Some(vec![final_path.to_string_lossy().to_string()]);
                     // Use file stem as binary name for script builds
@@ -1277,10 +2219,43 @@ pub fn build_and_run(
         run_args
     };
 
+    if let Some(build_cfg) = &config.build
+        && build_cfg.artifact_kind().is_library()
+    {
+        anyhow::bail!(
+            "Cannot run a library (kind = \"{}\"). Use 'cx build' instead.",
+            build_cfg.artifact_kind().as_str()
+        );
+    }
+
+    let package_bin = if let Some(build_cfg) = &config.build {
+        build_cfg.bin.clone().unwrap_or(config.package.name.clone())
+    } else {
+        config.package.name.clone()
+    };
+
+    // Pick which executable to run when the package declares [[bin]] targets
+    let bin_targets = resolve_bin_targets(&config, &package_bin);
+    let names: Vec<&str> = bin_targets.iter().map(|(n, _)| n.as_str()).collect();
+    let selected_bin = match bin {
+        // Single-binary packages accept their own name
+        Some(name) if bin_targets.is_empty() && name == package_bin => None,
+        // Unknown names are reported by build_project
+        Some(name) => Some(name),
+        None if bin_targets.len() == 1 => Some(names[0].to_string()),
+        None if names.contains(&package_bin.as_str()) => Some(package_bin.clone()),
+        None if bin_targets.is_empty() => None,
+        None => anyhow::bail!(
+            "Multiple binaries available: {}. Use 'cx run --bin <name>' to pick one.",
+            names.join(", ")
+        ),
+    };
+
     let options = BuildOptions {
         release,
         verbose,
         dry_run,
+        bin: selected_bin.clone(),
         ..Default::default()
     };
 
@@ -1289,61 +2264,32 @@ pub fn build_and_run(
         return Ok(());
     }
 
-    // In dry-run mode, don't actually run
-    if dry_run {
-        println!("\n{}", "Run:".bold());
-        let profile = if release { "release" } else { "debug" };
-        let bin_basename = if let Some(build_cfg) = &config.build {
-            build_cfg.bin.clone().unwrap_or(config.package.name.clone())
-        } else {
-            config.package.name.clone()
-        };
-        let bin_name = if cfg!(target_os = "windows") {
-            format!("{}.exe", bin_basename)
-        } else {
-            bin_basename
-        };
-        let bin_path = Path::new("build").join(profile).join(&bin_name);
+    let bin_basename = selected_bin.unwrap_or(package_bin);
 
-        // If script mode and 'src/' lookup happened, path might be tricky for bin name logic?
-        // Ephemeral config uses file stem as bin name, so it should be fine.
+    let bin_name = get_artifact_name(&bin_basename, ArtifactKind::Bin, false, TargetOs::host());
+    let profile = profile_name(options.profile.as_deref(), release);
+    let bin_path = profile_build_dir(profile, options.target.as_deref()).join(bin_name);
 
-        let bin_short = bin_path
-            .file_name()
-            .unwrap_or(bin_path.as_os_str())
-            .to_string_lossy();
+    // In dry-run mode, don't actually run
+    if dry_run {
+        status!("\n{}", "Run:".bold());
         let args_str = if run_args.is_empty() {
             String::new()
         } else {
             format!(" {}", run_args.join(" "))
         };
-        println!("  → {}{}", bin_short.cyan(), args_str);
+        status!("  → {}{}", bin_path.display().to_string().cyan(), args_str);
         return Ok(());
     }
 
-    let profile = if release { "release" } else { "debug" };
-    let bin_basename = if let Some(build_cfg) = &config.build {
-        build_cfg.bin.clone().unwrap_or(config.package.name.clone())
-    } else {
-        config.package.name.clone()
-    };
-
-    let bin_name = if cfg!(target_os = "windows") {
-        format!("{}.exe", bin_basename)
-    } else {
-        bin_basename
-    };
-
-    let bin_path = Path::new(".cx").join("build").join(profile).join(bin_name);
-
     if !bin_path.exists() {
         anyhow::bail!("Binary not found at {}", bin_path.display());
     }
 
     if verbose {
-        println!("{} Running: {}\n", "🚀".green(), bin_path.display());
+        status!("{} Running: {}\n", "🚀".green(), bin_path.display());
     } else {
-        println!("{} Running...\n", "▶".green());
+        status!("{} Running...\n", "▶".green());
     }
 
     let mut run_cmd = Command::new(bin_path);
@@ -1358,3 +2304,92 @@ pub fn build_and_run(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_jobs_priority() {
+        let config: CxConfig =
+            toml::from_str("[package]\nname = \"app\"\nversion = \"0.1.0\"\n[build]\njobs = 2\n")
+                .unwrap();
+        assert_eq!(jobs_from(Some(8), Some("4"), &config).unwrap(), Some(8));
+        assert_eq!(jobs_from(None, Some(" 4 "), &config).unwrap(), Some(4));
+        assert_eq!(jobs_from(None, None, &config).unwrap(), Some(2));
+        assert_eq!(jobs_from(None, Some(""), &config).unwrap(), Some(2));
+        assert_eq!(jobs_from(None, None, &CxConfig::default()).unwrap(), None);
+
+        // Zero means one job per core; garbage is an error
+        assert_eq!(jobs_from(None, Some("0"), &config).unwrap(), None);
+        assert_eq!(jobs_from(Some(0), None, &config).unwrap(), None);
+        let err = jobs_from(None, Some("many"), &config).unwrap_err();
+        assert!(err.to_string().contains("Invalid CX_JOBS value 'many'"));
+        assert!(jobs_from(None, Some("-1"), &config).is_err());
+    }
+
+    #[test]
+    fn test_profile_build_dir() {
+        let build = Path::new(".cx").join("build");
+        assert_eq!(
+            profile_build_dir(profile_name(None, false), None),
+            build.join("debug")
+        );
+        assert_eq!(
+            profile_build_dir(profile_name(Some("esp32"), true), Some("esp32-s3")),
+            build.join("esp32-s3").join("esp32")
+        );
+    }
+
+    #[test]
+    fn test_keep_going_collects_every_failure() {
+        let waves: Vec<Vec<PathBuf>> = vec![
+            ["a.cpp", "bad1.cpp", "b.cpp", "bad2.cpp"]
+                .iter()
+                .map(PathBuf::from)
+                .collect(),
+            vec![PathBuf::from("c.cpp"), PathBuf::from("uses_bad1.cpp")],
+            vec![PathBuf::from("uses_uses_bad1.cpp")],
+        ];
+        // uses_bad1 imports bad1's module, and uses_uses_bad1 imports uses_bad1's
+        let depends_on = |src: &Path, other: &Path| {
+            let (src, other) = (src.to_string_lossy(), other.to_string_lossy());
+            src.strip_prefix("uses_").is_some_and(|s| s == other)
+        };
+        let attempted = AtomicUsize::new(0);
+        let compile = |src: &PathBuf| -> Result<String> {
+            attempted.fetch_add(1, Ordering::Relaxed);
+            if src.to_string_lossy().starts_with("bad") {
+                anyhow::bail!("error in {}", src.display());
+            }
+            Ok(src.to_string_lossy().to_string())
+        };
+
+        // Independent files of later waves still compile; importers are skipped
+        match compile_waves(&waves, true, depends_on, compile) {
+            Err(CompileFailure::Files { failed, skipped }) => {
+                assert_eq!(failed, vec![&waves[0][1], &waves[0][3]]);
+                assert_eq!(skipped, vec![&waves[1][1], &waves[2][0]]);
+            }
+            _ => panic!("expected every failure"),
+        }
+        assert_eq!(attempted.load(Ordering::Relaxed), 5);
+
+        // Without --keep-going the first error is returned
+        assert!(matches!(
+            compile_waves(&waves, false, depends_on, compile),
+            Err(CompileFailure::Error(_))
+        ));
+
+        let ok = &waves[1..];
+        assert_eq!(
+            compile_waves(ok, true, depends_on, compile).ok(),
+            Some(vec![
+                "c.cpp".to_string(),
+                "uses_bad1.cpp".to_string(),
+                "uses_uses_bad1.cpp".to_string()
+            ])
+        );
+    }
+}
@@ -0,0 +1,399 @@
+//! Static libraries built from dependency sources.
+//!
+//! Dependencies with sources (see `deps::library`) are compiled with the
+//! project's toolchain into `~/.cx/cache/libs/<name>-<key>/`, where the key
+//! hashes the dependency's tree and sources, the compile flags, the toolchain
+//! fingerprint and the profile. Every project building the same revision the
+//! same way shares one library, and objects are only recompiled when a source
+//! or one of its headers changes.
+//!
+//! Dependencies with their own build system are handed to `external` first,
+//! as sources may include their headers.
+
+use super::core::{check_dependencies, resolve_jobs};
+use super::external;
+use super::fingerprint;
+use super::utils::{
+    get_archiver, get_artifact_name, get_object_path, get_profile_flags, get_std_flag_gcc,
+    get_std_flag_msvc,
+};
+use crate::config::{ArtifactKind, CxConfig, Profile};
+use crate::deps::{ExternalBuild, ResolvedDeps, SourceLib};
+use crate::status;
+use crate::toolchain::c_compiler_for;
+use crate::toolchain::cross::TargetOs;
+use anyhow::{Context, Result, bail};
+use colored::*;
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// How dependency libraries are compiled
+pub struct LibToolchain<'a> {
+    pub compiler: &'a str,
+    pub c_compiler: &'a str,
+    pub is_msvc: bool,
+    /// Toolchain fingerprint, part of the cache key
+    pub fingerprint: &'a str,
+    /// Variables injected into the compiler process (e.g. MSVC's INCLUDE/LIB)
+    pub env: &'a HashMap<String, String>,
+    /// Profile name (`debug`, `release`, `esp32`, ...), part of the cache key
+    pub profile: &'a str,
+    /// Optimized build (the build type given to CMake)
+    pub release: bool,
+    /// Parallel jobs for external builds (`--jobs`, or one per core)
+    pub jobs: usize,
+    /// Optimization, debug info and define flags
+    pub flags: Vec<String>,
+    /// `--target=`/`--sysroot=` and `[targets.<name>]` flags
+    pub target_flags: Vec<String>,
+    /// Target triple when cross-compiling
+    pub triple: Option<&'a str>,
+    /// The project's standards, for dependencies without their own cx.toml
+    pub edition: &'a str,
+    pub c_edition: &'a str,
+    pub target_os: TargetOs,
+}
+
+/// What the project compiles and links against for its built dependencies
+#[derive(Debug, Default)]
+pub struct Built {
+    pub include_dirs: Vec<PathBuf>,
+    pub cflags: Vec<String>,
+    /// Library files, in link order
+    pub link_flags: Vec<String>,
+    /// System libraries they need (`m`, `pthread`), linked by name
+    pub libs: Vec<String>,
+}
+
+/// Build every dependency in `externals` and `libs` (if not cached already).
+/// `include_paths` and `cflags` are those of the whole dependency graph, as
+/// sources often include their dependencies' headers.
+pub fn build_all(
+    externals: &[ExternalBuild],
+    libs: &[SourceLib],
+    include_paths: &[PathBuf],
+    cflags: &[String],
+    tc: &LibToolchain,
+) -> Result<Built> {
+    let mut built = Built::default();
+    for ext in externals {
+        let installed = external::build(ext, tc)?;
+        built.include_dirs.extend(installed.include_dirs);
+        built.cflags.extend(installed.cflags);
+        built.link_flags.extend(installed.link_flags);
+        built.libs.extend(installed.libs);
+    }
+
+    let include_paths: Vec<PathBuf> = include_paths
+        .iter()
+        .chain(&built.include_dirs)
+        .cloned()
+        .collect();
+    let cflags: Vec<String> = cflags.iter().chain(&built.cflags).cloned().collect();
+    let mut outputs = Vec::new();
+    for lib in libs {
+        let output = build_lib(lib, &include_paths, &cflags, tc)?;
+        outputs.push(output.to_string_lossy().to_string());
+    }
+    // Before the libraries they may depend on
+    built.link_flags.splice(0..0, outputs);
+    Ok(built)
+}
+
+/// Build the dependencies in `resolved` for `cx test`, as a host debug
+/// build would
+pub fn build_for_tests(config: &CxConfig, resolved: &ResolvedDeps) -> Result<Built> {
+    let toolchain = super::utils::get_toolchain(config, true).ok();
+    let (compiler, c_compiler, fingerprint, env) = match &toolchain {
+        Some(tc) => (
+            tc.cxx_path.to_string_lossy().to_string(),
+            tc.get_cc_compiler().to_string_lossy().to_string(),
+            tc.fingerprint(),
+            tc.env_vars.clone(),
+        ),
+        None => {
+            let compiler = super::utils::get_compiler(config, true);
+            let c_compiler = c_compiler_for(Path::new(&compiler))
+                .to_string_lossy()
+                .to_string();
+            (compiler.clone(), c_compiler, compiler, HashMap::new())
+        }
+    };
+    let is_msvc = compiler.contains("cl.exe") || compiler == "cl";
+    let target_os = TargetOs::host();
+
+    let mut flags: Vec<String> = get_profile_flags(&Profile::default(), false, is_msvc)
+        .into_iter()
+        .filter(|f| !["-Wall", "/W4"].contains(&f.as_str()))
+        .collect();
+    if !is_msvc && target_os != TargetOs::Windows {
+        flags.push("-fPIC".to_string());
+    }
+    let tc = LibToolchain {
+        compiler: &compiler,
+        c_compiler: &c_compiler,
+        is_msvc,
+        fingerprint: &fingerprint,
+        env: &env,
+        profile: "debug",
+        release: false,
+        jobs: resolve_jobs(None, config)?.unwrap_or_else(rayon::current_num_threads),
+        flags,
+        target_flags: Vec::new(),
+        triple: None,
+        edition: &config.package.edition,
+        c_edition: &config.package.c_edition(),
+        target_os,
+    };
+    build_all(
+        &resolved.external,
+        &resolved.sources,
+        &resolved.include_paths,
+        &resolved.cflags,
+        &tc,
+    )
+}
+
+/// Compile command for one source of `lib`
+fn compile_args(
+    lib: &SourceLib,
+    src: &Path,
+    obj: &Path,
+    include_paths: &[PathBuf],
+    cflags: &[String],
+    tc: &LibToolchain,
+) -> Vec<String> {
+    let is_c = src.extension().is_some_and(|e| e == "c");
+    let edition = if is_c {
+        lib.c_edition.as_deref().unwrap_or(tc.c_edition)
+    } else {
+        lib.edition.as_deref().unwrap_or(tc.edition)
+    };
+    let include = if tc.is_msvc { "/I" } else { "-I" };
+
+    let mut args = vec![if is_c { tc.c_compiler } else { tc.compiler }.to_string()];
+    if tc.is_msvc {
+        args.extend(["/nologo", "/c", "/w"].map(String::from));
+        if !is_c {
+            args.push("/EHsc".to_string());
+        }
+        args.push(src.to_string_lossy().to_string());
+        args.push(format!("/Fo{}", obj.display()));
+        args.push(get_std_flag_msvc(edition));
+        args.push("/sourceDependencies".to_string());
+        args.push(format!("{}.json", obj.display()));
+    } else {
+        // Third-party warnings are not the project's to fix
+        args.extend(["-c", "-w"].map(String::from));
+        args.push(src.to_string_lossy().to_string());
+        args.push("-o".to_string());
+        args.push(obj.to_string_lossy().to_string());
+        args.push(get_std_flag_gcc(edition));
+        args.extend(["-MMD", "-MF"].map(String::from));
+        args.push(obj.with_extension("d").to_string_lossy().to_string());
+    }
+    args.extend(tc.flags.iter().cloned());
+    args.extend(tc.target_flags.iter().cloned());
+    args.push(format!("{}{}", include, lib.dir.display()));
+    for path in include_paths {
+        args.push(format!("{}{}", include, path.display()));
+    }
+    args.extend(cflags.iter().cloned());
+    args
+}
+
+/// Cache key of `lib`: its sources and their compile commands, the
+/// toolchain and the profile
+fn cache_key(
+    lib: &SourceLib,
+    include_paths: &[PathBuf],
+    cflags: &[String],
+    tc: &LibToolchain,
+) -> String {
+    let mut key_args = vec![tc.profile.to_string()];
+    for src in &lib.sources {
+        key_args.extend(compile_args(
+            lib,
+            src,
+            Path::new(""),
+            include_paths,
+            cflags,
+            tc,
+        ));
+    }
+    fingerprint::compute(&key_args, tc.fingerprint, tc.env)
+}
+
+fn build_lib(
+    lib: &SourceLib,
+    include_paths: &[PathBuf],
+    cflags: &[String],
+    tc: &LibToolchain,
+) -> Result<PathBuf> {
+    let key = cache_key(lib, include_paths, cflags, tc);
+    let out_dir = crate::cache::lib_dir(&lib.name, &key)?;
+    let _lock = crate::cache::lock_entry(&out_dir)?;
+    let obj_dir = out_dir.join("obj");
+    let output = out_dir.join(get_artifact_name(
+        &lib.name,
+        ArtifactKind::Staticlib,
+        tc.is_msvc,
+        tc.target_os,
+    ));
+
+    let objects: Vec<(&PathBuf, PathBuf)> = lib
+        .sources
+        .iter()
+        .map(|src| {
+            let rel = src.strip_prefix(&lib.dir).unwrap_or(src);
+            (src, get_object_path(&obj_dir, rel, tc.is_msvc))
+        })
+        .collect();
+    let stale: Vec<&(&PathBuf, PathBuf)> = objects
+        .iter()
+        .filter(|(src, obj)| !obj.exists() || check_dependencies(obj, src).unwrap_or(true))
+        .collect();
+    if stale.is_empty() && output.exists() {
+        return Ok(output);
+    }
+
+    status!(
+        "   {} Compiling {} ({} file(s))...",
+        "🔨".yellow(),
+        lib.name,
+        stale.len()
+    );
+    let failures: Vec<String> = stale
+        .par_iter()
+        .filter_map(|(src, obj)| {
+            if let Some(parent) = obj.parent() {
+                let _ = fs::create_dir_all(parent);
+            }
+            let args = compile_args(lib, src, obj, include_paths, cflags, tc);
+            let result = Command::new(&args[0])
+                .args(&args[1..])
+                .envs(tc.env)
+                .output();
+            match result {
+                Ok(out) if out.status.success() => None,
+                Ok(out) => {
+                    let _ = fs::remove_file(obj);
+                    Some(format!(
+                        "{}{}{}",
+                        src.display(),
+                        String::from_utf8_lossy(&out.stdout),
+                        String::from_utf8_lossy(&out.stderr)
+                    ))
+                }
+                Err(e) => Some(format!(
+                    "{}: failed to run {}: {}",
+                    src.display(),
+                    args[0],
+                    e
+                )),
+            }
+        })
+        .collect();
+    if !failures.is_empty() {
+        for failure in &failures {
+            status!("{}", failure);
+        }
+        bail!(
+            "Failed to compile dependency '{}' ({} of {} file(s) failed)",
+            lib.name,
+            failures.len(),
+            stale.len()
+        );
+    }
+
+    // `ar rcs` adds to an existing archive, so start over
+    let _ = fs::remove_file(&output);
+    let archiver = get_archiver(tc.compiler, tc.is_msvc);
+    let mut cmd = Command::new(&archiver);
+    if tc.is_msvc {
+        cmd.arg("/NOLOGO").arg(format!("/OUT:{}", output.display()));
+    } else {
+        cmd.arg("rcs").arg(&output);
+    }
+    let out = cmd
+        .args(objects.iter().map(|(_, obj)| obj))
+        .envs(tc.env)
+        .output()
+        .with_context(|| format!("Failed to run {}", archiver))?;
+    if !out.status.success() {
+        bail!(
+            "Failed to archive dependency '{}': {}",
+            lib.name,
+            String::from_utf8_lossy(&out.stderr)
+        );
+    }
+    status!("   {} Built {}", "✓".green(), output.display());
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn toolchain<'a>(env: &'a HashMap<String, String>, profile: &'a str) -> LibToolchain<'a> {
+        LibToolchain {
+            compiler: "g++",
+            c_compiler: "gcc",
+            is_msvc: false,
+            fingerprint: "g++ 12",
+            env,
+            profile,
+            release: profile == "release",
+            jobs: 4,
+            flags: vec!["-O3".to_string()],
+            target_flags: Vec::new(),
+            triple: None,
+            edition: "c++20",
+            c_edition: "c17",
+            target_os: TargetOs::Linux,
+        }
+    }
+
+    #[test]
+    fn test_compile_args_and_cache_key() {
+        let lib = SourceLib {
+            name: "glad".to_string(),
+            dir: PathBuf::from("/cache/glad"),
+            sources: vec![PathBuf::from("/cache/glad/src/gl.c")],
+            edition: None,
+            c_edition: None,
+        };
+        let env = HashMap::new();
+        let includes = [PathBuf::from("/cache/glad/include")];
+        let release = toolchain(&env, "release");
+
+        let args = compile_args(
+            &lib,
+            &lib.sources[0],
+            Path::new("/out/gl.o"),
+            &includes,
+            &[],
+            &release,
+        );
+        assert_eq!(args[0], "gcc");
+        assert!(args.contains(&"-std=c17".to_string()));
+        assert!(args.contains(&"-I/cache/glad/include".to_string()));
+        assert!(args.contains(&"-O3".to_string()));
+
+        // Same settings share a library; a new profile or toolchain doesn't
+        let key = cache_key(&lib, &includes, &[], &release);
+        assert_eq!(key, cache_key(&lib, &includes, &[], &release));
+        assert_ne!(
+            key,
+            cache_key(&lib, &includes, &[], &toolchain(&env, "debug"))
+        );
+        let other = LibToolchain {
+            fingerprint: "clang++ 17",
+            ..toolchain(&env, "release")
+        };
+        assert_ne!(key, cache_key(&lib, &includes, &[], &other));
+    }
+}
@@ -0,0 +1,355 @@
+//! Structured compiler diagnostics and `--message-format=json` output.
+//!
+//! GCC, Clang, MSVC and clang-tidy output is parsed into [`Diagnostic`]
+//! records (file, line, column, severity, message, notes). In JSON mode every
+//! diagnostic, artifact and test result is written to stdout as one JSON
+//! object per line, so editor plugins and CI bots don't have to scrape the
+//! human output. Human-readable progress goes through [`status!`](crate::status),
+//! which moves to stderr in JSON mode so stdout stays machine-readable.
+
+use crate::config::ArtifactKind;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::json;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// How build results are reported
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum MessageFormat {
+    /// Colored, human-readable output
+    #[default]
+    Human,
+    /// One JSON object per line on stdout
+    Json,
+}
+
+impl MessageFormat {
+    pub fn is_json(self) -> bool {
+        self == MessageFormat::Json
+    }
+}
+
+static JSON_OUTPUT: AtomicBool = AtomicBool::new(false);
+
+/// Select the output mode for the rest of the process
+pub fn set_message_format(format: MessageFormat) {
+    JSON_OUTPUT.store(format.is_json(), Ordering::Relaxed);
+}
+
+/// Whether stdout is reserved for JSON records
+pub fn json_output() -> bool {
+    JSON_OUTPUT.load(Ordering::Relaxed)
+}
+
+/// Stdout for child processes whose output is meant for humans (build
+/// scripts): inherited normally, redirected to stderr in JSON mode.
+pub fn child_stdout() -> Stdio {
+    if json_output() {
+        std::io::stderr().into()
+    } else {
+        Stdio::inherit()
+    }
+}
+
+/// `println!` for human-readable output. Goes to stderr when
+/// `--message-format=json` is active so it doesn't mix with the JSON records.
+#[macro_export]
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if $crate::build::diagnostics::json_output() {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    pub severity: Severity,
+    /// Warning flag, check name or MSVC error code (e.g. `-Wunused`, `C2065`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    pub message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<Diagnostic>,
+}
+
+static ANSI: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\x1b\[[0-9;]*[A-Za-z]").unwrap());
+
+// file:line[:col]: severity: message  (GCC, Clang, clang-tidy)
+static GCC: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?P<file>.+?):(?P<line>\d+):(?:(?P<col>\d+):)?\s*(?P<sev>fatal error|error|warning|note|remark):\s*(?P<msg>.*)$",
+    )
+    .unwrap()
+});
+
+// file(line[,col]): severity CODE: message  (MSVC)
+static MSVC: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?P<file>.+?)\((?P<line>\d+)(?:,(?P<col>\d+))?\)\s*:\s*(?P<sev>fatal error|error|warning|note)\s*(?P<code>[A-Z]+\d+)?\s*:\s*(?P<msg>.*)$",
+    )
+    .unwrap()
+});
+
+// [origin: ]severity[ CODE]: message  (driver and linker errors without a location)
+static BARE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?:.*?:\s*)?(?P<sev>fatal error|error|warning)\s*(?P<code>[A-Z]+\d+)?\s*:\s*(?P<msg>.*)$",
+    )
+    .unwrap()
+});
+
+// Trailing `[-Wflag]` / `[check-name]`
+static FLAG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\s+\[(?P<code>[\w\-.,=+]+)\]$").unwrap());
+
+fn severity(sev: &str) -> Severity {
+    match sev {
+        "warning" => Severity::Warning,
+        "note" | "remark" => Severity::Note,
+        _ => Severity::Error,
+    }
+}
+
+fn split_code(msg: &str) -> (String, Option<String>) {
+    match FLAG.captures(msg) {
+        Some(caps) => {
+            let start = caps.get(0).map_or(msg.len(), |m| m.start());
+            (msg[..start].to_string(), Some(caps["code"].to_string()))
+        }
+        None => (msg.to_string(), None),
+    }
+}
+
+fn parse_line(line: &str) -> Option<Diagnostic> {
+    // MSVC first: its `file(line)` prefix would otherwise match the bare form
+    if let Some(caps) = MSVC.captures(line) {
+        return Some(Diagnostic {
+            file: Some(caps["file"].trim().to_string()),
+            line: caps["line"].parse().ok(),
+            column: caps.name("col").and_then(|c| c.as_str().parse().ok()),
+            severity: severity(&caps["sev"]),
+            code: caps.name("code").map(|c| c.as_str().to_string()),
+            message: caps["msg"].trim().to_string(),
+            notes: Vec::new(),
+        });
+    }
+
+    if let Some(caps) = GCC.captures(line) {
+        let (message, code) = split_code(caps["msg"].trim());
+        return Some(Diagnostic {
+            file: Some(caps["file"].to_string()),
+            line: caps["line"].parse().ok(),
+            column: caps.name("col").and_then(|c| c.as_str().parse().ok()),
+            severity: severity(&caps["sev"]),
+            code,
+            message,
+            notes: Vec::new(),
+        });
+    }
+
+    if let Some(caps) = BARE.captures(line) {
+        return Some(Diagnostic {
+            file: None,
+            line: None,
+            column: None,
+            severity: severity(&caps["sev"]),
+            code: caps.name("code").map(|c| c.as_str().to_string()),
+            message: caps["msg"].trim().to_string(),
+            notes: Vec::new(),
+        });
+    }
+
+    // GNU ld: "main.o:main.cpp:(.text+0x5): undefined reference to `foo'"
+    if let Some(idx) = line.find("undefined reference to") {
+        return Some(Diagnostic {
+            file: None,
+            line: None,
+            column: None,
+            severity: Severity::Error,
+            code: None,
+            message: line[idx..].trim().to_string(),
+            notes: Vec::new(),
+        });
+    }
+
+    None
+}
+
+/// Parse compiler, linker or clang-tidy output into diagnostics.
+///
+/// Notes are attached to the diagnostic they follow; source excerpts, caret
+/// lines and "In file included from" context are dropped.
+pub fn parse(output: &str) -> Vec<Diagnostic> {
+    let clean = ANSI.replace_all(output, "");
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+
+    for line in clean.lines() {
+        let Some(diag) = parse_line(line.trim_end()) else {
+            continue;
+        };
+        if diag.severity == Severity::Note
+            && let Some(parent) = diagnostics.last_mut()
+        {
+            parent.notes.push(diag);
+        } else {
+            diagnostics.push(diag);
+        }
+    }
+
+    diagnostics
+}
+
+/// Human-readable one-line form, e.g. `src/main.cpp:3:5: error: ...`
+pub fn render(diag: &Diagnostic) -> String {
+    let mut location = String::new();
+    if let Some(file) = &diag.file {
+        location.push_str(file);
+        if let Some(line) = diag.line {
+            location.push_str(&format!(":{}", line));
+            if let Some(col) = diag.column {
+                location.push_str(&format!(":{}", col));
+            }
+        }
+        location.push_str(": ");
+    }
+    let severity = match diag.severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Note => "note",
+    };
+    format!("{}{}: {}", location, severity, diag.message)
+}
+
+fn emit(value: serde_json::Value) {
+    println!("{}", value);
+}
+
+/// `{"reason":"compiler-message", ...}` for a diagnostic produced while
+/// processing `source`
+pub fn emit_diagnostic(source: &Path, diag: &Diagnostic) {
+    emit(json!({
+        "reason": "compiler-message",
+        "source": source.to_string_lossy(),
+        "diagnostic": diag,
+        "rendered": render(diag),
+    }));
+}
+
+/// `{"reason":"compiler-artifact", ...}` for a linked or archived output
+pub fn emit_artifact(kind: ArtifactKind, path: &Path, fresh: bool) {
+    let name = path
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    emit(json!({
+        "reason": "compiler-artifact",
+        "kind": kind,
+        "name": name,
+        "path": path.to_string_lossy(),
+        "fresh": fresh,
+    }));
+}
+
+/// `{"reason":"test-result", ...}` for one test executable
+pub fn emit_test_result(name: &str, success: bool, output: &str) {
+    emit(json!({
+        "reason": "test-result",
+        "name": name,
+        "success": success,
+        "output": output,
+    }));
+}
+
+/// `{"reason":"build-finished", ...}` closing a JSON stream
+pub fn emit_finished(success: bool) {
+    emit(json!({
+        "reason": "build-finished",
+        "success": success,
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_gcc_with_notes() {
+        let output = "src/main.cpp: In function 'int main()':\n\
+src/main.cpp:4:5: error: 'foo' was not declared in this scope\n\
+    4 |     foo();\n\
+      |     ^~~\n\
+src/util.h:2:6: note: suggested alternative: 'bar'\n\
+src/main.cpp:7:9: warning: unused variable 'x' [-Wunused-variable]\n";
+        let diags = parse(output);
+        assert_eq!(diags.len(), 2);
+
+        assert_eq!(diags[0].file.as_deref(), Some("src/main.cpp"));
+        assert_eq!(diags[0].line, Some(4));
+        assert_eq!(diags[0].column, Some(5));
+        assert_eq!(diags[0].severity, Severity::Error);
+        assert_eq!(diags[0].notes.len(), 1);
+        assert_eq!(diags[0].notes[0].file.as_deref(), Some("src/util.h"));
+
+        assert_eq!(diags[1].severity, Severity::Warning);
+        assert_eq!(diags[1].message, "unused variable 'x'");
+        assert_eq!(diags[1].code.as_deref(), Some("-Wunused-variable"));
+    }
+
+    #[test]
+    fn test_parse_clang_colored() {
+        let output = "\x1b[1msrc/a.c:10:3: \x1b[0m\x1b[0;1;31merror: \x1b[0m\x1b[1m'stdio.hh' file not found\x1b[0m\n";
+        let diags = parse(output);
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].file.as_deref(), Some("src/a.c"));
+        assert_eq!(diags[0].message, "'stdio.hh' file not found");
+    }
+
+    #[test]
+    fn test_parse_msvc_and_linker() {
+        let output = "src\\main.cpp(12,7): error C2065: 'y': undeclared identifier\n\
+main.obj : error LNK2019: unresolved external symbol foo referenced in function main\n\
+/usr/bin/ld: main.o: in function `main':\n\
+main.cpp:(.text+0x9): undefined reference to `foo()'\n\
+collect2: error: ld returned 1 exit status\n";
+        let diags = parse(output);
+        assert_eq!(diags.len(), 4);
+
+        assert_eq!(diags[0].file.as_deref(), Some("src\\main.cpp"));
+        assert_eq!(diags[0].line, Some(12));
+        assert_eq!(diags[0].column, Some(7));
+        assert_eq!(diags[0].code.as_deref(), Some("C2065"));
+
+        assert_eq!(diags[1].file, None);
+        assert_eq!(diags[1].code.as_deref(), Some("LNK2019"));
+
+        assert_eq!(diags[2].message, "undefined reference to `foo()'");
+        assert_eq!(diags[3].message, "ld returned 1 exit status");
+    }
+
+    #[test]
+    fn test_json_shape() {
+        let diag = &parse("a.cpp:1:2: warning: hi [-Wall]")[0];
+        let value = serde_json::to_value(diag).unwrap();
+        assert_eq!(value["severity"], "warning");
+        assert_eq!(value["code"], "-Wall");
+        assert!(value.get("notes").is_none());
+    }
+}
@@ -0,0 +1,1125 @@
+//! Dependencies built and installed with their own build system.
+//!
+//! CMake, Meson and Autotools dependencies are configured with the project's
+//! compilers, target and build type, built as static libraries, and
+//! installed into `~/.cx/cache/libs/<name>-<key>/install`, where the key
+//! hashes the source tree, the configure arguments and environment, the
+//! toolchain fingerprint and the profile.
+//!
+//! What to compile and link against is read from the install tree: the CMake
+//! package config it exports (`<Pkg>Targets*.cmake`, CMake only), else its
+//! pkg-config files (`lib/pkgconfig/*.pc`), else `include/` and the static
+//! libraries in `lib/`.
+
+use super::dep_libs::{Built, LibToolchain};
+use super::fingerprint;
+use super::utils::get_archiver;
+use crate::config::{AutotoolsConfig, CMakeConfig, MesonConfig};
+use crate::deps::{BuildSystem, ExternalBuild};
+use crate::status;
+use crate::toolchain::cross::TargetOs;
+use anyhow::{Context, Result, bail};
+use colored::*;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use walkdir::WalkDir;
+
+/// Written into the cache entry once the install succeeded
+const INSTALLED_MARKER: &str = ".cx-installed";
+
+/// Configure, build and install `ext` (if not cached already) and return
+/// what to compile and link against
+pub fn build(ext: &ExternalBuild, tc: &LibToolchain) -> Result<Built> {
+    let out_dir = crate::cache::lib_dir(&ext.name, &cache_key(ext, tc))?;
+    let _lock = crate::cache::lock_entry(&out_dir)?;
+    let prefix = out_dir.join("install");
+    let marker = out_dir.join(INSTALLED_MARKER);
+
+    // Cached trees never change; path and vendored ones get an incremental
+    // rebuild every time
+    if !(ext.pinned && marker.exists()) {
+        status!(
+            "   {} Building {} with {} ({})...",
+            "🔨".yellow(),
+            ext.name,
+            ext.system.label(),
+            if tc.release { "release" } else { "debug" }
+        );
+        fs::create_dir_all(&out_dir)?;
+        let _ = fs::remove_file(&marker);
+        match &ext.system {
+            BuildSystem::CMake(cmake) => build_cmake(ext, cmake, &out_dir, tc)?,
+            BuildSystem::Meson(meson) => build_meson(ext, meson, &out_dir, tc)?,
+            BuildSystem::Autotools(autotools) => build_autotools(ext, autotools, &out_dir, tc)?,
+        }
+        fs::write(&marker, "")?;
+        status!(
+            "   {} Installed {} into {}",
+            "✓".green(),
+            ext.name,
+            prefix.display()
+        );
+    }
+
+    read_install(ext, &prefix, tc)
+}
+
+/// Hash of everything that changes the installed libraries
+fn cache_key(ext: &ExternalBuild, tc: &LibToolchain) -> String {
+    let (build_dir, prefix) = (Path::new(""), Path::new(""));
+    let mut key_args = vec![tc.profile.to_string(), ext.system.label().to_string()];
+    match &ext.system {
+        BuildSystem::CMake(cmake) => {
+            key_args.extend(cmake_configure_args(ext, cmake, build_dir, prefix, tc));
+        }
+        BuildSystem::Meson(meson) => {
+            key_args.extend(meson_setup_args(ext, meson, build_dir, prefix, None, tc));
+        }
+        BuildSystem::Autotools(autotools) => {
+            key_args.push(ext.dir.to_string_lossy().to_string());
+            key_args.extend(configure_args(autotools, prefix, tc));
+        }
+    }
+    for (name, value) in build_env(tc, !matches!(ext.system, BuildSystem::Autotools(_))) {
+        key_args.push(format!("{}={}", name, value));
+    }
+    fingerprint::compute(&key_args, tc.fingerprint, tc.env)
+}
+
+/// `CC`, `CXX`, `AR`, `CFLAGS`, `CXXFLAGS` and `LDFLAGS` for Meson and
+/// Autotools: the project's compilers, plus the target flags (and, when the
+/// build system has no build type of its own, the profile's flags) after any
+/// `CFLAGS` already in the environment
+fn build_env(tc: &LibToolchain, has_build_type: bool) -> Vec<(String, String)> {
+    let mut env = vec![
+        ("CC".to_string(), tc.c_compiler.to_string()),
+        ("CXX".to_string(), tc.compiler.to_string()),
+    ];
+    if !tc.is_msvc {
+        env.push(("AR".to_string(), get_archiver(tc.compiler, tc.is_msvc)));
+    }
+
+    let mut flags = Vec::new();
+    if !has_build_type {
+        flags.extend(tc.flags.iter().cloned());
+    }
+    flags.extend(tc.target_flags.iter().cloned());
+    for (var, extra) in [
+        ("CFLAGS", &flags),
+        ("CXXFLAGS", &flags),
+        ("LDFLAGS", &tc.target_flags),
+    ] {
+        let value: Vec<String> = std::env::var(var)
+            .ok()
+            .into_iter()
+            .filter(|v| !v.is_empty())
+            .chain(extra.iter().cloned())
+            .collect();
+        if !value.is_empty() {
+            env.push((var.to_string(), value.join(" ")));
+        }
+    }
+    env
+}
+
+/// Run `program` for one step of building dependency `name`, showing its
+/// output only when it fails
+fn run(
+    program: &str,
+    name: &str,
+    step: &str,
+    args: &[String],
+    dir: Option<&Path>,
+    env: &[(String, String)],
+    tc: &LibToolchain,
+) -> Result<()> {
+    let mut cmd = Command::new(program);
+    cmd.args(args).envs(tc.env).envs(env.iter().cloned());
+    if let Some(dir) = dir {
+        cmd.current_dir(dir);
+    }
+    let output = cmd.output().with_context(|| {
+        format!(
+            "Failed to run {} for dependency '{}'.\n\n\
+            💡 Tip: Install {} and make sure it is on PATH.",
+            program, name, program
+        )
+    })?;
+    if !output.status.success() {
+        status!("{}", String::from_utf8_lossy(&output.stdout));
+        status!("{}", String::from_utf8_lossy(&output.stderr));
+        bail!("{} ({}) failed for dependency '{}'", program, step, name);
+    }
+    Ok(())
+}
+
+/// `cmake` configure arguments for building `ext` into `build_dir` and
+/// installing into `prefix`
+fn cmake_configure_args(
+    ext: &ExternalBuild,
+    cmake: &CMakeConfig,
+    build_dir: &Path,
+    prefix: &Path,
+    tc: &LibToolchain,
+) -> Vec<String> {
+    let build_type = if tc.release { "Release" } else { "Debug" };
+    let mut args = vec![
+        "-S".to_string(),
+        ext.dir.to_string_lossy().to_string(),
+        "-B".to_string(),
+        build_dir.to_string_lossy().to_string(),
+    ];
+    if let Some(generator) = cmake_generator(tc.is_msvc) {
+        args.push(format!("-G{}", generator));
+    }
+    args.extend([
+        format!("-DCMAKE_BUILD_TYPE={}", build_type),
+        format!("-DCMAKE_INSTALL_PREFIX={}", prefix.display()),
+        "-DCMAKE_INSTALL_LIBDIR=lib".to_string(),
+        format!("-DCMAKE_C_COMPILER={}", tc.c_compiler),
+        format!("-DCMAKE_CXX_COMPILER={}", tc.compiler),
+        // Static and position independent, like libraries built from sources
+        "-DBUILD_SHARED_LIBS=OFF".to_string(),
+        "-DCMAKE_POSITION_INDEPENDENT_CODE=ON".to_string(),
+        "-DBUILD_TESTING=OFF".to_string(),
+    ]);
+    if tc.is_msvc {
+        // The project links against the release DLL runtime (/MD)
+        args.push("-DCMAKE_MSVC_RUNTIME_LIBRARY=MultiThreadedDLL".to_string());
+    }
+    if let Some(triple) = tc.triple {
+        let system = match tc.target_os {
+            TargetOs::Linux => "Linux",
+            TargetOs::Windows => "Windows",
+            TargetOs::MacOs => "Darwin",
+            TargetOs::Wasm => "Emscripten",
+            TargetOs::None => "Generic",
+        };
+        args.push(format!("-DCMAKE_SYSTEM_NAME={}", system));
+        if let Some(arch) = triple.split('-').next() {
+            args.push(format!("-DCMAKE_SYSTEM_PROCESSOR={}", arch));
+        }
+        // Bare metal toolchains can't link test executables
+        if tc.target_os == TargetOs::None {
+            args.push("-DCMAKE_TRY_COMPILE_TARGET_TYPE=STATIC_LIBRARY".to_string());
+        }
+    }
+    if !tc.target_flags.is_empty() {
+        let flags = tc.target_flags.join(" ");
+        args.push(format!("-DCMAKE_C_FLAGS={}", flags));
+        args.push(format!("-DCMAKE_CXX_FLAGS={}", flags));
+    }
+    // Last, so the dependency's own options win
+    args.extend(cmake.define_args());
+    args
+}
+
+/// Ninja when it is installed, NMake for MSVC without it, else CMake's default
+fn cmake_generator(is_msvc: bool) -> Option<&'static str> {
+    if has_ninja() {
+        Some("Ninja")
+    } else if is_msvc {
+        Some("NMake Makefiles")
+    } else {
+        None
+    }
+}
+
+fn has_ninja() -> bool {
+    Command::new("ninja")
+        .arg("--version")
+        .output()
+        .is_ok_and(|out| out.status.success())
+}
+
+fn build_cmake(
+    ext: &ExternalBuild,
+    cmake: &CMakeConfig,
+    out_dir: &Path,
+    tc: &LibToolchain,
+) -> Result<()> {
+    let build_dir = out_dir.join("build");
+    let prefix = out_dir.join("install");
+    let config = if tc.release { "Release" } else { "Debug" };
+    if !build_dir.join("CMakeCache.txt").exists() {
+        let args = cmake_configure_args(ext, cmake, &build_dir, &prefix, tc);
+        run("cmake", &ext.name, "configure", &args, None, &[], tc)?;
+    }
+    let build_dir = build_dir.to_string_lossy().to_string();
+    let jobs = tc.jobs.to_string();
+    let steps = [
+        (
+            "build",
+            vec![
+                "--build",
+                &build_dir,
+                "--config",
+                config,
+                "--parallel",
+                &jobs,
+            ],
+        ),
+        ("install", vec!["--install", &build_dir, "--config", config]),
+    ];
+    for (step, args) in steps {
+        let args: Vec<String> = args.into_iter().map(String::from).collect();
+        run("cmake", &ext.name, step, &args, None, &[], tc)?;
+    }
+    Ok(())
+}
+
+/// `meson setup` arguments for building `ext` into `build_dir` and
+/// installing into `prefix`
+fn meson_setup_args(
+    ext: &ExternalBuild,
+    meson: &MesonConfig,
+    build_dir: &Path,
+    prefix: &Path,
+    cross_file: Option<&Path>,
+    tc: &LibToolchain,
+) -> Vec<String> {
+    let mut args = vec![
+        "setup".to_string(),
+        build_dir.to_string_lossy().to_string(),
+        ext.dir.to_string_lossy().to_string(),
+        format!("--prefix={}", prefix.display()),
+        "--libdir=lib".to_string(),
+        format!(
+            "--buildtype={}",
+            if tc.release { "release" } else { "debug" }
+        ),
+        // Static and position independent, like libraries built from sources
+        "--default-library=static".to_string(),
+        "-Db_staticpic=true".to_string(),
+    ];
+    if tc.is_msvc {
+        // The project links against the release DLL runtime (/MD)
+        args.push("-Db_vscrt=md".to_string());
+    }
+    if let Some(cross_file) = cross_file {
+        args.push(format!("--cross-file={}", cross_file.display()));
+    }
+    args.extend(meson.define_args());
+    args
+}
+
+/// Meson cross file describing the target, as Meson ignores `CC` and
+/// friends for the host machine when cross-compiling
+fn meson_cross_file(triple: &str, tc: &LibToolchain) -> String {
+    let quote = |s: &str| format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'"));
+    let list = |items: &[String]| {
+        let items: Vec<String> = items.iter().map(|s| quote(s)).collect();
+        format!("[{}]", items.join(", "))
+    };
+    let cpu = triple.split('-').next().unwrap_or(triple);
+    let cpu_family = match cpu {
+        "i386" | "i486" | "i586" | "i686" => "x86",
+        c if c.starts_with("armv") || c.starts_with("thumb") => "arm",
+        "arm64" => "aarch64",
+        c => c,
+    };
+    let system = match tc.target_os {
+        TargetOs::Linux => "linux",
+        TargetOs::Windows => "windows",
+        TargetOs::MacOs => "darwin",
+        TargetOs::Wasm => "emscripten",
+        TargetOs::None => "none",
+    };
+    format!(
+        "[binaries]\n\
+        c = {}\n\
+        cpp = {}\n\
+        ar = {}\n\n\
+        [built-in options]\n\
+        c_args = {flags}\n\
+        cpp_args = {flags}\n\
+        c_link_args = {flags}\n\
+        cpp_link_args = {flags}\n\n\
+        [host_machine]\n\
+        system = '{}'\n\
+        cpu_family = '{}'\n\
+        cpu = '{}'\n\
+        endian = 'little'\n",
+        quote(tc.c_compiler),
+        quote(tc.compiler),
+        quote(&get_archiver(tc.compiler, tc.is_msvc)),
+        system,
+        cpu_family,
+        cpu,
+        flags = list(&tc.target_flags),
+    )
+}
+
+fn build_meson(
+    ext: &ExternalBuild,
+    meson: &MesonConfig,
+    out_dir: &Path,
+    tc: &LibToolchain,
+) -> Result<()> {
+    let build_dir = out_dir.join("build");
+    let prefix = out_dir.join("install");
+    let env = build_env(tc, true);
+    if !build_dir.join("build.ninja").exists() {
+        let cross_file = match tc.triple {
+            Some(triple) => {
+                let path = out_dir.join("cross.ini");
+                fs::write(&path, meson_cross_file(triple, tc))?;
+                Some(path)
+            }
+            None => None,
+        };
+        let args = meson_setup_args(ext, meson, &build_dir, &prefix, cross_file.as_deref(), tc);
+        run("meson", &ext.name, "setup", &args, None, &env, tc)?;
+    }
+    let dir = build_dir.to_string_lossy().to_string();
+    let jobs = format!("-j{}", tc.jobs);
+    if has_ninja() {
+        let args = [jobs, "-C".to_string(), dir, "install".to_string()];
+        run("ninja", &ext.name, "install", &args, None, &env, tc)
+    } else {
+        let args = ["compile".to_string(), "-C".to_string(), dir.clone(), jobs];
+        run("meson", &ext.name, "build", &args, None, &env, tc)?;
+        let args = ["install", "-C", &dir, "--no-rebuild"].map(String::from);
+        run("meson", &ext.name, "install", &args, None, &env, tc)
+    }
+}
+
+/// `./configure` arguments installing into `prefix`
+fn configure_args(autotools: &AutotoolsConfig, prefix: &Path, tc: &LibToolchain) -> Vec<String> {
+    let mut args = vec![
+        format!("--prefix={}", prefix.display()),
+        format!("--libdir={}", prefix.join("lib").display()),
+        // Static and position independent, like libraries built from sources
+        "--enable-static".to_string(),
+        "--disable-shared".to_string(),
+        "--with-pic".to_string(),
+    ];
+    if let Some(triple) = tc.triple {
+        args.push(format!("--host={}", triple));
+    }
+    args.extend(autotools.args.iter().cloned());
+    args
+}
+
+fn build_autotools(
+    ext: &ExternalBuild,
+    autotools: &AutotoolsConfig,
+    out_dir: &Path,
+    tc: &LibToolchain,
+) -> Result<()> {
+    if tc.is_msvc {
+        bail!(
+            "Autotools dependency '{}' needs a GCC or Clang toolchain, not MSVC",
+            ext.name
+        );
+    }
+    let build_dir = out_dir.join("build");
+    let prefix = out_dir.join("install");
+    let env = build_env(tc, false);
+
+    // Without a generated configure script, generate one in a copy, as
+    // cached trees are shared and checksummed
+    let mut src_dir = ext.dir.clone();
+    if !src_dir.join("configure").exists() {
+        src_dir = out_dir.join("src");
+        if !src_dir.join("configure").exists() {
+            copy_tree(&ext.dir, &src_dir)?;
+            let args = ["-fi".to_string()];
+            run(
+                "autoreconf",
+                &ext.name,
+                "generate",
+                &args,
+                Some(&src_dir),
+                &env,
+                tc,
+            )?;
+        }
+    }
+
+    fs::create_dir_all(&build_dir)?;
+    if !build_dir.join("Makefile").exists() {
+        let configure = src_dir.join("configure").to_string_lossy().to_string();
+        let mut args = vec![configure];
+        args.extend(configure_args(autotools, &prefix, tc));
+        run(
+            "sh",
+            &ext.name,
+            "configure",
+            &args,
+            Some(&build_dir),
+            &env,
+            tc,
+        )?;
+    }
+    run(
+        "make",
+        &ext.name,
+        "build",
+        &[format!("-j{}", tc.jobs)],
+        Some(&build_dir),
+        &env,
+        tc,
+    )?;
+    run(
+        "make",
+        &ext.name,
+        "install",
+        &["install".to_string()],
+        Some(&build_dir),
+        &env,
+        tc,
+    )
+}
+
+/// Copy `src` to `dst`, leaving out `.git`
+fn copy_tree(src: &Path, dst: &Path) -> Result<()> {
+    let _ = fs::remove_dir_all(dst);
+    for entry in WalkDir::new(src)
+        .into_iter()
+        .filter_entry(|e| e.file_name() != ".git")
+    {
+        let entry = entry?;
+        let target = dst.join(entry.path().strip_prefix(src)?);
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Properties of an imported target from a `<Pkg>Targets*.cmake` file
+#[derive(Debug, Default, Clone, PartialEq)]
+struct ImportedTarget {
+    location: Option<String>,
+    include_dirs: Vec<String>,
+    defines: Vec<String>,
+    link_libraries: Vec<String>,
+}
+
+/// Add the `set_target_properties(...)` calls of an exported targets file
+/// to `targets`, with `${_IMPORT_PREFIX}` resolved to `prefix`
+fn parse_targets_file(text: &str, prefix: &Path, targets: &mut BTreeMap<String, ImportedTarget>) {
+    let prefix = prefix.to_string_lossy();
+    let mut rest = text;
+    while let Some(start) = rest.find("set_target_properties(") {
+        rest = &rest[start + "set_target_properties(".len()..];
+        let tokens = call_arguments(rest);
+        let [name, properties, values @ ..] = tokens.as_slice() else {
+            continue;
+        };
+        if properties != "PROPERTIES" {
+            continue;
+        }
+        let target = targets.entry(name.clone()).or_default();
+        for pair in values.chunks(2) {
+            let [property, value] = pair else { continue };
+            let value = value.replace("${_IMPORT_PREFIX}", &prefix);
+            let list = || {
+                value
+                    .split(';')
+                    .filter_map(strip_generator_expression)
+                    .map(String::from)
+                    .collect::<Vec<_>>()
+            };
+            match property.as_str() {
+                p if p == "IMPORTED_LOCATION" || p.starts_with("IMPORTED_LOCATION_") => {
+                    target.location = Some(value.clone());
+                }
+                "INTERFACE_INCLUDE_DIRECTORIES" => target.include_dirs.extend(list()),
+                "INTERFACE_COMPILE_DEFINITIONS" => target.defines.extend(list()),
+                "INTERFACE_LINK_LIBRARIES" => target.link_libraries.extend(list()),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Arguments of a CMake command call, up to its closing parenthesis
+fn call_arguments(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut chars = text.chars();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                if quoted {
+                    tokens.push(std::mem::take(&mut current));
+                }
+                quoted = !quoted;
+            }
+            '\\' if quoted => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            ')' if !quoted => break,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+/// `$<LINK_ONLY:x>` is `x`; other generator expressions don't apply to a
+/// plain compile and link
+fn strip_generator_expression(item: &str) -> Option<&str> {
+    let item = item.trim();
+    if let Some(inner) = item
+        .strip_prefix("$<LINK_ONLY:")
+        .and_then(|i| i.strip_suffix('>'))
+    {
+        return Some(inner);
+    }
+    (!item.is_empty() && !item.contains("$<")).then_some(item)
+}
+
+/// Include directories, defines and libraries of the install tree at `prefix`
+fn read_install(ext: &ExternalBuild, prefix: &Path, tc: &LibToolchain) -> Result<Built> {
+    let requested: &[String] = match &ext.system {
+        BuildSystem::CMake(cmake) => &cmake.targets,
+        _ => &[],
+    };
+    if matches!(ext.system, BuildSystem::CMake(_)) {
+        let mut targets = BTreeMap::new();
+        for entry in WalkDir::new(prefix).into_iter().flatten() {
+            let file = entry.file_name().to_string_lossy().to_lowercase();
+            if file.ends_with(".cmake") && file.contains("targets") {
+                let text = fs::read_to_string(entry.path())?;
+                parse_targets_file(&text, prefix, &mut targets);
+            }
+        }
+        if !targets.is_empty() {
+            return select_targets(&ext.name, &targets, requested, tc);
+        }
+    }
+
+    let mut packages = BTreeMap::new();
+    for dir in [prefix.join("lib"), prefix.join("share")] {
+        let dir = dir.join("pkgconfig");
+        for entry in fs::read_dir(&dir).into_iter().flatten().flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|e| e == "pc") {
+                let name = path.file_stem().unwrap_or_default().to_string_lossy();
+                let text = fs::read_to_string(&path)?;
+                packages.insert(name.to_string(), parse_pc(&text, &dir));
+            }
+        }
+    }
+    if !packages.is_empty() {
+        return Ok(from_pkg_config(&ext.name, &packages));
+    }
+
+    Ok(scan_install(prefix, requested, tc.is_msvc))
+}
+
+/// `requested` targets (all, if none) and the targets they link to
+fn select_targets(
+    name: &str,
+    targets: &BTreeMap<String, ImportedTarget>,
+    requested: &[String],
+    tc: &LibToolchain,
+) -> Result<Built> {
+    let roots: Vec<String> = if requested.is_empty() {
+        targets.keys().cloned().collect()
+    } else {
+        let mut roots = Vec::new();
+        for want in requested {
+            let found: Vec<&String> = targets
+                .keys()
+                .filter(|t| *t == want || t.rsplit("::").next() == Some(want.as_str()))
+                .collect();
+            if found.is_empty() {
+                bail!(
+                    "CMake target '{}' is not exported by dependency '{}' (available: {})",
+                    want,
+                    name,
+                    targets.keys().cloned().collect::<Vec<_>>().join(", ")
+                );
+            }
+            roots.extend(found.into_iter().cloned());
+        }
+        roots
+    };
+
+    let mut built = Built::default();
+    let mut seen = Vec::new();
+    for root in &roots {
+        add_target(root, targets, &mut seen, &mut built, tc);
+    }
+    Ok(built)
+}
+
+/// Add `name` and, after it (static link order), the targets it links to
+fn add_target(
+    name: &str,
+    targets: &BTreeMap<String, ImportedTarget>,
+    seen: &mut Vec<String>,
+    built: &mut Built,
+    tc: &LibToolchain,
+) {
+    if seen.iter().any(|s| s == name) {
+        return;
+    }
+    seen.push(name.to_string());
+    let Some(target) = targets.get(name) else {
+        // Not one of ours: a file, a flag or a system library
+        if name == "Threads::Threads" {
+            if !tc.is_msvc && tc.target_os != TargetOs::Windows {
+                push_unique(&mut built.libs, "pthread".to_string());
+            }
+        } else if let Some(lib) = name.strip_prefix("-l") {
+            push_unique(&mut built.libs, lib.to_string());
+        } else if name.contains('/') || name.contains('\\') || name.starts_with('-') {
+            push_unique(&mut built.link_flags, name.to_string());
+        } else if !name.contains("::") {
+            push_unique(&mut built.libs, name.to_string());
+        }
+        return;
+    };
+
+    for dir in &target.include_dirs {
+        push_unique(&mut built.include_dirs, PathBuf::from(dir));
+    }
+    let define = if tc.is_msvc { "/D" } else { "-D" };
+    for def in &target.defines {
+        push_unique(&mut built.cflags, format!("{}{}", define, def));
+    }
+    if let Some(location) = &target.location {
+        push_unique(&mut built.link_flags, location.clone());
+    }
+    for lib in &target.link_libraries {
+        add_target(lib, targets, seen, built, tc);
+    }
+}
+
+/// A pkg-config file of the install tree
+#[derive(Debug, Default, PartialEq)]
+struct PcFile {
+    cflags: Vec<String>,
+    /// `Libs` and `Libs.private`, as the libraries are static
+    libs: Vec<String>,
+    /// Package names from `Requires` and `Requires.private`
+    requires: Vec<String>,
+}
+
+/// Parse a `.pc` file found in `pcfiledir`
+fn parse_pc(text: &str, pcfiledir: &Path) -> PcFile {
+    let mut vars = HashMap::new();
+    vars.insert(
+        "pcfiledir".to_string(),
+        pcfiledir.to_string_lossy().to_string(),
+    );
+    let mut pc = PcFile::default();
+    for line in text.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        // `Field: value` or `variable=value`, whichever separator comes first
+        let colon = line.find(':');
+        let equals = line.find('=');
+        match (colon, equals) {
+            (Some(c), e) if e.is_none_or(|e| c < e) => {
+                let value = expand_pc_vars(line[c + 1..].trim(), &vars);
+                let words = value.split_whitespace().map(String::from);
+                match line[..c].trim() {
+                    "Cflags" | "CFlags" => pc.cflags.extend(words),
+                    "Libs" | "Libs.private" => pc.libs.extend(words),
+                    "Requires" | "Requires.private" => {
+                        // `glib-2.0 >= 2.70, zlib`: drop the version constraints
+                        let mut skip = false;
+                        for word in value.split([',', ' ', '\t']).filter(|w| !w.is_empty()) {
+                            if ["<", ">", "=", "<=", ">=", "!="].contains(&word) {
+                                skip = true;
+                            } else if !std::mem::take(&mut skip) {
+                                pc.requires.push(word.to_string());
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            (_, Some(e)) => {
+                let value = expand_pc_vars(line[e + 1..].trim(), &vars);
+                vars.insert(line[..e].trim().to_string(), value);
+            }
+            _ => {}
+        }
+    }
+    pc
+}
+
+fn expand_pc_vars(value: &str, vars: &HashMap<String, String>) -> String {
+    let mut out = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        out.push_str(&rest[..start]);
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        let name = &rest[start + 2..start + len];
+        out.push_str(vars.get(name).map_or("", String::as_str));
+        rest = &rest[start + len + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Flags of every package in `packages`, dependents before the packages
+/// they require (static link order); requirements from outside the install
+/// tree are asked of the system's pkg-config
+fn from_pkg_config(name: &str, packages: &BTreeMap<String, PcFile>) -> Built {
+    fn visit<'a>(
+        pkg: &'a str,
+        packages: &'a BTreeMap<String, PcFile>,
+        done: &mut Vec<&'a str>,
+        system: &mut Vec<&'a str>,
+    ) {
+        if done.contains(&pkg) || system.contains(&pkg) {
+            return;
+        }
+        let Some(pc) = packages.get(pkg) else {
+            system.push(pkg);
+            return;
+        };
+        for required in &pc.requires {
+            visit(required, packages, done, system);
+        }
+        done.push(pkg);
+    }
+    let mut order = Vec::new();
+    let mut system = Vec::new();
+    for pkg in packages.keys() {
+        visit(pkg, packages, &mut order, &mut system);
+    }
+    order.reverse();
+
+    let mut cflags = Vec::new();
+    let mut libs = Vec::new();
+    for pkg in order {
+        cflags.extend(packages[pkg].cflags.iter().cloned());
+        libs.extend(packages[pkg].libs.iter().cloned());
+    }
+    if !system.is_empty() {
+        match Command::new("pkg-config")
+            .args(["--cflags", "--libs"])
+            .args(&system)
+            .output()
+        {
+            Ok(out) if out.status.success() => {
+                let flags = String::from_utf8_lossy(&out.stdout);
+                let (system_cflags, system_libs): (Vec<&str>, Vec<&str>) = flags
+                    .split_whitespace()
+                    .partition(|f| f.starts_with("-I") || f.starts_with("-D"));
+                cflags.extend(system_cflags.into_iter().map(String::from));
+                libs.extend(system_libs.into_iter().map(String::from));
+            }
+            _ => status!(
+                "{} Warning: pkg-config could not find {} (required by '{}')",
+                "!".yellow(),
+                system.join(", "),
+                name
+            ),
+        }
+    }
+
+    let mut built = Built::default();
+    for flag in cflags {
+        match flag.strip_prefix("-I") {
+            Some(dir) => push_unique(&mut built.include_dirs, PathBuf::from(dir)),
+            None => push_unique(&mut built.cflags, flag),
+        }
+    }
+    for flag in libs {
+        // Repeating a library is harmless, dropping one may break the order
+        if flag.starts_with("-l") {
+            built.link_flags.push(flag);
+        } else {
+            push_unique(&mut built.link_flags, flag);
+        }
+    }
+    built
+}
+
+/// Without a package config: `include/`, and the static libraries in `lib/`
+/// (only those named after `requested` targets, if any)
+fn scan_install(prefix: &Path, requested: &[String], is_msvc: bool) -> Built {
+    let mut built = Built::default();
+    if prefix.join("include").is_dir() {
+        built.include_dirs.push(prefix.join("include"));
+    }
+    let extension = if is_msvc { "lib" } else { "a" };
+    let mut libs: Vec<PathBuf> = fs::read_dir(prefix.join("lib"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|e| e == extension))
+        .filter(|p| {
+            let stem = p.file_stem().unwrap_or_default().to_string_lossy();
+            let stem = stem.strip_prefix("lib").unwrap_or(&stem);
+            requested.is_empty() || requested.iter().any(|t| t == stem)
+        })
+        .collect();
+    libs.sort();
+    built
+        .link_flags
+        .extend(libs.iter().map(|p| p.to_string_lossy().to_string()));
+    built
+}
+
+fn push_unique<T: PartialEq>(list: &mut Vec<T>, item: T) {
+    if !list.contains(&item) {
+        list.push(item);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    const TARGETS: &str = r#"
+# Create imported target fmt::fmt
+add_library(fmt::fmt STATIC IMPORTED)
+
+set_target_properties(fmt::fmt PROPERTIES
+  INTERFACE_COMPILE_DEFINITIONS "FMT_STATIC"
+  INTERFACE_INCLUDE_DIRECTORIES "${_IMPORT_PREFIX}/include"
+  INTERFACE_LINK_LIBRARIES "fmt::base;\$<LINK_ONLY:Threads::Threads>;m"
+)
+
+add_library(fmt::base STATIC IMPORTED)
+
+set_target_properties(fmt::base PROPERTIES
+  INTERFACE_INCLUDE_DIRECTORIES "${_IMPORT_PREFIX}/include;\$<BUILD_INTERFACE:/src/include>"
+)
+"#;
+
+    const TARGETS_RELEASE: &str = r#"
+set_property(TARGET fmt::fmt APPEND PROPERTY IMPORTED_CONFIGURATIONS RELEASE)
+set_target_properties(fmt::fmt PROPERTIES
+  IMPORTED_LINK_INTERFACE_LANGUAGES_RELEASE "CXX"
+  IMPORTED_LOCATION_RELEASE "${_IMPORT_PREFIX}/lib/libfmt.a"
+  )
+set_target_properties(fmt::base PROPERTIES
+  IMPORTED_LOCATION_RELEASE "${_IMPORT_PREFIX}/lib/libfmt-base.a"
+  )
+"#;
+
+    fn toolchain(env: &HashMap<String, String>) -> LibToolchain<'_> {
+        LibToolchain {
+            compiler: "g++",
+            c_compiler: "gcc",
+            is_msvc: false,
+            fingerprint: "g++ 12",
+            env,
+            profile: "release",
+            release: true,
+            jobs: 4,
+            flags: Vec::new(),
+            target_flags: Vec::new(),
+            triple: None,
+            edition: "c++20",
+            c_edition: "c17",
+            target_os: TargetOs::Linux,
+        }
+    }
+
+    #[test]
+    fn test_parse_targets_file() {
+        let prefix = Path::new("/cache/fmt/install");
+        let mut targets = BTreeMap::new();
+        parse_targets_file(TARGETS, prefix, &mut targets);
+        parse_targets_file(TARGETS_RELEASE, prefix, &mut targets);
+
+        let fmt = &targets["fmt::fmt"];
+        assert_eq!(
+            fmt.location.as_deref(),
+            Some("/cache/fmt/install/lib/libfmt.a")
+        );
+        assert_eq!(fmt.include_dirs, vec!["/cache/fmt/install/include"]);
+        assert_eq!(fmt.defines, vec!["FMT_STATIC"]);
+        assert_eq!(
+            fmt.link_libraries,
+            vec!["fmt::base", "Threads::Threads", "m"]
+        );
+        // Build-tree paths are dropped
+        assert_eq!(
+            targets["fmt::base"].include_dirs,
+            vec!["/cache/fmt/install/include"]
+        );
+
+        // A target comes before what it links to
+        let env = HashMap::new();
+        let tc = toolchain(&env);
+        let mut built = Built::default();
+        add_target("fmt::fmt", &targets, &mut Vec::new(), &mut built, &tc);
+        assert_eq!(
+            built.link_flags,
+            vec![
+                "/cache/fmt/install/lib/libfmt.a",
+                "/cache/fmt/install/lib/libfmt-base.a"
+            ]
+        );
+        assert_eq!(built.libs, vec!["pthread", "m"]);
+        assert_eq!(built.cflags, vec!["-DFMT_STATIC"]);
+        assert_eq!(
+            built.include_dirs,
+            vec![PathBuf::from("/cache/fmt/install/include")]
+        );
+    }
+
+    #[test]
+    fn test_pkg_config_files() {
+        let dir = Path::new("/cache/glib/install/lib/pkgconfig");
+        let glib = parse_pc(
+            "prefix=/cache/glib/install\n\
+            libdir=${prefix}/lib\n\
+            includedir=${prefix}/include\n\n\
+            Name: GLib\n\
+            Version: 2.80.0\n\
+            Requires.private: libpcre2-8 >= 10.32\n\
+            Libs: -L${libdir} -lglib-2.0\n\
+            Libs.private: -pthread -lm\n\
+            Cflags: -I${includedir}/glib-2.0 -I${libdir}/glib-2.0/include\n",
+            dir,
+        );
+        assert_eq!(
+            glib.cflags,
+            vec![
+                "-I/cache/glib/install/include/glib-2.0",
+                "-I/cache/glib/install/lib/glib-2.0/include"
+            ]
+        );
+        assert_eq!(
+            glib.libs,
+            vec!["-L/cache/glib/install/lib", "-lglib-2.0", "-pthread", "-lm"]
+        );
+        assert_eq!(glib.requires, vec!["libpcre2-8"]);
+
+        let gobject = parse_pc(
+            "libdir=${pcfiledir}/..\nRequires: glib-2.0\nLibs: -L${libdir} -lgobject-2.0\n",
+            dir,
+        );
+        assert_eq!(
+            gobject.libs,
+            vec!["-L/cache/glib/install/lib/pkgconfig/..", "-lgobject-2.0"]
+        );
+
+        // Dependents link before what they require
+        let glib = PcFile {
+            requires: Vec::new(),
+            ..glib
+        };
+        let packages = BTreeMap::from([
+            ("glib-2.0".to_string(), glib),
+            ("gobject-2.0".to_string(), gobject),
+        ]);
+        let built = from_pkg_config("glib", &packages);
+        let libs: Vec<&String> = built
+            .link_flags
+            .iter()
+            .filter(|f| f.starts_with("-l"))
+            .collect();
+        assert_eq!(libs, vec!["-lgobject-2.0", "-lglib-2.0", "-lm"]);
+        assert_eq!(built.include_dirs.len(), 2);
+    }
+
+    #[test]
+    fn test_meson_and_autotools_args() {
+        let env = HashMap::new();
+        let cross = LibToolchain {
+            compiler: "/usr/bin/aarch64-linux-gnu-g++",
+            c_compiler: "/usr/bin/aarch64-linux-gnu-gcc",
+            triple: Some("aarch64-linux-gnu"),
+            target_flags: vec!["--sysroot=/opt/arm".to_string()],
+            ..toolchain(&env)
+        };
+        let ini = meson_cross_file("aarch64-linux-gnu", &cross);
+        assert!(ini.contains("c = '/usr/bin/aarch64-linux-gnu-gcc'"));
+        assert!(ini.contains("ar = '/usr/bin/aarch64-linux-gnu-ar'"));
+        assert!(ini.contains("c_args = ['--sysroot=/opt/arm']"));
+        assert!(ini.contains("cpu_family = 'aarch64'"));
+
+        let meson = MesonConfig {
+            options: BTreeMap::from([("tests".to_string(), toml::Value::Boolean(false))]),
+        };
+        let ext = ExternalBuild {
+            name: "glib".to_string(),
+            dir: PathBuf::from("/cache/glib"),
+            system: BuildSystem::Meson(meson.clone()),
+            pinned: true,
+        };
+        let args = meson_setup_args(
+            &ext,
+            &meson,
+            Path::new("/out/build"),
+            Path::new("/out/install"),
+            None,
+            &toolchain(&env),
+        );
+        assert!(args.contains(&"--buildtype=release".to_string()));
+        assert!(args.contains(&"--default-library=static".to_string()));
+        assert_eq!(args.last().unwrap(), "-Dtests=false");
+
+        let autotools = AutotoolsConfig {
+            args: vec!["--disable-docs".to_string()],
+        };
+        let args = configure_args(&autotools, Path::new("/out/install"), &cross);
+        assert!(args.contains(&"--disable-shared".to_string()));
+        assert!(args.contains(&"--host=aarch64-linux-gnu".to_string()));
+        assert_eq!(args.last().unwrap(), "--disable-docs");
+
+        // Autotools has no build type, so it gets the profile's flags
+        let env_vars = build_env(&cross, false);
+        let cflags = env_vars.iter().find(|(k, _)| k == "CFLAGS").unwrap();
+        assert!(cflags.1.ends_with("--sysroot=/opt/arm"));
+        assert!(env_vars.contains(&("CC".to_string(), cross.c_compiler.to_string())));
+    }
+
+    #[test]
+    fn test_cmake_configure_args() {
+        let mut options = BTreeMap::new();
+        options.insert("FMT_TEST".to_string(), toml::Value::Boolean(false));
+        options.insert("BUILD_SHARED_LIBS".to_string(), toml::Value::Boolean(true));
+        let cmake = CMakeConfig {
+            options,
+            targets: vec!["fmt".to_string()],
+        };
+        let ext = ExternalBuild {
+            name: "fmt".to_string(),
+            dir: PathBuf::from("/cache/fmt"),
+            system: BuildSystem::CMake(cmake.clone()),
+            pinned: true,
+        };
+        let env = HashMap::new();
+        let args = cmake_configure_args(
+            &ext,
+            &cmake,
+            Path::new("/out/build"),
+            Path::new("/out/install"),
+            &toolchain(&env),
+        );
+        assert!(args.contains(&"-DCMAKE_BUILD_TYPE=Release".to_string()));
+        assert!(args.contains(&"-DCMAKE_CXX_COMPILER=g++".to_string()));
+        assert!(args.contains(&"-DCMAKE_INSTALL_PREFIX=/out/install".to_string()));
+        assert!(args.contains(&"-DFMT_TEST=OFF".to_string()));
+        // User options come last and override the defaults
+        assert_eq!(args.last().unwrap(), "-DFMT_TEST=OFF");
+        let shared = args
+            .iter()
+            .rposition(|a| a.starts_with("-DBUILD_SHARED_LIBS"));
+        assert_eq!(args[shared.unwrap()], "-DBUILD_SHARED_LIBS=ON");
+    }
+}
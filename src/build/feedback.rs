@@ -1,22 +1,31 @@
+use super::diagnostics::{Diagnostic, Severity};
 use colored::*;
 
 pub struct FeedbackAnalyzer;
 
 impl FeedbackAnalyzer {
-    pub fn analyze(output: &str) -> Option<String> {
+    pub fn analyze(diags: &[Diagnostic]) -> Option<String> {
+        let errors: Vec<&Diagnostic> = diags
+            .iter()
+            .filter(|d| d.severity == Severity::Error)
+            .collect();
+        let has_code = |code: &str| errors.iter().any(|d| d.code.as_deref() == Some(code));
+        let has_message = |text: &str| errors.iter().any(|d| d.message.contains(text));
+
         // 1. Main function missing (Specific Linker Error)
-        if output.contains("undefined reference to `main'")
-            || output.contains("entry point must be defined")
+        if has_message("undefined reference to `main'")
+            || has_code("LNK1561")
+            || has_message("entry point must be defined")
         {
             return Some(format!(
                 "Your project is missing a {} function.\nEnsure you have a valid entry point or set {} if this is a library.",
                 "main()".bold().yellow(),
-                "bin = \"lib\"".bold().green()
+                "kind = \"staticlib\"".bold().green()
             ));
         }
 
         // 2. Generic Missing Library (Linker Error)
-        if output.contains("LNK2019") || output.contains("undefined reference to") {
+        if has_code("LNK2019") || has_code("LNK2001") || has_message("undefined reference to") {
             return Some(format!(
                 "It looks like a {} error.\nYou might be missing a library in {}.\nTry using {} to find the correct package.",
                 "Linker".bold().red(),
@@ -26,11 +35,11 @@ impl FeedbackAnalyzer {
         }
 
         // 3. Missing Header (Compiler Error)
-        if output.contains("fatal error: ") && output.contains("No such file or directory")
-            || output.contains("cannot open include file")
+        if has_code("C1083")
+            || has_message("No such file or directory")
+            || has_message("file not found")
+            || has_message("cannot open include file")
         {
-            // Extract the missing file name if possible?
-            // Regex is heavy, let's just give general advice for now.
             return Some(format!(
                 "It looks like a {} error.\nYou might be missing an include path or a dependency.\nCheck your {} dependencies or {} in cx.toml.",
                 "Missing Header".bold().red(),
@@ -46,11 +55,16 @@ impl FeedbackAnalyzer {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::build::diagnostics;
+
+    fn analyze(output: &str) -> Option<String> {
+        FeedbackAnalyzer::analyze(&diagnostics::parse(output))
+    }
 
     #[test]
     fn test_linker_error() {
         let err = "error LNK2019: unresolved external symbol foo";
-        let msg = FeedbackAnalyzer::analyze(err).unwrap();
+        let msg = analyze(err).unwrap();
         assert!(msg.contains("Linker error"));
         assert!(msg.contains("cx.toml"));
     }
@@ -58,14 +72,21 @@ mod tests {
     #[test]
     fn test_include_error() {
         let err = "fatal error: foo.h: No such file or directory";
-        let msg = FeedbackAnalyzer::analyze(err).unwrap();
+        let msg = analyze(err).unwrap();
         assert!(msg.contains("Missing Header"));
     }
 
     #[test]
     fn test_main_error() {
         let err = "undefined reference to `main'";
-        let msg = FeedbackAnalyzer::analyze(err).unwrap();
+        let msg = analyze(err).unwrap();
         assert!(msg.contains("missing a main() function"));
     }
+
+    #[test]
+    fn test_clang_missing_header() {
+        let err = "src/main.cpp:1:10: fatal error: 'fmt/core.h' file not found";
+        let msg = analyze(err).unwrap();
+        assert!(msg.contains("Missing Header"));
+    }
 }
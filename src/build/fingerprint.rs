@@ -0,0 +1,143 @@
+//! Build fingerprints for objects and linked artifacts.
+//!
+//! mtimes alone miss changes that don't touch a source file: new `flags` in
+//! cx.toml, a different compiler from `cx toolchain select`, or toggling
+//! `--sanitize`/`--lto`. Every object and artifact records a hash of the full
+//! command line, the toolchain fingerprint and the relevant environment in
+//! `.cx/build/fingerprints.json`; a mismatch forces a rebuild.
+
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Environment variables that change compiler or linker output
+const TRACKED_ENV: &[&str] = &[
+    "CPATH",
+    "C_INCLUDE_PATH",
+    "CPLUS_INCLUDE_PATH",
+    "LIBRARY_PATH",
+    "INCLUDE",
+    "LIB",
+    "LIBPATH",
+    "SDKROOT",
+    "MACOSX_DEPLOYMENT_TARGET",
+];
+
+/// Compute the fingerprint of a command.
+///
+/// `toolchain_env` holds the variables injected into the compiler process
+/// (e.g. MSVC's INCLUDE/LIB); they take precedence over the inherited ones.
+pub fn compute<S: AsRef<str>>(
+    args: &[S],
+    toolchain: &str,
+    toolchain_env: &HashMap<String, String>,
+) -> String {
+    let mut hasher = Sha256::new();
+    for arg in args {
+        hasher.update(arg.as_ref().as_bytes());
+        hasher.update([0]);
+    }
+    hasher.update(b"toolchain=");
+    hasher.update(toolchain.as_bytes());
+    hasher.update([0]);
+
+    let mut env: BTreeMap<&str, String> = BTreeMap::new();
+    for key in TRACKED_ENV {
+        if let Ok(value) = std::env::var(key) {
+            env.insert(key, value);
+        }
+    }
+    for (key, value) in toolchain_env {
+        env.insert(key, value.clone());
+    }
+    for (key, value) in env {
+        hasher.update(key.as_bytes());
+        hasher.update(b"=");
+        hasher.update(value.as_bytes());
+        hasher.update([0]);
+    }
+
+    format!("{:x}", hasher.finalize())
+}
+
+/// Fingerprints of everything produced by previous builds, keyed by output path
+#[derive(Debug, Default)]
+pub struct FingerprintStore {
+    path: PathBuf,
+    entries: BTreeMap<String, String>,
+}
+
+impl FingerprintStore {
+    /// Load the store, starting empty if it is missing or unreadable
+    pub fn load(path: &Path) -> Self {
+        let entries = fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            path: path.to_path_buf(),
+            entries,
+        }
+    }
+
+    /// Check whether `output` was built with `fingerprint`
+    pub fn is_fresh(&self, output: &Path, fingerprint: &str) -> bool {
+        self.entries
+            .get(&output.to_string_lossy().to_string())
+            .is_some_and(|stored| stored == fingerprint)
+    }
+
+    pub fn record(&mut self, output: &Path, fingerprint: String) {
+        self.entries
+            .insert(output.to_string_lossy().to_string(), fingerprint);
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&self.entries)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_changes_with_args_and_toolchain() {
+        let env = HashMap::new();
+        let base = compute(&["g++", "-c", "a.cpp"], "tc1", &env);
+        assert_eq!(base, compute(&["g++", "-c", "a.cpp"], "tc1", &env));
+        assert_ne!(
+            base,
+            compute(&["g++", "-c", "a.cpp", "-fsanitize=address"], "tc1", &env)
+        );
+        assert_ne!(base, compute(&["g++", "-c", "a.cpp"], "tc2", &env));
+
+        let mut msvc_env = HashMap::new();
+        msvc_env.insert("INCLUDE".to_string(), "C:\\sdk".to_string());
+        assert_ne!(base, compute(&["g++", "-c", "a.cpp"], "tc1", &msvc_env));
+    }
+
+    #[test]
+    fn test_store_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("cx_fp_test_{}", std::process::id()));
+        let path = dir.join("fingerprints.json");
+        let obj = Path::new(".cx/build/debug/obj/src/main.o");
+
+        let mut store = FingerprintStore::load(&path);
+        assert!(!store.is_fresh(obj, "abc"));
+        store.record(obj, "abc".to_string());
+        store.save().unwrap();
+
+        let reloaded = FingerprintStore::load(&path);
+        assert!(reloaded.is_fresh(obj, "abc"));
+        assert!(!reloaded.is_fresh(obj, "def"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
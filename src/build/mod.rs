@@ -15,14 +15,28 @@
 //!
 //! - [`core`] - Main build logic and parallel compilation
 //! - [`utils`] - Toolchain detection and helper functions
+//! - [`diagnostics`] - Structured compiler diagnostics and JSON output
+//! - `dep_libs` - Static libraries built from dependency sources
+//! - `external` - Dependencies built with CMake, Meson or Autotools
+//! - `modules` - C++20 module scanning, build order and BMIs
+//! - `fingerprint` - Rebuild detection for flag/toolchain/env changes
+//! - [`object_cache`] - Built-in content-addressed object cache
+//! - `unity` - Unity (jumbo) build batching
 //! - [`test`] - Test runner for C/C++ unit tests
 //! - [`arduino`] - Arduino/IoT build support
 
 pub mod arduino;
 mod clean;
 mod core;
+mod dep_libs;
+pub mod diagnostics;
+mod external;
 mod feedback;
+mod fingerprint;
+mod modules;
+pub mod object_cache;
 mod test;
+mod unity;
 pub mod utils;
 mod watcher;
 
@@ -0,0 +1,695 @@
+//! C++20 modules: dependency scanning, build order and BMI management.
+//!
+//! Sources are scanned for the modules they provide and import, using the
+//! compiler's P1689 output (`clang-scan-deps`, GCC `-fdeps-format=p1689r5`,
+//! MSVC `/scanDependencies`). Compilers without a scanner fall back to reading
+//! `module`/`import` declarations directly. Scan results are cached as `.ddi`
+//! files under `.cx/build/<profile>/modules`, and built module interfaces
+//! (BMIs) go to `.cx/build/<profile>/bmi`.
+//!
+//! The scan produces a [`ModuleGraph`], which splits the sources into waves:
+//! every file in a wave only imports modules built by earlier waves, so each
+//! wave still compiles in parallel.
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::LazyLock;
+
+/// Extensions of module interface units
+pub const INTERFACE_EXTENSIONS: &[&str] = &["cppm", "ixx", "mpp", "cxxm", "ccm"];
+
+pub fn is_interface_unit(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|e| INTERFACE_EXTENSIONS.contains(&e.to_string_lossy().as_ref()))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompilerFamily {
+    Gcc,
+    Clang,
+    Msvc,
+}
+
+impl CompilerFamily {
+    pub fn detect(compiler: &str) -> Self {
+        let name = Path::new(compiler)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if name.starts_with("clang-cl") || name == "cl" || name == "cl.exe" {
+            CompilerFamily::Msvc
+        } else if name.contains("clang") || name.starts_with("em++") {
+            CompilerFamily::Clang
+        } else {
+            CompilerFamily::Gcc
+        }
+    }
+
+    fn bmi_extension(self) -> &'static str {
+        match self {
+            CompilerFamily::Gcc => "gcm",
+            CompilerFamily::Clang => "pcm",
+            CompilerFamily::Msvc => "ifc",
+        }
+    }
+}
+
+/// Flags that must precede the source file so interface units with
+/// non-standard extensions are compiled as C++ modules
+pub fn language_args(family: CompilerFamily, src: &Path) -> Vec<String> {
+    if !is_interface_unit(src) {
+        return Vec::new();
+    }
+    match family {
+        CompilerFamily::Gcc => vec!["-x".into(), "c++".into()],
+        CompilerFamily::Clang => vec!["-x".into(), "c++-module".into()],
+        CompilerFamily::Msvc => vec!["/interface".into(), "/TP".into()],
+    }
+}
+
+/// What a translation unit exports and imports
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ModuleUnit {
+    /// Module (or partition, `name:part`) whose BMI this unit produces
+    pub provides: Option<String>,
+    /// Imported modules; header units are not tracked
+    pub requires: Vec<String>,
+}
+
+impl ModuleUnit {
+    pub fn is_empty(&self) -> bool {
+        self.provides.is_none() && self.requires.is_empty()
+    }
+}
+
+static COMMENTS: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)/\*.*?\*/|//[^\n]*").unwrap());
+
+static MODULE_DECL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^\s*(?P<export>export\s+)?module\s+(?P<name>[A-Za-z_][\w.]*)(?P<part>:[A-Za-z_][\w.]*)?\s*;",
+    )
+    .unwrap()
+});
+
+static IMPORT_DECL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^\s*(?:export\s+)?import\s+(?P<name>[A-Za-z_][\w.]*)?(?P<part>:[A-Za-z_][\w.]*)?\s*;",
+    )
+    .unwrap()
+});
+
+/// Read `module`/`import` declarations straight from the source text.
+///
+/// Used when the compiler has no P1689 scanner (e.g. GCC < 14). Doesn't run
+/// the preprocessor, so conditional imports are always counted.
+pub fn scan_source_text(content: &str) -> ModuleUnit {
+    let content = COMMENTS.replace_all(content, "");
+    let mut unit = ModuleUnit::default();
+    let mut current: Option<String> = None;
+
+    for line in content.lines() {
+        if let Some(caps) = MODULE_DECL.captures(line) {
+            let name = caps["name"].to_string();
+            match caps.name("part") {
+                Some(part) => unit.provides = Some(format!("{}{}", name, part.as_str())),
+                None if caps.name("export").is_some() => unit.provides = Some(name.clone()),
+                // Implementation unit: implicitly imports its interface
+                None => unit.requires.push(name.clone()),
+            }
+            current = Some(name);
+        } else if let Some(caps) = IMPORT_DECL.captures(line) {
+            let import = match (caps.name("name"), caps.name("part"), &current) {
+                (Some(name), None, _) => name.as_str().to_string(),
+                (None, Some(part), Some(module)) => format!("{}{}", module, part.as_str()),
+                _ => continue,
+            };
+            if !unit.requires.contains(&import) {
+                unit.requires.push(import);
+            }
+        }
+    }
+
+    unit
+}
+
+/// Whether a source declares or imports a module (cheap check, no compiler)
+pub fn uses_modules(path: &Path) -> bool {
+    is_interface_unit(path)
+        || fs::read_to_string(path).is_ok_and(|content| !scan_source_text(&content).is_empty())
+}
+
+#[derive(Deserialize)]
+struct P1689 {
+    rules: Vec<P1689Rule>,
+}
+
+#[derive(Deserialize)]
+struct P1689Rule {
+    #[serde(default)]
+    provides: Vec<P1689Module>,
+    #[serde(default)]
+    requires: Vec<P1689Module>,
+}
+
+#[derive(Deserialize)]
+struct P1689Module {
+    #[serde(rename = "logical-name")]
+    logical_name: String,
+    /// Set for header units (`import <vector>;`)
+    #[serde(rename = "lookup-method")]
+    lookup_method: Option<String>,
+}
+
+/// Parse a P1689 dependency file
+pub fn parse_p1689(content: &str) -> Result<ModuleUnit> {
+    let deps: P1689 = serde_json::from_str(content).context("Invalid P1689 dependency file")?;
+    let mut unit = ModuleUnit::default();
+    for rule in deps.rules {
+        if let Some(provided) = rule.provides.into_iter().next() {
+            unit.provides = Some(provided.logical_name);
+        }
+        unit.requires.extend(
+            rule.requires
+                .into_iter()
+                .filter(|m| m.lookup_method.is_none())
+                .map(|m| m.logical_name),
+        );
+    }
+    Ok(unit)
+}
+
+fn to_p1689(unit: &ModuleUnit, obj: &Path) -> serde_json::Value {
+    let provides: Vec<_> = unit
+        .provides
+        .iter()
+        .map(|name| json!({ "logical-name": name, "is-interface": true }))
+        .collect();
+    let requires: Vec<_> = unit
+        .requires
+        .iter()
+        .map(|name| json!({ "logical-name": name }))
+        .collect();
+    json!({
+        "version": 1,
+        "revision": 0,
+        "rules": [{
+            "primary-output": obj.to_string_lossy(),
+            "provides": provides,
+            "requires": requires,
+        }],
+    })
+}
+
+/// `clang-scan-deps` next to the compiler, keeping any version suffix
+fn clang_scan_deps(compiler: &str) -> PathBuf {
+    let path = Path::new(compiler);
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let driver = if name.contains("clang++") {
+        "clang++"
+    } else {
+        "clang"
+    };
+    let scanner = name.replacen(driver, "clang-scan-deps", 1);
+    path.with_file_name(scanner)
+}
+
+/// Run the compiler's P1689 scanner; `None` if it isn't available or fails
+fn run_scanner(
+    family: CompilerFamily,
+    args: &[String],
+    obj: &Path,
+    ddi: &Path,
+    env: &HashMap<String, String>,
+) -> Option<ModuleUnit> {
+    let flags: Vec<String> = super::object_cache::strip_outputs(args)
+        .into_iter()
+        .skip(1)
+        .filter(|a| a != "-c" && a != "/c")
+        .collect();
+    let null_device = if cfg!(windows) { "NUL" } else { "/dev/null" };
+    let depfile = ddi.with_extension("ddi.d");
+
+    let mut cmd = match family {
+        CompilerFamily::Clang => {
+            let mut cmd = Command::new(clang_scan_deps(&args[0]));
+            cmd.args(["-format=p1689", "--", &args[0]]);
+            cmd.args(&flags);
+            cmd.arg("-c").arg("-o").arg(obj);
+            cmd
+        }
+        CompilerFamily::Gcc => {
+            let mut cmd = Command::new(&args[0]);
+            cmd.args(&flags);
+            cmd.args(["-E", "-fmodules-ts", "-fdeps-format=p1689r5"]);
+            cmd.arg(format!("-fdeps-file={}", ddi.display()));
+            cmd.arg(format!("-fdeps-target={}", obj.display()));
+            cmd.arg("-MD").arg("-MF").arg(&depfile);
+            cmd.arg("-o").arg(null_device);
+            cmd
+        }
+        CompilerFamily::Msvc => {
+            let mut cmd = Command::new(&args[0]);
+            cmd.args(&flags);
+            cmd.arg("/scanDependencies").arg(ddi);
+            cmd
+        }
+    };
+    if !env.is_empty() {
+        cmd.envs(env);
+    }
+
+    let output = cmd.output().ok()?;
+    let _ = fs::remove_file(&depfile);
+    if !output.status.success() {
+        return None;
+    }
+    let content = match family {
+        CompilerFamily::Clang => String::from_utf8_lossy(&output.stdout).to_string(),
+        _ => fs::read_to_string(ddi).ok()?,
+    };
+    parse_p1689(&content).ok()
+}
+
+/// Scan one source, reusing the cached `.ddi` while it is newer than the source.
+///
+/// `args` is the full compile command (`args[0]` is the compiler).
+pub fn scan(
+    family: CompilerFamily,
+    args: &[String],
+    src: &Path,
+    obj: &Path,
+    ddi: &Path,
+    env: &HashMap<String, String>,
+) -> Result<ModuleUnit> {
+    let mtime = |p: &Path| fs::metadata(p).and_then(|m| m.modified()).ok();
+    if let (Some(ddi_time), Some(src_time)) = (mtime(ddi), mtime(src))
+        && ddi_time >= src_time
+        && let Ok(unit) = fs::read_to_string(ddi)
+            .map_err(anyhow::Error::from)
+            .and_then(|c| parse_p1689(&c))
+    {
+        return Ok(unit);
+    }
+
+    if let Some(parent) = ddi.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let unit = match run_scanner(family, args, obj, ddi, env) {
+        Some(unit) => unit,
+        None => scan_source_text(
+            &fs::read_to_string(src)
+                .with_context(|| format!("Failed to read {}", src.display()))?,
+        ),
+    };
+    fs::write(ddi, serde_json::to_string_pretty(&to_p1689(&unit, obj))?)?;
+    Ok(unit)
+}
+
+/// Sources of the standard library modules (`std`, `std.compat`) shipped with
+/// the toolchain, from libc++/libstdc++'s module manifest or MSVC's
+/// `VCToolsInstallDir\modules`
+pub fn std_library_modules(
+    family: CompilerFamily,
+    compiler: &str,
+    env: &HashMap<String, String>,
+) -> Vec<(String, PathBuf)> {
+    if family == CompilerFamily::Msvc {
+        let Some(tools) = env
+            .get("VCToolsInstallDir")
+            .cloned()
+            .or_else(|| std::env::var("VCToolsInstallDir").ok())
+        else {
+            return Vec::new();
+        };
+        let dir = Path::new(&tools).join("modules");
+        return [("std", "std.ixx"), ("std.compat", "std.compat.ixx")]
+            .into_iter()
+            .map(|(name, file)| (name.to_string(), dir.join(file)))
+            .filter(|(_, path)| path.exists())
+            .collect();
+    }
+
+    let query = match family {
+        CompilerFamily::Clang => "-print-library-module-manifest-path",
+        _ => "-print-file-name=libstdc++.modules.json",
+    };
+    let Some(manifest) = Command::new(compiler)
+        .arg(query)
+        .output()
+        .ok()
+        .map(|out| PathBuf::from(String::from_utf8_lossy(&out.stdout).trim()))
+        .filter(|path| path.is_absolute() && path.exists())
+    else {
+        return Vec::new();
+    };
+
+    let base = manifest.parent().unwrap_or(Path::new(".")).to_path_buf();
+    let Ok(value) = fs::read_to_string(&manifest)
+        .map_err(anyhow::Error::from)
+        .and_then(|c| Ok(serde_json::from_str::<serde_json::Value>(&c)?))
+    else {
+        return Vec::new();
+    };
+    value["modules"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|m| {
+            let name = m["logical-name"].as_str()?;
+            let source = m["source-path"].as_str()?;
+            Some((name.to_string(), base.join(source)))
+        })
+        .filter(|(_, path)| path.exists())
+        .collect()
+}
+
+/// Providers and importers of every module in the build
+pub struct ModuleGraph {
+    family: CompilerFamily,
+    bmi_dir: PathBuf,
+    units: BTreeMap<PathBuf, ModuleUnit>,
+    providers: BTreeMap<String, PathBuf>,
+}
+
+impl ModuleGraph {
+    /// Build the graph, rejecting duplicate providers and unknown imports
+    pub fn new(
+        family: CompilerFamily,
+        bmi_dir: PathBuf,
+        units: Vec<(PathBuf, ModuleUnit)>,
+    ) -> Result<Self> {
+        let mut providers = BTreeMap::new();
+        for (src, unit) in &units {
+            if let Some(name) = &unit.provides
+                && let Some(other) = providers.insert(name.clone(), src.clone())
+            {
+                anyhow::bail!(
+                    "Module '{}' is provided by both {} and {}",
+                    name,
+                    other.display(),
+                    src.display()
+                );
+            }
+        }
+
+        for (src, unit) in &units {
+            for name in &unit.requires {
+                if providers.contains_key(name) {
+                    continue;
+                }
+                if name == "std" || name == "std.compat" {
+                    anyhow::bail!(
+                        "{} uses 'import {};' but the toolchain ships no standard library modules \
+                         (needs libc++ 17+, libstdc++ 15+ or MSVC 17.5+)",
+                        src.display(),
+                        name
+                    );
+                }
+                anyhow::bail!(
+                    "Module '{}' imported by {} is not provided by any source file",
+                    name,
+                    src.display()
+                );
+            }
+        }
+
+        Ok(Self {
+            family,
+            bmi_dir,
+            units: units.into_iter().collect(),
+            providers,
+        })
+    }
+
+    pub fn is_module_unit(&self, src: &Path) -> bool {
+        self.units.get(src).is_some_and(|u| !u.is_empty())
+    }
+
+    pub fn bmi_path(&self, name: &str) -> PathBuf {
+        self.bmi_dir.join(format!(
+            "{}.{}",
+            name.replace(':', "-"),
+            self.family.bmi_extension()
+        ))
+    }
+
+    /// Every module `src` needs at compile time, including transitive imports
+    fn imports(&self, src: &Path) -> BTreeSet<String> {
+        let mut seen = BTreeSet::new();
+        let mut stack: Vec<String> = self
+            .units
+            .get(src)
+            .map(|u| u.requires.clone())
+            .unwrap_or_default();
+        while let Some(name) = stack.pop() {
+            if !seen.insert(name.clone()) {
+                continue;
+            }
+            if let Some(unit) = self.providers.get(&name).and_then(|p| self.units.get(p)) {
+                stack.extend(unit.requires.iter().cloned());
+            }
+        }
+        seen
+    }
+
+    /// Whether `src` imports, directly or not, the module `other` provides
+    pub fn depends_on(&self, src: &Path, other: &Path) -> bool {
+        self.units
+            .get(other)
+            .and_then(|unit| unit.provides.as_ref())
+            .is_some_and(|name| self.imports(src).contains(name))
+    }
+
+    /// Split `sources` into waves that can each be compiled in parallel
+    pub fn waves(&self, sources: &[PathBuf]) -> Result<Vec<Vec<PathBuf>>> {
+        let mut built: BTreeSet<&str> = BTreeSet::new();
+        let mut remaining: Vec<&PathBuf> = sources.iter().collect();
+        let mut waves = Vec::new();
+
+        while !remaining.is_empty() {
+            let (ready, blocked): (Vec<&PathBuf>, Vec<&PathBuf>) =
+                remaining.into_iter().partition(|src| {
+                    self.units
+                        .get(*src)
+                        .is_none_or(|u| u.requires.iter().all(|r| built.contains(r.as_str())))
+                });
+            if ready.is_empty() {
+                let files: Vec<String> = blocked.iter().map(|p| p.display().to_string()).collect();
+                anyhow::bail!("Module import cycle between: {}", files.join(", "));
+            }
+            for src in &ready {
+                if let Some(name) = self.units.get(*src).and_then(|u| u.provides.as_deref()) {
+                    built.insert(name);
+                }
+            }
+            waves.push(ready.into_iter().cloned().collect());
+            remaining = blocked;
+        }
+
+        Ok(waves)
+    }
+
+    /// Create the BMI directory (and GCC's module mapper)
+    pub fn prepare(&self) -> Result<()> {
+        fs::create_dir_all(&self.bmi_dir)?;
+        if self.family == CompilerFamily::Gcc {
+            let root = std::env::current_dir()?.join(&self.bmi_dir);
+            let mut mapper = format!("$root {}\n", root.display());
+            for name in self.providers.keys() {
+                let bmi = self.bmi_path(name);
+                let file = bmi.file_name().unwrap_or_default().to_string_lossy();
+                mapper.push_str(&format!("{} {}\n", name, file));
+            }
+            fs::write(self.mapper_path(), mapper)?;
+        }
+        Ok(())
+    }
+
+    fn mapper_path(&self) -> PathBuf {
+        self.bmi_dir.join("module.map")
+    }
+
+    /// Flags that let `src` emit its BMI and find the BMIs it imports
+    pub fn compile_args(&self, src: &Path) -> Vec<String> {
+        let Some(unit) = self.units.get(src).filter(|u| !u.is_empty()) else {
+            return Vec::new();
+        };
+        let mut args = Vec::new();
+        match self.family {
+            CompilerFamily::Gcc => {
+                args.push("-fmodules-ts".to_string());
+                args.push(format!("-fmodule-mapper={}", self.mapper_path().display()));
+            }
+            CompilerFamily::Clang => {
+                if let Some(name) = &unit.provides {
+                    args.push(format!("-fmodule-output={}", self.bmi_path(name).display()));
+                }
+                for name in self.imports(src) {
+                    args.push(format!(
+                        "-fmodule-file={}={}",
+                        name,
+                        self.bmi_path(&name).display()
+                    ));
+                }
+            }
+            CompilerFamily::Msvc => {
+                if let Some(name) = &unit.provides {
+                    args.push("/ifcOutput".to_string());
+                    args.push(self.bmi_path(name).display().to_string());
+                }
+                for name in self.imports(src) {
+                    args.push("/reference".to_string());
+                    args.push(format!("{}={}", name, self.bmi_path(&name).display()));
+                }
+            }
+        }
+        args
+    }
+
+    /// Whether `src` must be recompiled because its BMI is missing or an
+    /// imported BMI is newer than `obj`
+    pub fn needs_rebuild(&self, src: &Path, obj: &Path) -> bool {
+        let Some(unit) = self.units.get(src) else {
+            return false;
+        };
+        if let Some(name) = &unit.provides
+            && !self.bmi_path(name).exists()
+        {
+            return true;
+        }
+        let Some(obj_time) = fs::metadata(obj).and_then(|m| m.modified()).ok() else {
+            return true;
+        };
+        self.imports(src).iter().any(|name| {
+            fs::metadata(self.bmi_path(name))
+                .and_then(|m| m.modified())
+                .map_or(true, |t| t > obj_time)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit(provides: Option<&str>, requires: &[&str]) -> ModuleUnit {
+        ModuleUnit {
+            provides: provides.map(String::from),
+            requires: requires.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_scan_source_text() {
+        let src = "module;\n#include <cstdio>\n// import commented;\nexport module math;\n\
+                   export import :ops;\nimport util;\nimport <vector>;\n";
+        assert_eq!(
+            scan_source_text(src),
+            unit(Some("math"), &["math:ops", "util"])
+        );
+        assert_eq!(
+            scan_source_text("module math;\nimport std;\n"),
+            unit(None, &["math", "std"])
+        );
+        assert_eq!(
+            scan_source_text("module math:ops;\n"),
+            unit(Some("math:ops"), &[])
+        );
+        assert!(scan_source_text("#include <vector>\nint main() {}\n").is_empty());
+    }
+
+    #[test]
+    fn test_parse_p1689() {
+        let ddi = r#"{"version":1,"revision":0,"rules":[{"primary-output":"a.o",
+            "provides":[{"logical-name":"a","is-interface":true}],
+            "requires":[{"logical-name":"b"},{"logical-name":"<vector>","lookup-method":"include-angle"}]}]}"#;
+        assert_eq!(parse_p1689(ddi).unwrap(), unit(Some("a"), &["b"]));
+
+        let ours = to_p1689(&unit(Some("x:y"), &["z"]), Path::new("x.o")).to_string();
+        assert_eq!(parse_p1689(&ours).unwrap(), unit(Some("x:y"), &["z"]));
+    }
+
+    #[test]
+    fn test_graph_waves_and_args() {
+        let graph = ModuleGraph::new(
+            CompilerFamily::Clang,
+            PathBuf::from("bmi"),
+            vec![
+                (PathBuf::from("main.cpp"), unit(None, &["app"])),
+                (PathBuf::from("app.cppm"), unit(Some("app"), &["core"])),
+                (PathBuf::from("core.cppm"), unit(Some("core"), &[])),
+            ],
+        )
+        .unwrap();
+        let sources = vec![
+            PathBuf::from("main.cpp"),
+            PathBuf::from("app.cppm"),
+            PathBuf::from("core.cppm"),
+            PathBuf::from("plain.c"),
+        ];
+        let waves = graph.waves(&sources).unwrap();
+        assert_eq!(
+            waves,
+            vec![
+                vec![PathBuf::from("core.cppm"), PathBuf::from("plain.c")],
+                vec![PathBuf::from("app.cppm")],
+                vec![PathBuf::from("main.cpp")],
+            ]
+        );
+
+        let bmi = |n: &str| Path::new("bmi").join(n).display().to_string();
+        assert_eq!(
+            graph.compile_args(Path::new("main.cpp")),
+            vec![
+                format!("-fmodule-file=app={}", bmi("app.pcm")),
+                format!("-fmodule-file=core={}", bmi("core.pcm")),
+            ]
+        );
+        assert!(graph.compile_args(Path::new("plain.c")).is_empty());
+
+        // main.cpp reaches core through app; plain.c imports nothing
+        let depends = |src: &str, other: &str| graph.depends_on(Path::new(src), Path::new(other));
+        assert!(depends("main.cpp", "core.cppm") && depends("app.cppm", "core.cppm"));
+        assert!(!depends("core.cppm", "app.cppm") && !depends("plain.c", "core.cppm"));
+    }
+
+    #[test]
+    fn test_graph_errors() {
+        let missing = ModuleGraph::new(
+            CompilerFamily::Gcc,
+            PathBuf::from("bmi"),
+            vec![(PathBuf::from("a.cpp"), unit(None, &["nope"]))],
+        );
+        assert!(missing.is_err());
+
+        let cycle = ModuleGraph::new(
+            CompilerFamily::Gcc,
+            PathBuf::from("bmi"),
+            vec![
+                (PathBuf::from("a.cppm"), unit(Some("a"), &["b"])),
+                (PathBuf::from("b.cppm"), unit(Some("b"), &["a"])),
+            ],
+        )
+        .unwrap();
+        let sources = vec![PathBuf::from("a.cppm"), PathBuf::from("b.cppm")];
+        assert!(cycle.waves(&sources).is_err());
+    }
+
+    #[test]
+    fn test_clang_scan_deps_name() {
+        assert_eq!(
+            clang_scan_deps("/usr/bin/clang++-17"),
+            PathBuf::from("/usr/bin/clang-scan-deps-17")
+        );
+        assert_eq!(clang_scan_deps("clang"), PathBuf::from("clang-scan-deps"));
+    }
+}
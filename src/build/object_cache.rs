@@ -0,0 +1,664 @@
+//! Built-in content-addressed object cache.
+//!
+//! Objects are stored in `~/.cx/cache/objects/<xx>/<key>.<o|obj>`. Lookups work
+//! like ccache's direct mode, so nothing is preprocessed: the source, the
+//! toolchain fingerprint and the compiler flags hash to a manifest listing the
+//! headers each cached build included (taken from its dependency file), and an
+//! object's key adds the contents of those headers. Unlike ccache/sccache this
+//! needs nothing on PATH, so every machine and CI runner gets the same
+//! behaviour. Dependency files (`.d` / MSVC `.json`) are cached next to the
+//! object so incremental checks keep working after a hit.
+//!
+//! Hits refresh an entry's modification time, so it doubles as the last use.
+//! Once a build has stored a tenth of [`max_size`] (`CX_CACHE_MAX_SIZE`,
+//! default 5 GB), or an hour after the last trim, the least recently used
+//! entries are evicted until the cache fits; `cx cache clean --objects
+//! --older-than/--max-size` prunes on demand.
+
+use crate::status;
+use anyhow::Result;
+use colored::*;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime};
+
+/// Size the cache is trimmed to after a build, unless `CX_CACHE_MAX_SIZE` says otherwise
+pub const DEFAULT_MAX_SIZE: u64 = 5 * 1024 * 1024 * 1024;
+
+/// Builds storing less than a tenth of the cap trim the cache at most this often
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Header sets remembered per manifest (e.g. one per branch being switched between)
+const MAX_MANIFEST_ENTRIES: usize = 8;
+
+/// Location of the object cache (`~/.cx/cache/objects`)
+pub fn cache_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".cx").join("cache").join("objects"))
+}
+
+/// Number of cached objects and their total size in bytes
+pub fn stats() -> (usize, u64) {
+    let Some(dir) = cache_dir() else {
+        return (0, 0);
+    };
+    let mut count = 0;
+    let mut size = 0;
+    for entry in walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+    {
+        if entry
+            .path()
+            .extension()
+            .is_some_and(|e| e == "o" || e == "obj")
+            && let Ok(meta) = entry.metadata()
+        {
+            count += 1;
+            size += meta.len();
+        }
+    }
+    (count, size)
+}
+
+/// Remove every cached object, returning how many were removed
+pub fn clear() -> Result<usize> {
+    let (count, _) = stats();
+    if let Some(dir) = cache_dir()
+        && dir.exists()
+    {
+        fs::remove_dir_all(&dir)?;
+    }
+    Ok(count)
+}
+
+/// Cap on the cache size: `CX_CACHE_MAX_SIZE` (e.g. `500M`, `10G`), else
+/// [`DEFAULT_MAX_SIZE`]. An invalid value is reported and ignored.
+pub fn max_size() -> u64 {
+    match std::env::var("CX_CACHE_MAX_SIZE") {
+        Ok(value) if !value.is_empty() => parse_size(&value).unwrap_or_else(|| {
+            status!(
+                "{} Warning: ignoring invalid CX_CACHE_MAX_SIZE '{}' (expected e.g. 500M or 10G)",
+                "!".yellow(),
+                value
+            );
+            DEFAULT_MAX_SIZE
+        }),
+        _ => DEFAULT_MAX_SIZE,
+    }
+}
+
+/// Parse a size like `1024`, `500K`, `500M`, `10G` or `10GB` into bytes
+pub fn parse_size(value: &str) -> Option<u64> {
+    let value = value.trim().to_uppercase();
+    let value = value.strip_suffix('B').unwrap_or(&value);
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => value.split_at(i),
+        None => (value, ""),
+    };
+    let unit: u64 = match unit.trim() {
+        "" => 1,
+        "K" => 1024,
+        "M" => 1024 * 1024,
+        "G" => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+    number.parse::<u64>().ok()?.checked_mul(unit)
+}
+
+/// Parse an age like `30d`, `12h` or `45m` (a bare number is days)
+pub fn parse_age(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => value.split_at(i),
+        None => (value, "d"),
+    };
+    let seconds: u64 = match unit {
+        "d" => 24 * 60 * 60,
+        "h" => 60 * 60,
+        "m" => 60,
+        _ => return None,
+    };
+    let seconds = number.parse::<u64>().ok()?.checked_mul(seconds)?;
+    Some(Duration::from_secs(seconds))
+}
+
+/// A cached object and the files stored next to it
+struct Entry {
+    files: Vec<PathBuf>,
+    size: u64,
+    last_used: SystemTime,
+}
+
+/// Entries of the cache at `dir`, grouped by key
+fn entries(dir: &Path) -> Vec<Entry> {
+    let mut entries: HashMap<PathBuf, Entry> = HashMap::new();
+    for file in walkdir::WalkDir::new(dir)
+        .min_depth(2)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+    {
+        let Ok(meta) = file.metadata() else {
+            continue;
+        };
+        let name = file.file_name().to_string_lossy();
+        let key = file
+            .path()
+            .with_file_name(name.split('.').next().unwrap_or_default());
+        let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+        let entry = entries.entry(key).or_insert(Entry {
+            files: Vec::new(),
+            size: 0,
+            last_used: modified,
+        });
+        entry.files.push(file.into_path());
+        entry.size += meta.len();
+        entry.last_used = entry.last_used.max(modified);
+    }
+    entries.into_values().collect()
+}
+
+/// Remove entries not used since `older_than` before `now`, then the least
+/// recently used ones until the cache fits in `max_size`. Returns the number
+/// of objects removed and the bytes freed.
+pub fn prune_dir(
+    dir: &Path,
+    older_than: Option<Duration>,
+    max_size: Option<u64>,
+    now: SystemTime,
+) -> (usize, u64) {
+    let mut entries = entries(dir);
+    // Most recently used first, so eviction pops from the end
+    entries.sort_by_key(|e| std::cmp::Reverse(e.last_used));
+    let mut total: u64 = entries.iter().map(|e| e.size).sum();
+    let cutoff = older_than.and_then(|age| now.checked_sub(age));
+
+    let mut removed = 0;
+    let mut freed = 0;
+    while let Some(entry) = entries.pop() {
+        let expired = cutoff.is_some_and(|cutoff| entry.last_used < cutoff);
+        let over = max_size.is_some_and(|max| total > max);
+        if !expired && !over {
+            break;
+        }
+        for file in &entry.files {
+            let _ = fs::remove_file(file);
+        }
+        total -= entry.size;
+        freed += entry.size;
+        removed += 1;
+    }
+    (removed, freed)
+}
+
+/// [`prune_dir`] on the object cache
+pub fn prune(older_than: Option<Duration>, max_size: Option<u64>) -> (usize, u64) {
+    match cache_dir() {
+        Some(dir) if dir.exists() => prune_dir(&dir, older_than, max_size, SystemTime::now()),
+        _ => (0, 0),
+    }
+}
+
+/// Dependency file written next to an object by the compiler
+fn dep_file(obj_path: &Path, is_msvc: bool) -> PathBuf {
+    if is_msvc {
+        PathBuf::from(format!("{}.json", obj_path.display()))
+    } else {
+        obj_path.with_extension("d")
+    }
+}
+
+/// Drop the output arguments (`-o`, `-MF`, `/Fo`, `/sourceDependencies`) so the
+/// remaining flags don't depend on where the object is written
+pub(super) fn strip_outputs(args: &[String]) -> Vec<String> {
+    let mut stripped = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-o" | "-MF" | "/sourceDependencies" => {
+                iter.next();
+            }
+            "-MMD" => {}
+            _ if arg.starts_with("/Fo") => {}
+            _ => stripped.push(arg.clone()),
+        }
+    }
+    stripped
+}
+
+/// Files a compile read, from the dependency file it wrote next to `obj_path`
+fn included_files(obj_path: &Path, is_msvc: bool) -> Option<Vec<String>> {
+    let content = fs::read_to_string(dep_file(obj_path, is_msvc)).ok()?;
+    if !is_msvc {
+        return Some(parse_make_deps(&content));
+    }
+    // {"Data": {"Source": "...", "Includes": ["..."]}}
+    let json: serde_json::Value = serde_json::from_str(&content).ok()?;
+    json.pointer("/Data/Includes")?
+        .as_array()?
+        .iter()
+        .map(|include| include.as_str().map(String::from))
+        .collect()
+}
+
+/// Prerequisites of a Makefile rule as written by `-MMD` (`a.o: a.cpp a.h \`)
+fn parse_make_deps(content: &str) -> Vec<String> {
+    let Some((_, deps)) = content.split_once(": ") else {
+        return Vec::new();
+    };
+    let mut files = Vec::new();
+    let mut current = String::new();
+    let mut chars = deps.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            // Escaped space inside a path
+            ('\\', Some(' ')) => {
+                current.push(' ');
+                chars.next();
+            }
+            // Line continuation
+            ('\\', Some('\n' | '\r')) => {}
+            (c, _) if c.is_whitespace() => {
+                if !current.is_empty() {
+                    files.push(std::mem::take(&mut current));
+                }
+            }
+            (c, _) => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        files.push(current);
+    }
+    files
+}
+
+fn hash_file(path: &str) -> Option<String> {
+    let bytes = fs::read(path).ok()?;
+    Some(format!("{:x}", Sha256::digest(&bytes)))
+}
+
+/// Key of the object built from a manifest's source with these headers
+fn object_key(manifest_key: &str, headers: &[(String, String)]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(manifest_key.as_bytes());
+    for (path, hash) in headers {
+        hasher.update([0]);
+        hasher.update(path.as_bytes());
+        hasher.update([0]);
+        hasher.update(hash.as_bytes());
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// Record a use of a cache file for LRU eviction
+fn touch(path: &Path) {
+    if let Ok(file) = fs::File::options().write(true).open(path) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+}
+
+/// Header paths and content hashes of one cached build of a source
+type HeaderSet = Vec<(String, String)>;
+
+/// Shared handle to the object cache for one build
+pub struct ObjectCache {
+    dir: PathBuf,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+    stored: AtomicU64,
+}
+
+impl ObjectCache {
+    pub fn open() -> Option<Self> {
+        let dir = cache_dir()?;
+        fs::create_dir_all(&dir).ok()?;
+        Some(Self::at(dir))
+    }
+
+    fn at(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+            stored: AtomicU64::new(0),
+        }
+    }
+
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    pub fn misses(&self) -> usize {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Compute the manifest key for compiling `src_path` with `args` (`args[0]`
+    /// is the compiler).
+    ///
+    /// Returns `None` if the source can't be read; the real compile will then
+    /// report the error.
+    pub fn key(
+        &self,
+        src_path: &Path,
+        args: &[String],
+        toolchain: &str,
+        toolchain_env: &HashMap<String, String>,
+    ) -> Option<String> {
+        let source = fs::read(src_path).ok()?;
+
+        let mut hasher = Sha256::new();
+        hasher.update(&source);
+        hasher.update([0]);
+        hasher.update(toolchain.as_bytes());
+        for arg in strip_outputs(args) {
+            hasher.update([0]);
+            hasher.update(arg.as_bytes());
+        }
+        // INCLUDE and friends decide which headers are found
+        for (name, value) in toolchain_env.iter().collect::<BTreeMap<_, _>>() {
+            hasher.update([0]);
+            hasher.update(name.as_bytes());
+            hasher.update(b"=");
+            hasher.update(value.as_bytes());
+        }
+        Some(format!("{:x}", hasher.finalize()))
+    }
+
+    fn entry_path(&self, key: &str, obj_path: &Path) -> PathBuf {
+        let ext = obj_path
+            .extension()
+            .map(|e| e.to_string_lossy().to_string())
+            .unwrap_or_else(|| "o".to_string());
+        self.dir.join(&key[..2]).join(format!("{}.{}", key, ext))
+    }
+
+    fn manifest_path(&self, key: &str) -> PathBuf {
+        self.dir.join(&key[..2]).join(format!("{}.manifest", key))
+    }
+
+    fn read_manifest(&self, key: &str) -> Vec<HeaderSet> {
+        fs::read_to_string(self.manifest_path(key))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Restore the object (and its dependency file) cached for `key` whose
+    /// headers are unchanged to `obj_path`
+    pub fn fetch(&self, key: &str, obj_path: &Path, is_msvc: bool) -> bool {
+        // Headers shared by several recorded builds are only hashed once
+        let mut hashes: HashMap<String, Option<String>> = HashMap::new();
+        let restored = self.read_manifest(key).iter().any(|headers| {
+            let unchanged = headers.iter().all(|(path, hash)| {
+                hashes
+                    .entry(path.clone())
+                    .or_insert_with(|| hash_file(path))
+                    .as_deref()
+                    == Some(hash.as_str())
+            });
+            unchanged && self.restore(&object_key(key, headers), obj_path, is_msvc)
+        });
+
+        if restored {
+            touch(&self.manifest_path(key));
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+        }
+        restored
+    }
+
+    fn restore(&self, object_key: &str, obj_path: &Path, is_msvc: bool) -> bool {
+        let entry = self.entry_path(object_key, obj_path);
+        let restored = entry.exists()
+            && fs::copy(&entry, obj_path).is_ok()
+            && restore_dep_file(&dep_file(&entry, is_msvc), obj_path, is_msvc).is_ok();
+        if restored {
+            touch(&entry);
+        }
+        restored
+    }
+
+    /// Store a freshly compiled object under the headers its dependency file
+    /// lists. Failures are ignored: the cache is only an optimization.
+    pub fn store(&self, key: &str, obj_path: &Path, is_msvc: bool) {
+        let Some(files) = included_files(obj_path, is_msvc) else {
+            return;
+        };
+        let headers: Option<HeaderSet> = files
+            .into_iter()
+            .map(|path| hash_file(&path).map(|hash| (path, hash)))
+            .collect();
+        let Some(headers) = headers else {
+            return;
+        };
+
+        let entry = self.entry_path(&object_key(key, &headers), obj_path);
+        if write_atomic(obj_path, &entry).is_err() {
+            return;
+        }
+        let dep = dep_file(obj_path, is_msvc);
+        let _ = write_atomic(&dep, &dep_file(&entry, is_msvc));
+
+        // Most recent first, so the current headers are checked before older ones
+        let mut manifest = self.read_manifest(key);
+        manifest.retain(|recorded| *recorded != headers);
+        manifest.insert(0, headers);
+        manifest.truncate(MAX_MANIFEST_ENTRIES);
+        let manifest_path = self.manifest_path(key);
+        let tmp = manifest_path.with_extension(format!("tmp{}", std::process::id()));
+        if let Ok(json) = serde_json::to_string(&manifest)
+            && fs::create_dir_all(self.dir.join(&key[..2])).is_ok()
+            && fs::write(&tmp, json).is_ok()
+        {
+            let _ = fs::rename(&tmp, &manifest_path);
+        }
+
+        let size = fs::metadata(&entry).map(|m| m.len()).unwrap_or(0);
+        self.stored.fetch_add(size, Ordering::Relaxed);
+    }
+
+    /// Trim the cache to `max_size` once this build stored a tenth of it, or
+    /// if it stored anything and the last trim was over [`PRUNE_INTERVAL`] ago.
+    /// Returns the number of objects evicted.
+    pub fn prune_if_due(&self, max_size: u64) -> usize {
+        let stored = self.stored.load(Ordering::Relaxed);
+        if stored == 0 {
+            return 0;
+        }
+        let stamp = self.dir.join("last-prune");
+        let trimmed_recently = fs::metadata(&stamp)
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|time| time.elapsed().ok())
+            .is_some_and(|age| age < PRUNE_INTERVAL);
+        if trimmed_recently && stored < max_size / 10 {
+            return 0;
+        }
+        if fs::File::create(&stamp).is_ok() {
+            touch(&stamp);
+        }
+        prune_dir(&self.dir, None, Some(max_size), SystemTime::now()).0
+    }
+}
+
+/// Copy via a temporary file so concurrent builds never see partial entries
+fn write_atomic(from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = to.with_extension(format!("tmp{}", std::process::id()));
+    fs::copy(from, &tmp)?;
+    fs::rename(&tmp, to)?;
+    Ok(())
+}
+
+/// Write the cached dependency file for `obj_path`, pointing GCC-style rules
+/// at the restored object
+fn restore_dep_file(cached: &Path, obj_path: &Path, is_msvc: bool) -> Result<()> {
+    if !cached.exists() {
+        return Ok(());
+    }
+    let target = dep_file(obj_path, is_msvc);
+    let content = fs::read_to_string(cached)?;
+    let content = match content.split_once(':') {
+        Some((_, deps)) if !is_msvc => format!("{}:{}", obj_path.display(), deps),
+        _ => content,
+    };
+    fs::write(target, content)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_make_deps() {
+        let content =
+            "obj/a.o: src/a.cpp include/a.h \\\n  include/my\\ dir/b.h \\\r\n C:\\inc\\c.h\n";
+        assert_eq!(
+            parse_make_deps(content),
+            args(&[
+                "src/a.cpp",
+                "include/a.h",
+                "include/my dir/b.h",
+                "C:\\inc\\c.h"
+            ])
+        );
+        assert!(parse_make_deps("").is_empty());
+    }
+
+    #[test]
+    fn test_store_and_fetch_follow_headers() {
+        let dir = std::env::temp_dir().join(format!("cx_objcache_direct_{}", std::process::id()));
+        let cache = ObjectCache::at(dir.join("cache"));
+        fs::create_dir_all(&dir).unwrap();
+        let header = dir.join("a.h").to_string_lossy().to_string();
+        let obj = dir.join("a.o");
+        let compile = |header_text: &str, object: &str| {
+            fs::write(&header, header_text).unwrap();
+            fs::write(&obj, object).unwrap();
+            fs::write(dir.join("a.d"), format!("a.o: {}\n", header)).unwrap();
+        };
+
+        compile("int v1;", "object v1");
+        assert!(!cache.fetch("abcd", &obj, false));
+        cache.store("abcd", &obj, false);
+        compile("int v2;", "object v2");
+        assert!(!cache.fetch("abcd", &obj, false));
+        cache.store("abcd", &obj, false);
+
+        // Both header versions are remembered; each restores its own object
+        fs::write(&header, "int v1;").unwrap();
+        assert!(cache.fetch("abcd", &obj, false));
+        assert_eq!(fs::read_to_string(&obj).unwrap(), "object v1");
+        fs::write(&header, "int v2;").unwrap();
+        assert!(cache.fetch("abcd", &obj, false));
+        assert_eq!(fs::read_to_string(&obj).unwrap(), "object v2");
+
+        // A header that changed again misses
+        fs::write(&header, "int v3;").unwrap();
+        assert!(!cache.fetch("abcd", &obj, false));
+        assert_eq!((cache.hits(), cache.misses()), (2, 3));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_prune_if_due_skips_small_recent_builds() {
+        let dir = std::env::temp_dir().join(format!("cx_objcache_due_{}", std::process::id()));
+        let cache = ObjectCache::at(dir.clone());
+        let old = dir.join("aa").join("aa11.o");
+        fs::create_dir_all(old.parent().unwrap()).unwrap();
+        fs::write(&old, vec![0u8; 100]).unwrap();
+
+        // Nothing stored yet
+        assert_eq!(cache.prune_if_due(10), 0);
+        assert!(old.exists());
+
+        // Stored a tenth of the cap: trims and records the time
+        cache.stored.store(1, Ordering::Relaxed);
+        assert_eq!(cache.prune_if_due(10), 1);
+        assert!(!old.exists() && dir.join("last-prune").exists());
+
+        // Trimmed recently and stored little: skipped
+        fs::write(&old, vec![0u8; 100]).unwrap();
+        assert_eq!(cache.prune_if_due(1000), 0);
+        assert!(old.exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_parse_size_and_age() {
+        assert_eq!(parse_size("1024"), Some(1024));
+        assert_eq!(parse_size("500M"), Some(500 * 1024 * 1024));
+        assert_eq!(parse_size("10gb"), Some(10 * 1024 * 1024 * 1024));
+        assert_eq!(parse_size("ten"), None);
+        assert_eq!(parse_age("30d"), Some(Duration::from_secs(30 * 86400)));
+        assert_eq!(parse_age("12h"), Some(Duration::from_secs(12 * 3600)));
+        assert_eq!(parse_age("7"), Some(Duration::from_secs(7 * 86400)));
+        assert_eq!(parse_age("1w"), None);
+        assert_eq!(parse_age("18446744073709551615d"), None);
+    }
+
+    #[test]
+    fn test_prune_evicts_least_recently_used() {
+        let dir = std::env::temp_dir().join(format!("cx_objcache_prune_{}", std::process::id()));
+        let now = SystemTime::now();
+        let day = Duration::from_secs(86400);
+        // (key, age in days): each entry is a 100-byte object plus its .d file
+        for (key, age) in [("aa11", 1), ("aa22", 10), ("bb33", 40), ("bb44", 3)] {
+            let sub = dir.join(&key[..2]);
+            fs::create_dir_all(&sub).unwrap();
+            for (file, len) in [(format!("{}.o", key), 100), (format!("{}.d", key), 10)] {
+                let path = sub.join(file);
+                fs::write(&path, vec![0u8; len]).unwrap();
+                let f = fs::File::options().write(true).open(&path).unwrap();
+                f.set_modified(now - day * age).unwrap();
+            }
+        }
+        let exists = |key: &str| dir.join(&key[..2]).join(format!("{}.o", key)).exists();
+
+        // Age: only the 40-day-old entry goes, with its dependency file
+        assert_eq!(prune_dir(&dir, Some(day * 30), None, now), (1, 110));
+        assert!(!exists("bb33"));
+        assert!(!dir.join("bb").join("bb33.d").exists());
+
+        // Size: the oldest remaining entries go until 2 fit
+        assert_eq!(prune_dir(&dir, None, Some(250), now), (1, 110));
+        assert!(!exists("aa22"));
+        assert!(exists("aa11") && exists("bb44"));
+
+        // Nothing to do
+        assert_eq!(prune_dir(&dir, Some(day * 30), Some(250), now), (0, 0));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_restore_dep_file_retargets_object() {
+        let dir = std::env::temp_dir().join(format!("cx_objcache_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let cached = dir.join("abc.d");
+        fs::write(&cached, "/elsewhere/a.o: src/a.cpp include/a.h\n").unwrap();
+
+        let obj = dir.join("a.o");
+        restore_dep_file(&cached, &obj, false).unwrap();
+        let restored = fs::read_to_string(dir.join("a.d")).unwrap();
+        assert_eq!(
+            restored,
+            format!("{}: src/a.cpp include/a.h\n", obj.display())
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
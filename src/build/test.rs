@@ -8,25 +8,34 @@
 //! - Auto-links project sources for testing internals
 //! - Parallel test compilation
 //! - Test filtering with `--filter`
-
-use super::utils::{get_compiler, get_std_flag_gcc, get_std_flag_msvc, load_config};
-use crate::config::CxConfig;
+//! - JSON results with `--message-format=json`
+
+use super::diagnostics::{self, MessageFormat};
+use super::utils::{
+    get_compiler, get_object_path, get_std_flag_gcc, get_std_flag_msvc, load_config,
+};
+use crate::config::{ArtifactKind, CxConfig};
+use crate::status;
 use anyhow::Result;
 use colored::*;
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
 use rayon::prelude::*;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use walkdir::WalkDir;
 
-pub fn run_tests(filter: Option<String>) -> Result<()> {
+pub fn run_tests(filter: Option<String>, message_format: MessageFormat) -> Result<()> {
+    let json_output = message_format.is_json();
+    diagnostics::set_message_format(message_format);
+
     // Load config or default
-    let config = load_config().unwrap_or_else(|_| CxConfig {
+    let mut config = load_config().unwrap_or_else(|_| CxConfig {
         package: crate::config::PackageConfig {
             name: "test_runner".into(),
             version: "0.0.0".into(),
             edition: "c++20".into(),
+            c_edition: None,
         },
         ..Default::default()
     });
@@ -39,50 +48,97 @@ pub fn run_tests(filter: Option<String>) -> Result<()> {
     let test_dir = Path::new(&test_dir_str);
 
     if !test_dir.exists() {
-        println!("{} No {}/ directory found.", "!".yellow(), test_dir_str);
+        status!("{} No {}/ directory found.", "!".yellow(), test_dir_str);
         return Ok(());
     }
 
     let mut include_paths = Vec::new();
     let mut extra_cflags = Vec::new();
     let mut dep_libs = Vec::new();
+    let mut dep_lib_names = Vec::new();
+
+    let features = crate::features::apply_project(&mut config)?;
+    let compiler = get_compiler(&config, true);
+    let is_msvc = compiler.contains("cl.exe") || compiler == "cl";
+    let (define, link_dir) = if is_msvc {
+        ("/D", "/LIBPATH:")
+    } else {
+        ("-D", "-L")
+    };
 
     if let Some(deps) = &config.dependencies
         && !deps.is_empty()
     {
-        let (paths, cflags, libs) = crate::deps::fetch_dependencies(deps)?;
-        include_paths = paths;
-        extra_cflags = cflags;
-        dep_libs = libs;
+        let resolved = crate::deps::fetch_dependencies(deps, &Default::default())?;
+        let built = if resolved.sources.is_empty() && resolved.external.is_empty() {
+            Default::default()
+        } else {
+            super::dep_libs::build_for_tests(&config, &resolved)?
+        };
+        include_paths = resolved.include_paths;
+        include_paths.extend(built.include_dirs);
+        extra_cflags = resolved.cflags;
+        extra_cflags.extend(built.cflags);
+        dep_libs = built.link_flags;
+        dep_libs.extend(resolved.link_flags);
+        dep_lib_names = resolved.libs;
+        dep_lib_names.extend(built.libs);
+
+        // Macros and search paths declared on dependencies
+        extra_cflags.extend(resolved.defines.iter().map(|d| format!("{}{}", define, d)));
+        dep_libs.extend(
+            resolved
+                .link_dirs
+                .iter()
+                .map(|d| format!("{}{}", link_dir, d.display())),
+        );
+    }
+    extra_cflags.extend(features.defines.iter().map(|d| format!("{}{}", define, d)));
+    // Libraries required by caxe-project dependencies link like our own
+    if !dep_lib_names.is_empty() {
+        config
+            .build
+            .get_or_insert_with(Default::default)
+            .libs
+            .get_or_insert_with(Vec::new)
+            .extend(dep_lib_names);
     }
 
-    println!("{} Running tests...", "🧪".magenta());
+    status!("{} Running tests...", "🧪".magenta());
     if let Some(f) = &filter {
-        println!("   Filter: {}", f.cyan());
+        status!("   Filter: {}", f.cyan());
     }
     fs::create_dir_all("build/tests")?;
 
-    // Collect Project Object Files (excluding main)
+    // Collect the last build's object files (excluding main), so objects of
+    // since-disabled features or removed sources aren't linked.
     // We assume the project was built in 'debug' mode for tests
-    let obj_dir = Path::new("build/debug/obj");
+    let build_dir = Path::new(".cx").join("build").join("debug");
+    let obj_dir = build_dir.join("obj");
     let mut project_objs = Vec::new();
-    if obj_dir.exists() {
-        for entry in WalkDir::new(obj_dir).into_iter().filter_map(|e| e.ok()) {
-            let path = entry.path();
-            if path.extension().is_some_and(|e| e == "o" || e == "obj") {
-                // Exclude main.o / main.obj to avoid multiple entry points
-                let stem = path.file_stem().unwrap_or_default().to_string_lossy();
-                if stem != "main" {
-                    project_objs.push(path.to_path_buf());
-                }
+    // [[bin]] entry points define main() too
+    let bin_mains: Vec<PathBuf> = config
+        .bins
+        .iter()
+        .flatten()
+        .map(|b| get_object_path(&obj_dir, Path::new(&b.main_path()), false).with_extension(""))
+        .collect();
+    if let Ok(list) = fs::read_to_string(build_dir.join(super::core::OBJECT_LIST)) {
+        let objects: Vec<PathBuf> = serde_json::from_str(&list)?;
+        for path in objects {
+            // Exclude main.cpp.o / main.c.obj to avoid multiple entry points
+            let source = path.with_extension("");
+            let is_main = source.file_stem().is_some_and(|s| s == "main");
+            if !is_main && !bin_mains.contains(&source) {
+                project_objs.push(path);
             }
         }
     } else {
-        println!(
+        status!(
             "{} Warning: Project not built. Running tests without linking project sources.",
             "!".yellow()
         );
-        println!("   Run 'cx build' first to link project code.");
+        status!("   Run 'cx build' first to link project code.");
     }
 
     let mut test_files = Vec::new();
@@ -107,7 +163,7 @@ pub fn run_tests(filter: Option<String>) -> Result<()> {
     }
 
     if test_files.is_empty() {
-        println!("{} No tests found.", "!".yellow());
+        status!("{} No tests found.", "!".yellow());
         return Ok(());
     }
 
@@ -120,7 +176,7 @@ pub fn run_tests(filter: Option<String>) -> Result<()> {
         .unwrap_or(false);
 
     if single_binary {
-        println!("{} Building single test runner...", "🔨".cyan());
+        status!("{} Building single test runner...", "🔨".cyan());
         let runner_name = "test_runner";
         let output_bin = format!("build/tests/{}", runner_name); // Linux/Mac
 
@@ -141,6 +197,7 @@ pub fn run_tests(filter: Option<String>) -> Result<()> {
                 args.push(format!("/I{}", p.display()));
             }
             args.push("/Isrc".to_string());
+            args.extend(extra_cflags.iter().cloned());
 
             // Sources
             for (path, _) in &test_files {
@@ -166,6 +223,7 @@ pub fn run_tests(filter: Option<String>) -> Result<()> {
                 args.push(format!("-I{}", p.display()));
             }
             args.push("-Isrc".to_string());
+            args.extend(extra_cflags.iter().cloned());
 
             // Sources
             for (path, _) in &test_files {
@@ -195,15 +253,24 @@ pub fn run_tests(filter: Option<String>) -> Result<()> {
         let start = std::time::Instant::now();
         let output = cmd.output()?;
         if !output.status.success() {
-            println!("{} Test Runner Compilation Failed:", "x".red());
-            println!("{}", String::from_utf8_lossy(&output.stdout));
-            println!("{}", String::from_utf8_lossy(&output.stderr));
+            status!("{} Test Runner Compilation Failed:", "x".red());
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if json_output {
+                for diag in diagnostics::parse(&format!("{}{}", stdout, stderr)) {
+                    diagnostics::emit_diagnostic(test_dir, &diag);
+                }
+                diagnostics::emit_finished(false);
+            } else {
+                status!("{}", stdout);
+                status!("{}", stderr);
+            }
             return Ok(());
         }
-        println!("   {} Compiled in {:.2?}s", "✓".green(), start.elapsed());
+        status!("   {} Compiled in {:.2?}s", "✓".green(), start.elapsed());
 
         // Run It
-        println!("{} Running tests...", "🚀".cyan());
+        status!("{} Running tests...", "🚀".cyan());
         let run_path = if cfg!(target_os = "windows") {
             format!("{}.exe", output_bin)
         } else {
@@ -216,11 +283,24 @@ pub fn run_tests(filter: Option<String>) -> Result<()> {
             run_cmd.arg(f);
         }
 
+        if json_output {
+            diagnostics::emit_artifact(ArtifactKind::Bin, Path::new(&output_bin), false);
+            let output = run_cmd.output()?;
+            let log = format!(
+                "{}{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            );
+            diagnostics::emit_test_result(runner_name, output.status.success(), &log);
+            diagnostics::emit_finished(output.status.success());
+            return Ok(());
+        }
+
         let status = run_cmd.status()?;
         if status.success() {
-            println!("{}", "TESTS PASSED".green().bold());
+            status!("{}", "TESTS PASSED".green().bold());
         } else {
-            println!("{}", "TESTS FAILED".red().bold());
+            status!("{}", "TESTS FAILED".red().bold());
         }
         return Ok(());
     }
@@ -232,6 +312,9 @@ pub fn run_tests(filter: Option<String>) -> Result<()> {
             .unwrap_or_else(|_| ProgressStyle::default_bar())
             .progress_chars("●○·"),
     );
+    if json_output {
+        pb.set_draw_target(ProgressDrawTarget::hidden());
+    }
 
     // Phase 1: Parallel Compilation
     let compiled_results: Vec<(String, Option<String>)> = test_files
@@ -267,6 +350,9 @@ pub fn run_tests(filter: Option<String>) -> Result<()> {
             };
 
             if skip_compile {
+                if json_output {
+                    diagnostics::emit_artifact(ArtifactKind::Bin, Path::new(&output_bin), true);
+                }
                 pb.inc(1); // Skip compile step
                 return (test_name, Some(output_bin));
             }
@@ -310,16 +396,14 @@ pub fn run_tests(filter: Option<String>) -> Result<()> {
                 cmd.args(flags);
             }
 
-            // Link Libs & Project Objects
+            // Link Project Objects, then the libraries they use
             if is_msvc {
                 cmd.arg("/link");
             }
-            cmd.args(&dep_libs);
-
-            // Link Project Objects
             for obj in &project_objs {
                 cmd.arg(obj);
             }
+            cmd.args(&dep_libs);
 
             if let Some(build_cfg) = &config.build
                 && let Some(libs) = &build_cfg.libs
@@ -336,12 +420,29 @@ pub fn run_tests(filter: Option<String>) -> Result<()> {
             let output = cmd.output();
             let success = match output {
                 Ok(out) => {
+                    let stdout = String::from_utf8_lossy(&out.stdout);
+                    let stderr = String::from_utf8_lossy(&out.stderr);
+                    if json_output {
+                        for diag in diagnostics::parse(&format!("{}{}", stdout, stderr)) {
+                            diagnostics::emit_diagnostic(path, &diag);
+                        }
+                        if out.status.success() {
+                            diagnostics::emit_artifact(
+                                ArtifactKind::Bin,
+                                Path::new(&output_bin),
+                                false,
+                            );
+                        }
+                    }
+
                     if !out.status.success() {
-                        pb.suspend(|| {
-                            println!("{} COMPILE FAIL: {}", "x".red(), test_name.bold());
-                            println!("{}", String::from_utf8_lossy(&out.stdout));
-                            println!("{}", String::from_utf8_lossy(&out.stderr));
-                        });
+                        if !json_output {
+                            pb.suspend(|| {
+                                status!("{} COMPILE FAIL: {}", "x".red(), test_name.bold());
+                                status!("{}", stdout);
+                                status!("{}", stderr);
+                            });
+                        }
                         false
                     } else {
                         true
@@ -349,7 +450,7 @@ pub fn run_tests(filter: Option<String>) -> Result<()> {
                 }
                 Err(e) => {
                     pb.suspend(|| {
-                        println!("{} COMPILER ERROR: {} ({})", "x".red(), test_name.bold(), e);
+                        status!("{} COMPILER ERROR: {} ({})", "x".red(), test_name.bold(), e);
                     });
                     false
                 }
@@ -380,13 +481,33 @@ pub fn run_tests(filter: Option<String>) -> Result<()> {
                 format!("./{}", output_bin)
             };
 
+            if json_output {
+                let (success, log) = match Command::new(&run_path).output() {
+                    Ok(out) => (
+                        out.status.success(),
+                        format!(
+                            "{}{}",
+                            String::from_utf8_lossy(&out.stdout),
+                            String::from_utf8_lossy(&out.stderr)
+                        ),
+                    ),
+                    Err(e) => (false, e.to_string()),
+                };
+                diagnostics::emit_test_result(&test_name, success, &log);
+                if success {
+                    passed_tests += 1;
+                }
+                pb.inc(1);
+                continue;
+            }
+
             let run_status = Command::new(&run_path).status();
 
             match run_status {
                 Ok(status) => {
                     if status.success() {
                         pb.suspend(|| {
-                            println!(
+                            status!(
                                 "   {} TEST {} ... {}",
                                 "✓".green(),
                                 test_name.bold(),
@@ -396,7 +517,7 @@ pub fn run_tests(filter: Option<String>) -> Result<()> {
                         passed_tests += 1;
                     } else {
                         pb.suspend(|| {
-                            println!(
+                            status!(
                                 "   {} TEST {} ... {}",
                                 "x".red(),
                                 test_name.bold(),
@@ -407,7 +528,7 @@ pub fn run_tests(filter: Option<String>) -> Result<()> {
                 }
                 Err(_) => {
                     pb.suspend(|| {
-                        println!(
+                        status!(
                             "   {} TEST {} ... {}",
                             "x".red(),
                             test_name.bold(),
@@ -416,6 +537,8 @@ pub fn run_tests(filter: Option<String>) -> Result<()> {
                     });
                 }
             }
+        } else if json_output {
+            diagnostics::emit_test_result(&test_name, false, "compilation failed");
         }
 
         pb.inc(1);
@@ -423,11 +546,16 @@ pub fn run_tests(filter: Option<String>) -> Result<()> {
 
     pb.finish_and_clear();
 
-    println!("\nTest Result: {}/{} passed.", passed_tests, total_tests);
+    if json_output {
+        diagnostics::emit_finished(total_tests > 0 && passed_tests == total_tests);
+        return Ok(());
+    }
+
+    status!("\nTest Result: {}/{} passed.", passed_tests, total_tests);
     if total_tests > 0 && passed_tests == total_tests {
-        println!("{}", "ALL TESTS PASSED ✨".green().bold());
+        status!("{}", "ALL TESTS PASSED ✨".green().bold());
     } else if total_tests > 0 {
-        println!("{}", "SOME TESTS FAILED 💀".red().bold());
+        status!("{}", "SOME TESTS FAILED 💀".red().bold());
     }
 
     Ok(())
@@ -0,0 +1,264 @@
+//! Unity (jumbo) builds.
+//!
+//! Sources are batched into generated translation units under
+//! `.cx/build/<profile>/unity`, each `#include`-ing about `unity_batch_size`
+//! files, so shared headers are parsed once per batch instead of once per
+//! file. Files are assigned to batches by a hash of their path, so adding or
+//! removing a source only changes the batch it falls in, and a batch file is
+//! only rewritten when its member list changes.
+
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Sources per unity TU when `unity_batch_size` is unset
+pub const DEFAULT_BATCH_SIZE: usize = 8;
+
+/// Generated unity TUs and the sources still compiled on their own
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct UnityPlan {
+    pub batches: Vec<(PathBuf, Vec<PathBuf>)>,
+    pub standalone: Vec<PathBuf>,
+}
+
+impl UnityPlan {
+    /// Sources to hand to the compiler: unity TUs first, then standalone files
+    pub fn sources(&self) -> Vec<PathBuf> {
+        self.batches
+            .iter()
+            .map(|(unity, _)| unity.clone())
+            .chain(self.standalone.iter().cloned())
+            .collect()
+    }
+
+    pub fn batched(&self) -> impl Iterator<Item = &PathBuf> {
+        self.batches.iter().flat_map(|(_, members)| members)
+    }
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .collect()
+}
+
+/// Whether `src` matches an `unity_exclude` entry (a file or a directory)
+pub fn is_excluded(src: &Path, patterns: &[String]) -> bool {
+    let src = normalize(src);
+    patterns
+        .iter()
+        .any(|pattern| src.starts_with(normalize(Path::new(pattern))))
+}
+
+/// Stable batch index for `src` out of `buckets` (jump consistent hash).
+///
+/// The index only depends on the path, and growing the bucket count only
+/// moves files into the new bucket.
+fn bucket(src: &Path, buckets: usize) -> usize {
+    let path = normalize(src).to_string_lossy().replace('\\', "/");
+    let digest = Sha256::digest(path.as_bytes());
+    let mut key = u64::from_le_bytes(digest[..8].try_into().unwrap_or_default());
+    let (mut b, mut j) = (0u64, 0u64);
+    while j < buckets as u64 {
+        b = j;
+        key = key.wrapping_mul(2862933555777941757).wrapping_add(1);
+        j = ((b + 1) as f64 * ((1u64 << 31) as f64 / ((key >> 33) + 1) as f64)) as u64;
+    }
+    b as usize
+}
+
+/// Split sources into batches of about `batch_size`, keeping C and C++ apart.
+///
+/// Each file is hashed into one of `len / batch_size` batches, so adding or
+/// removing a file leaves the other batches alone; a batch with a single
+/// member is compiled directly instead.
+pub fn plan(
+    sources: &[PathBuf],
+    unity_dir: &Path,
+    batch_size: usize,
+    exclude: impl Fn(&Path) -> bool,
+) -> UnityPlan {
+    let mut plan = UnityPlan::default();
+    let mut cpp = Vec::new();
+    let mut c = Vec::new();
+    for src in sources {
+        if exclude(src) {
+            plan.standalone.push(src.clone());
+        } else if src.extension().is_some_and(|e| e == "c") {
+            c.push(src.clone());
+        } else {
+            cpp.push(src.clone());
+        }
+    }
+
+    for (files, prefix, ext) in [(cpp, "unity_", "cpp"), (c, "unity_c_", "c")] {
+        let count = files.len().div_ceil(batch_size.max(1));
+        let mut buckets: BTreeMap<usize, Vec<PathBuf>> = BTreeMap::new();
+        for src in files {
+            buckets.entry(bucket(&src, count)).or_default().push(src);
+        }
+        for (i, mut members) in buckets {
+            if members.len() == 1 {
+                plan.standalone.append(&mut members);
+                continue;
+            }
+            members.sort();
+            let unity = unity_dir.join(format!("{}{}.{}", prefix, i, ext));
+            plan.batches.push((unity, members));
+        }
+    }
+    plan
+}
+
+fn include_path(src: &Path) -> String {
+    let path = fs::canonicalize(src).unwrap_or_else(|_| src.to_path_buf());
+    let path = path.to_string_lossy().replace('\\', "/");
+    // Drop the Windows verbatim prefix (`\\?\C:\...`) added by canonicalize
+    path.strip_prefix("//?/").unwrap_or(&path).to_string()
+}
+
+/// Write the unity TUs, leaving unchanged batches untouched and removing
+/// batches that no longer exist
+pub fn write(plan: &UnityPlan, unity_dir: &Path) -> Result<()> {
+    fs::create_dir_all(unity_dir)?;
+
+    for (unity, members) in &plan.batches {
+        let mut content = String::from("// Generated by cx (unity build). Do not edit.\n");
+        for src in members {
+            content.push_str(&format!("#include \"{}\"\n", include_path(src)));
+        }
+        if fs::read_to_string(unity).ok().as_deref() != Some(content.as_str()) {
+            fs::write(unity, content)?;
+        }
+    }
+
+    for entry in fs::read_dir(unity_dir)?.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if !plan.batches.iter().any(|(unity, _)| *unity == path) {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(list: &[&str]) -> Vec<PathBuf> {
+        list.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn test_plan_batches_by_language() {
+        let sources = paths(&[
+            "src/d.cpp",
+            "src/a.cpp",
+            "src/main.cpp",
+            "src/c.cpp",
+            "src/b.cpp",
+            "src/x.c",
+            "src/y.c",
+            "src/legacy/old.cpp",
+        ]);
+        let exclude = vec!["src/legacy".to_string()];
+        let plan = plan(&sources, Path::new("unity"), 8, |src| {
+            is_excluded(src, &exclude) || src.file_stem().is_some_and(|s| s == "main")
+        });
+
+        assert_eq!(
+            plan.batches,
+            vec![
+                (
+                    PathBuf::from("unity/unity_0.cpp"),
+                    paths(&["src/a.cpp", "src/b.cpp", "src/c.cpp", "src/d.cpp"])
+                ),
+                (
+                    PathBuf::from("unity/unity_c_0.c"),
+                    paths(&["src/x.c", "src/y.c"])
+                ),
+            ]
+        );
+        assert_eq!(
+            plan.standalone,
+            paths(&["src/main.cpp", "src/legacy/old.cpp"])
+        );
+    }
+
+    #[test]
+    fn test_adding_a_file_changes_one_batch() {
+        let sources: Vec<PathBuf> = (0..39)
+            .map(|i| PathBuf::from(format!("src/file{}.cpp", i)))
+            .collect();
+        let before = plan(&sources, Path::new("unity"), 8, |_| false);
+
+        let mut more = sources.clone();
+        more.push(PathBuf::from("./src/new.cpp"));
+        let after = plan(&more, Path::new("unity"), 8, |_| false);
+
+        let mut changed: Vec<_> = before
+            .batches
+            .iter()
+            .filter(|batch| !after.batches.contains(batch))
+            .collect();
+        changed.extend(
+            after
+                .batches
+                .iter()
+                .filter(|batch| !before.batches.contains(batch)),
+        );
+        assert!(changed.len() <= 2, "{:?}", changed);
+        assert!(changed.iter().all(|(unity, _)| *unity == changed[0].0));
+        assert!(
+            after
+                .batched()
+                .chain(&after.standalone)
+                .any(|src| *src == more[39])
+        );
+        assert_eq!(before.batched().count() + before.standalone.len(), 39);
+    }
+
+    #[test]
+    fn test_write_only_touches_changed_batches() {
+        let dir = std::env::temp_dir().join(format!("cx_unity_test_{}", std::process::id()));
+        let unity_dir = dir.join("unity");
+        fs::create_dir_all(&dir).unwrap();
+        let sources: Vec<PathBuf> = ["a.cpp", "b.cpp", "c.cpp", "d.cpp"]
+            .iter()
+            .map(|name| {
+                let path = dir.join(name);
+                fs::write(&path, "").unwrap();
+                path
+            })
+            .collect();
+
+        let batch0 = unity_dir.join("unity_0.cpp");
+        let batch1 = unity_dir.join("unity_1.cpp");
+        let first = UnityPlan {
+            batches: vec![
+                (batch0.clone(), sources[..2].to_vec()),
+                (batch1.clone(), sources[2..].to_vec()),
+            ],
+            standalone: Vec::new(),
+        };
+        write(&first, &unity_dir).unwrap();
+        fs::write(unity_dir.join("unity_9.cpp"), "stale").unwrap();
+        let before = fs::metadata(&batch0).unwrap().modified().unwrap();
+
+        // Same first batch, second batch gone
+        let second = UnityPlan {
+            batches: vec![(batch0.clone(), sources[..2].to_vec())],
+            standalone: sources[2..].to_vec(),
+        };
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        write(&second, &unity_dir).unwrap();
+
+        assert_eq!(fs::metadata(&batch0).unwrap().modified().unwrap(), before);
+        assert!(!unity_dir.join("unity_9.cpp").exists());
+        assert!(!batch1.exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
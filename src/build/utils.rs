@@ -1,10 +1,13 @@
-use crate::config::{CxConfig, Profile};
+use super::diagnostics;
+use crate::config::{ArtifactKind, CxConfig, Profile};
+use crate::status;
+use crate::toolchain::cross::TargetOs;
 use crate::toolchain::{self, CompilerType, Toolchain, ToolchainError};
 use anyhow::{Context, Result};
 use colored::*;
 use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
 use std::process::Command;
 
 // --- Helper: Load Config with Profile Parsing ---
@@ -18,20 +21,28 @@ pub fn load_config() -> Result<CxConfig> {
     let config_str =
         fs::read_to_string("cx.toml").context("Failed to read cx.toml - check file permissions")?;
 
-    // Parse as raw TOML Value first to extract [profile:*] tables
+    // Parse as raw TOML Value first to extract profile tables
     let raw_value: toml::Value = toml::from_str(&config_str)
         .context("Failed to parse cx.toml - check for syntax errors (missing quotes, brackets)")?;
 
-    // Extract profiles from [profile:name] tables
+    // Extract profiles from [profile.name] and ["profile:name"] tables
     let mut profiles: HashMap<String, Profile> = HashMap::new();
     if let toml::Value::Table(root) = &raw_value {
-        for (key, value) in root {
-            if key.starts_with("profile:") {
-                let profile_name = key.strip_prefix("profile:").unwrap().to_string();
-                if let Ok(profile) = value.clone().try_into::<Profile>() {
-                    profiles.insert(profile_name, profile);
-                }
-            }
+        let nested = root
+            .get("profile")
+            .and_then(|p| p.as_table())
+            .into_iter()
+            .flatten()
+            .map(|(name, value)| (name.as_str(), value));
+        let prefixed = root
+            .iter()
+            .filter_map(|(key, value)| Some((key.strip_prefix("profile:")?, value)));
+        for (profile_name, value) in nested.chain(prefixed) {
+            let profile = value
+                .clone()
+                .try_into::<Profile>()
+                .with_context(|| format!("Invalid profile '{}' in cx.toml", profile_name))?;
+            profiles.insert(profile_name.to_string(), profile);
         }
     }
 
@@ -85,7 +96,7 @@ pub fn get_toolchain(config: &CxConfig, _has_cpp: bool) -> Result<Toolchain, Too
     // 2. Try to detect toolchain using proper discovery
     match toolchain::get_or_detect_toolchain(preferred, false) {
         Ok(tc) => {
-            println!(
+            status!(
                 "   {} Detected toolchain: {} ({})",
                 "🔧".cyan(),
                 tc.cxx_path.display(),
@@ -100,13 +111,13 @@ pub fn get_toolchain(config: &CxConfig, _has_cpp: bool) -> Result<Toolchain, Too
                 let msg = format!("{}", e);
                 // Don't show VS Install help for intentional non-MSVC compiler preferences
                 if !msg.contains("Clang/GCC") {
-                    println!("{} {}", "x".red(), e);
-                    println!();
-                    println!("{}:", "To fix this".bold());
-                    println!("  1. Install Visual Studio Build Tools from:");
-                    println!("     https://visualstudio.microsoft.com/visual-cpp-build-tools/");
-                    println!("  2. Select 'Desktop development with C++' workload");
-                    println!();
+                    status!("{} {}", "x".red(), e);
+                    status!();
+                    status!("{}:", "To fix this".bold());
+                    status!("  1. Install Visual Studio Build Tools from:");
+                    status!("     https://visualstudio.microsoft.com/visual-cpp-build-tools/");
+                    status!("  2. Select 'Desktop development with C++' workload");
+                    status!();
                 }
             }
             Err(e)
@@ -122,7 +133,7 @@ pub fn get_compiler(config: &CxConfig, has_cpp: bool) -> String {
     }
 
     // Fallback to old PATH-based detection (backward compatibility)
-    println!(
+    status!(
         "   {} Falling back to PATH-based compiler detection",
         "⚠".yellow()
     );
@@ -175,7 +186,7 @@ pub fn run_script(script: &str, project_dir: &Path) -> Result<()> {
     if script.ends_with(".rhai") {
         let script_path = project_dir.join(script);
         if script_path.exists() {
-            println!("   {} Running Rhai script: '{}'...", "📜".magenta(), script);
+            status!("   {} Running Rhai script: '{}'...", "📜".magenta(), script);
             let engine = rhai::Engine::new();
             engine
                 .run_file(script_path)
@@ -184,16 +195,18 @@ pub fn run_script(script: &str, project_dir: &Path) -> Result<()> {
         }
     }
 
-    println!("   {} Running script: '{}'...", "📜".magenta(), script);
+    status!("   {} Running script: '{}'...", "📜".magenta(), script);
     let status = if cfg!(target_os = "windows") {
         Command::new("cmd")
             .args(["/C", script])
             .current_dir(project_dir)
+            .stdout(diagnostics::child_stdout())
             .status()?
     } else {
         Command::new("sh")
             .args(["-c", script])
             .current_dir(project_dir)
+            .stdout(diagnostics::child_stdout())
             .status()?
     };
 
@@ -203,6 +216,117 @@ pub fn run_script(script: &str, project_dir: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Get the output file name for a build artifact on the target OS
+/// e.g. `app.exe`, `libapp.a`, `app.lib`, `libapp.so`, `app.dll`
+pub fn get_artifact_name(
+    basename: &str,
+    kind: ArtifactKind,
+    is_msvc: bool,
+    os: TargetOs,
+) -> String {
+    match kind {
+        ArtifactKind::Bin if os == TargetOs::Wasm => format!("{}.html", basename),
+        ArtifactKind::Bin if os == TargetOs::Windows => format!("{}.exe", basename),
+        ArtifactKind::Bin => basename.to_string(),
+        ArtifactKind::Staticlib if is_msvc => format!("{}.lib", basename),
+        ArtifactKind::Staticlib => format!("lib{}.a", basename),
+        ArtifactKind::Sharedlib if os == TargetOs::Windows => format!("{}.dll", basename),
+        ArtifactKind::Sharedlib if os == TargetOs::MacOs => format!("lib{}.dylib", basename),
+        ArtifactKind::Sharedlib => format!("lib{}.so", basename),
+    }
+}
+
+/// Optimization, debug info, warning and define flags for a build profile.
+/// Without explicit settings this is `-O3` in release and `-g -Wall` in debug
+/// (`/O2` and `/Z7 /W4` for MSVC).
+pub fn get_profile_flags(profile: &Profile, release: bool, is_msvc: bool) -> Vec<String> {
+    let mut flags = Vec::new();
+
+    match profile.opt_level.as_deref() {
+        Some(level) if is_msvc => flags.push(
+            match level {
+                "0" => "/Od",
+                "1" | "s" | "z" => "/O1",
+                _ => "/O2",
+            }
+            .to_string(),
+        ),
+        Some(level) => flags.push(format!("-O{}", level)),
+        None if release => flags.push(if is_msvc { "/O2" } else { "-O3" }.to_string()),
+        None => {}
+    }
+
+    if profile.debug.unwrap_or(!release) {
+        flags.push(if is_msvc { "/Z7" } else { "-g" }.to_string());
+    }
+    if !release {
+        flags.push(if is_msvc { "/W4" } else { "-Wall" }.to_string());
+    }
+    if profile.warnings_as_errors.unwrap_or(false) {
+        flags.push(if is_msvc { "/WX" } else { "-Werror" }.to_string());
+    }
+
+    for define in profile.defines.iter().flatten() {
+        flags.push(format!("{}{}", if is_msvc { "/D" } else { "-D" }, define));
+    }
+    flags
+}
+
+/// Get the static library archiver matching a compiler
+/// MSVC uses lib.exe, Emscripten uses emar, and prefixed GCC cross compilers
+/// (e.g. `aarch64-linux-gnu-g++`) use their matching `<prefix>-ar`
+pub fn get_archiver(compiler: &str, is_msvc: bool) -> String {
+    if is_msvc {
+        return "lib".to_string();
+    }
+
+    let file_name = Path::new(compiler)
+        .file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_else(|| compiler.to_string());
+    let stem = file_name.trim_end_matches(".exe");
+
+    if stem == "em++" || stem == "emcc" {
+        return "emar".to_string();
+    }
+
+    for driver in ["g++", "gcc", "c++", "cc"] {
+        if let Some(prefix) = stem.strip_suffix(driver)
+            && prefix.ends_with('-')
+        {
+            let archiver = format!("{}ar", prefix);
+            return match Path::new(compiler).parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => {
+                    dir.join(archiver).to_string_lossy().to_string()
+                }
+                _ => archiver,
+            };
+        }
+    }
+
+    "ar".to_string()
+}
+
+/// Get the object file path for a source file
+/// Mirrors the source's relative path under `obj_dir` so `src/net/util.cpp` and
+/// `src/fs/util.cpp` don't both end up as `util.o`, and appends the object
+/// extension so `util.c` and `util.cpp` don't either (`util.cpp.o`). `..`
+/// becomes `__` and absolute paths are re-rooted under `obj_dir`.
+pub fn get_object_path(obj_dir: &Path, src: &Path, is_msvc: bool) -> PathBuf {
+    let mut rel = PathBuf::new();
+    for component in src.components() {
+        match component {
+            Component::Normal(part) => rel.push(part),
+            Component::ParentDir => rel.push("__"),
+            Component::CurDir | Component::RootDir | Component::Prefix(_) => {}
+        }
+    }
+    let mut name = rel.file_name().unwrap_or_default().to_os_string();
+    name.push(if is_msvc { ".obj" } else { ".o" });
+    rel.set_file_name(name);
+    obj_dir.join(rel)
+}
+
 /// Get the MSVC-compatible standard flag for a given edition
 /// MSVC uses /std: prefix and has different naming for newer standards
 pub fn get_std_flag_msvc(edition: &str) -> String {
@@ -348,6 +472,114 @@ mod tests {
         assert_eq!(get_std_flag_gcc("c2x"), "-std=c23");
     }
 
+    #[test]
+    fn test_get_artifact_name_static() {
+        assert_eq!(
+            get_artifact_name("core", ArtifactKind::Staticlib, false, TargetOs::host()),
+            "libcore.a"
+        );
+        assert_eq!(
+            get_artifact_name("core", ArtifactKind::Staticlib, true, TargetOs::host()),
+            "core.lib"
+        );
+    }
+
+    #[test]
+    fn test_get_artifact_name_shared() {
+        let name = get_artifact_name("core", ArtifactKind::Sharedlib, false, TargetOs::host());
+        if cfg!(target_os = "windows") {
+            assert_eq!(name, "core.dll");
+        } else if cfg!(target_os = "macos") {
+            assert_eq!(name, "libcore.dylib");
+        } else {
+            assert_eq!(name, "libcore.so");
+        }
+    }
+
+    #[test]
+    fn test_get_artifact_name_wasm_bin() {
+        assert_eq!(
+            get_artifact_name("app", ArtifactKind::Bin, false, TargetOs::Wasm),
+            "app.html"
+        );
+    }
+
+    #[test]
+    fn test_get_artifact_name_cross_target() {
+        assert_eq!(
+            get_artifact_name("app", ArtifactKind::Bin, false, TargetOs::Windows),
+            "app.exe"
+        );
+        assert_eq!(
+            get_artifact_name("core", ArtifactKind::Sharedlib, false, TargetOs::Linux),
+            "libcore.so"
+        );
+    }
+
+    #[test]
+    fn test_get_profile_flags_defaults() {
+        let profile = Profile::default();
+        assert_eq!(get_profile_flags(&profile, true, false), vec!["-O3"]);
+        assert_eq!(
+            get_profile_flags(&profile, false, false),
+            vec!["-g", "-Wall"]
+        );
+        assert_eq!(get_profile_flags(&profile, false, true), vec!["/Z7", "/W4"]);
+    }
+
+    #[test]
+    fn test_get_profile_flags_settings() {
+        let profile = Profile {
+            opt_level: Some("s".to_string()),
+            debug: Some(true),
+            warnings_as_errors: Some(true),
+            defines: Some(vec!["BOARD=esp32".to_string()]),
+            ..Default::default()
+        };
+        assert_eq!(
+            get_profile_flags(&profile, true, false),
+            vec!["-Os", "-g", "-Werror", "-DBOARD=esp32"]
+        );
+        assert_eq!(
+            get_profile_flags(&profile, true, true),
+            vec!["/O1", "/Z7", "/WX", "/DBOARD=esp32"]
+        );
+    }
+
+    #[test]
+    fn test_get_archiver() {
+        assert_eq!(get_archiver("cl", true), "lib");
+        assert_eq!(get_archiver("g++", false), "ar");
+        assert_eq!(get_archiver("clang++", false), "ar");
+        assert_eq!(get_archiver("em++", false), "emar");
+        assert_eq!(
+            get_archiver("aarch64-linux-gnu-g++", false),
+            "aarch64-linux-gnu-ar"
+        );
+        assert_eq!(
+            get_archiver("/opt/xtensa/bin/xtensa-esp32-elf-gcc", false),
+            "/opt/xtensa/bin/xtensa-esp32-elf-ar"
+        );
+    }
+
+    #[test]
+    fn test_get_object_path_mirrors_source_tree() {
+        let obj_dir = Path::new(".cx/build/debug/obj");
+        let net = get_object_path(obj_dir, Path::new("src/net/util.cpp"), false);
+        let fs = get_object_path(obj_dir, Path::new("./src/fs/util.cpp"), false);
+        assert_eq!(net, obj_dir.join("src/net/util.cpp.o"));
+        assert_eq!(fs, obj_dir.join("src/fs/util.cpp.o"));
+        assert_eq!(
+            get_object_path(obj_dir, Path::new("../common/util.c"), true),
+            obj_dir.join("__/common/util.c.obj")
+        );
+        // Same stem, different language
+        assert_ne!(
+            get_object_path(obj_dir, Path::new("src/x.c"), false),
+            get_object_path(obj_dir, Path::new("src/x.cpp"), false)
+        );
+    }
+
     #[test]
     fn test_get_std_flag_gcc_strip_prefix() {
         assert_eq!(get_std_flag_gcc("-std=c++20"), "-std=c++20");
@@ -9,6 +9,7 @@
 //! - `cx watch --test` - TDD mode: run tests on every change
 
 use super::core;
+use super::diagnostics::MessageFormat;
 use anyhow::Result;
 use colored::*;
 use notify::{Config, RecursiveMode, Watcher};
@@ -49,9 +50,9 @@ fn run_and_clear(run_tests: bool) {
     println!("{} File changed. Rebuilding...", "🔄".yellow());
 
     let result = if run_tests {
-        super::test::run_tests(None)
+        super::test::run_tests(None, MessageFormat::Human)
     } else {
-        core::build_and_run(false, false, false, vec![], None)
+        core::build_and_run(false, false, false, None, vec![], None)
     };
 
     if let Err(e) = result {
@@ -2,47 +2,278 @@
 //!
 //! This module handles the `~/.cx/cache` directory where downloaded dependencies are stored.
 //!
+//! ## Layout
+//!
+//! ```text
+//! ~/.cx/cache/
+//! ├── git/db/<name>-<hash>/                  # one bare repository per URL
+//! ├── git/checkouts/<name>-<hash>/<commit>/  # one working tree per revision
+//! ├── archives/<file>-<sha256>/host/         # one extracted tree per archive
+//! ├── libs/<name>-<hash>/                    # built libraries and external installs
+//! └── objects/                               # compiled object cache
+//! ```
+//!
+//! `<hash>` is derived from the URL, so forks sharing a name don't collide,
+//! and checkouts are never moved between revisions, so projects using
+//! different tags of the same library can build side by side. Archives are
+//! keyed by their checksum; built libraries by their sources (or
+//! configure arguments), toolchain fingerprint and profile. Builds writing
+//! into an entry (library builds, build scripts in a checkout) hold a
+//! `.<entry>.lock` file lock next to it, so concurrent builds take turns.
+//!
 //! ## Commands
 //!
 //! - `cx cache path` - Print cache directory location
-//! - `cx cache list` - List cached libraries
-//! - `cx cache clean` - Clear all cached dependencies
+//! - `cx cache list` - List cached libraries and object cache usage
+//! - `cx cache clean` - Clear all cached dependencies (`--objects` for compiled objects only,
+//!   `--older-than <age>` / `--max-size <size>` to prune them instead)
 //! - `cx cache prune` - Remove unused dependencies
 
+use crate::build::object_cache;
+use crate::lock::{LockFile, PackageLock};
+use crate::status;
 use crate::ui;
 use anyhow::{Context, Result};
 use colored::*;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
 use std::fs;
+use std::path::{Path, PathBuf};
 
-pub fn print_path() -> Result<()> {
+/// Subdirectory of `~/.cx/cache` holding compiled objects (not a dependency)
+const OBJECTS_DIR: &str = "objects";
+
+/// Subdirectory of `~/.cx/cache` holding extracted archives
+const ARCHIVES_DIR: &str = "archives";
+
+/// Subdirectory of `~/.cx/cache` holding static libraries built from sources
+const LIBS_DIR: &str = "libs";
+
+/// Archive extensions, longest first
+pub const ARCHIVE_EXTENSIONS: [&str; 5] = [".tar.gz", ".tar.xz", ".tgz", ".txz", ".zip"];
+
+/// `~/.cx/cache`
+pub fn cache_root() -> Result<PathBuf> {
     let home = dirs::home_dir().context("Could not find home directory")?;
-    let cache_dir = home.join(".cx").join("cache");
-    println!("{}", cache_dir.display());
+    Ok(home.join(".cx").join("cache"))
+}
+
+/// Normalize a git URL so `.../fmt.git`, `.../fmt/` and `.../Fmt` are one source
+pub fn normalize_git_url(url: &str) -> String {
+    url.trim_end_matches('/')
+        .trim_end_matches(".git")
+        .to_lowercase()
+}
+
+/// Cache key for a git URL: the repository name plus a hash of the URL
+pub fn source_key(url: &str) -> String {
+    let normalized = normalize_git_url(url);
+    let name = normalized
+        .rsplit(['/', '\\', ':'])
+        .find(|part| !part.is_empty())
+        .unwrap_or("dep");
+    let hash = format!("{:x}", Sha256::digest(normalized.as_bytes()));
+    format!("{}-{}", sanitize(name), &hash[..16])
+}
+
+/// Cache key for an archive: its file name without extension plus the start
+/// of its checksum
+pub fn archive_key(url: &str, sha256: &str) -> String {
+    let file = url
+        .split(['?', '#'])
+        .next()
+        .unwrap_or(url)
+        .rsplit('/')
+        .find(|part| !part.is_empty())
+        .unwrap_or("archive");
+    let lower = file.to_lowercase();
+    let stem = ARCHIVE_EXTENSIONS
+        .iter()
+        .find(|ext| lower.ends_with(*ext))
+        .map_or(file, |ext| &file[..file.len() - ext.len()]);
+    let sha256 = sha256.to_lowercase();
+    format!("{}-{}", sanitize(stem), &sha256[..sha256.len().min(16)])
+}
+
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Bare repository shared by every revision of `url`
+pub fn git_db_dir(url: &str) -> Result<PathBuf> {
+    Ok(cache_root()?.join("git").join("db").join(source_key(url)))
+}
+
+/// Working tree of `url` at `commit`. `variant` (a target triple) keeps
+/// dependencies whose build script writes into the tree apart per target.
+pub fn checkout_dir(url: &str, commit: &str, variant: Option<&str>) -> Result<PathBuf> {
+    let short = &commit[..commit.len().min(12)];
+    let leaf = match variant {
+        Some(variant) => format!("{}-{}", short, variant),
+        None => short.to_string(),
+    };
+    Ok(cache_root()?
+        .join("git")
+        .join("checkouts")
+        .join(source_key(url))
+        .join(leaf))
+}
+
+/// Extracted archive with checksum `sha256`; `variant` as for [`checkout_dir`]
+pub fn archive_dir(url: &str, sha256: &str, variant: Option<&str>) -> Result<PathBuf> {
+    Ok(cache_root()?
+        .join(ARCHIVES_DIR)
+        .join(archive_key(url, sha256))
+        .join(variant.unwrap_or("host")))
+}
+
+/// Static library of dependency `name` built with the settings hashed in `key`
+pub fn lib_dir(name: &str, key: &str) -> Result<PathBuf> {
+    Ok(cache_root()?.join(LIBS_DIR).join(format!(
+        "{}-{}",
+        sanitize(name),
+        &key[..key.len().min(16)]
+    )))
+}
+
+/// Cached source of a locked package, as built for the host
+pub fn locked_dir(entry: &PackageLock) -> Result<PathBuf> {
+    match (&entry.url, &entry.sha256) {
+        (Some(url), Some(sha256)) => archive_dir(url, sha256, None),
+        _ => checkout_dir(&entry.git, &entry.rev, None),
+    }
+}
+
+/// Exclusive lock on a cache entry, released when dropped
+pub struct EntryLock {
+    _file: fs::File,
+}
+
+/// Lock the cache entry `dir` before building into it, waiting for another
+/// `cx` process that holds it
+pub fn lock_entry(dir: &Path) -> Result<EntryLock> {
+    let name = file_name(dir);
+    let path = dir.with_file_name(format!(".{}.lock", name));
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let file = fs::File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)
+        .with_context(|| format!("Failed to create lock file {}", path.display()))?;
+    match file.try_lock() {
+        Ok(()) => {}
+        Err(fs::TryLockError::WouldBlock) => {
+            status!(
+                "   {} Waiting for another build of {}...",
+                "⏳".yellow(),
+                name
+            );
+            file.lock()
+                .with_context(|| format!("Failed to lock {}", path.display()))?;
+        }
+        Err(fs::TryLockError::Error(e)) => {
+            return Err(e).with_context(|| format!("Failed to lock {}", path.display()));
+        }
+    }
+    Ok(EntryLock { _file: file })
+}
+
+fn subdirs(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
+}
+
+pub fn print_path() -> Result<()> {
+    println!("{}", cache_root()?.display());
     Ok(())
 }
 
 pub fn list() -> Result<()> {
-    let home = dirs::home_dir().context("Could not find home directory")?;
-    let cache_dir = home.join(".cx").join("cache");
+    let cache_dir = cache_root()?;
 
     if !cache_dir.exists() {
         println!("{} Cache is empty.", "ℹ".blue());
         return Ok(());
     }
 
-    let entries = fs::read_dir(&cache_dir)?;
-    let mut table = ui::Table::new(&["Cached Library"]);
+    let (objects, bytes) = object_cache::stats();
+    println!(
+        "{} Object cache: {} object(s), {:.1} MB (capped at {:.1} MB)",
+        "📦".blue(),
+        objects,
+        bytes as f64 / (1024.0 * 1024.0),
+        object_cache::max_size() as f64 / (1024.0 * 1024.0)
+    );
+
+    let git_dir = cache_dir.join("git");
+    let mut table = ui::Table::new(&["Cached Library", "Source", "Revisions"]);
     let mut count = 0;
 
-    for entry in entries {
-        if let Ok(entry) = entry
-            && let Ok(ft) = entry.file_type()
-            && ft.is_dir()
-        {
-            let name = entry.file_name();
-            table.add_row(vec![name.to_string_lossy().to_string()]);
-            count += 1;
-        }
+    for db in subdirs(&git_dir.join("db")) {
+        let key = file_name(&db);
+        let source = git2::Repository::open_bare(&db)
+            .ok()
+            .and_then(|repo| {
+                let remote = repo.find_remote("origin").ok()?;
+                remote.url().map(str::to_string)
+            })
+            .unwrap_or_default();
+        let revisions: Vec<String> = subdirs(&git_dir.join("checkouts").join(&key))
+            .iter()
+            .map(|checkout| file_name(checkout))
+            .collect();
+        let name = key.rsplit_once('-').map_or(key.as_str(), |(name, _)| name);
+        table.add_row(vec![name.to_string(), source, revisions.join(", ")]);
+        count += 1;
+    }
+
+    for archive in subdirs(&cache_dir.join(ARCHIVES_DIR)) {
+        let key = file_name(&archive);
+        let (name, sha256) = key.rsplit_once('-').unwrap_or((key.as_str(), ""));
+        let variants: Vec<String> = subdirs(&archive).iter().map(|v| file_name(v)).collect();
+        table.add_row(vec![
+            name.to_string(),
+            format!("archive (sha256 {}…)", sha256),
+            variants.join(", "),
+        ]);
+        count += 1;
+    }
+
+    for lib in subdirs(&cache_dir.join(LIBS_DIR)) {
+        let key = file_name(&lib);
+        let (name, hash) = key.rsplit_once('-').unwrap_or((key.as_str(), ""));
+        table.add_row(vec![
+            name.to_string(),
+            "built library".to_string(),
+            hash.to_string(),
+        ]);
+        count += 1;
     }
 
     if count == 0 {
@@ -54,24 +285,63 @@ pub fn list() -> Result<()> {
     Ok(())
 }
 
-pub fn clean() -> Result<()> {
-    let home = dirs::home_dir().context("Could not find home directory")?;
-    let cache_dir = home.join(".cx").join("cache");
+pub fn clean(objects_only: bool, older_than: Option<&str>, max_size: Option<&str>) -> Result<()> {
+    let cache_dir = cache_root()?;
+
+    if older_than.is_some() || max_size.is_some() {
+        let older_than = older_than
+            .map(|age| {
+                object_cache::parse_age(age).with_context(|| {
+                    format!("Invalid --older-than '{}' (expected e.g. 30d or 12h)", age)
+                })
+            })
+            .transpose()?;
+        let max_size = max_size
+            .map(|size| {
+                object_cache::parse_size(size).with_context(|| {
+                    format!("Invalid --max-size '{}' (expected e.g. 500M or 2G)", size)
+                })
+            })
+            .transpose()?;
+        let (removed, freed) = object_cache::prune(older_than, max_size);
+        println!(
+            "{} Removed {} cached object(s), freed {:.1} MB.",
+            "✓".green(),
+            removed,
+            freed as f64 / (1024.0 * 1024.0)
+        );
+        return Ok(());
+    }
+
+    if objects_only {
+        let removed = object_cache::clear()?;
+        println!("{} Removed {} cached object(s).", "✓".green(), removed);
+        return Ok(());
+    }
 
     if cache_dir.exists() {
         println!("{} Cleaning cache...", "🧹".yellow());
+        let removed = object_cache::clear()?;
         fs::remove_dir_all(&cache_dir)?;
         fs::create_dir_all(&cache_dir)?;
-        println!("{} Cache cleaned.", "✓".green());
+        println!(
+            "{} Cache cleaned ({} cached object(s) removed).",
+            "✓".green(),
+            removed
+        );
     } else {
         println!("{} Cache already empty.", "✓".green());
     }
     Ok(())
 }
 
-pub fn prune_unused(keep_deps: &[String]) -> Result<()> {
-    let home = dirs::home_dir().context("Could not find home directory")?;
-    let cache_dir = home.join(".cx").join("cache");
+/// Remove cached sources and revisions not locked in `lock`.
+///
+/// Checkouts of a locked source at other revisions go too, as do libraries
+/// built for packages that are no longer locked; the object cache is left
+/// alone (`cx cache clean --objects` handles it).
+pub fn prune_unused(lock: &LockFile) -> Result<()> {
+    let cache_dir = cache_root()?;
 
     if !cache_dir.exists() {
         println!("{} Cache is already empty.", "✓".green());
@@ -79,23 +349,7 @@ pub fn prune_unused(keep_deps: &[String]) -> Result<()> {
     }
 
     println!("{} Pruning unused packages...", "🧹".yellow());
-    let entries = fs::read_dir(&cache_dir)?;
-    let mut removed_count = 0;
-
-    for entry in entries.flatten() {
-        let path = entry.path();
-        if path.is_dir() {
-            let name = entry.file_name().to_string_lossy().to_string();
-            if !keep_deps.contains(&name) {
-                println!("   {} Removing unused: {}", "🗑️".red(), name);
-                if let Err(e) = fs::remove_dir_all(&path) {
-                    println!("     Error removing {}: {}", name, e);
-                } else {
-                    removed_count += 1;
-                }
-            }
-        }
-    }
+    let removed_count = prune_dir(&cache_dir, lock);
 
     if removed_count == 0 {
         println!("{} All cached packages are in use.", "✓".green());
@@ -106,29 +360,183 @@ pub fn prune_unused(keep_deps: &[String]) -> Result<()> {
     Ok(())
 }
 
+fn prune_dir(cache_dir: &Path, lock: &LockFile) -> usize {
+    let git_packages = lock.packages.values().filter(|p| !p.git.is_empty());
+    let keep_sources: HashSet<String> = git_packages.clone().map(|p| source_key(&p.git)).collect();
+    let keep_checkouts: HashSet<(String, String)> = git_packages
+        .map(|p| (source_key(&p.git), p.rev[..p.rev.len().min(12)].to_string()))
+        .collect();
+    let keep_archives: HashSet<String> = lock
+        .packages
+        .values()
+        .filter_map(|p| Some(archive_key(p.url.as_ref()?, p.sha256.as_ref()?)))
+        .collect();
+
+    let mut removed_count = 0;
+    let mut remove = |path: &Path, label: String| {
+        println!("   {} Removing unused: {}", "🗑️".red(), label);
+        if let Err(e) = fs::remove_dir_all(path) {
+            println!("     Error removing {}: {}", label, e);
+        } else {
+            removed_count += 1;
+        }
+    };
+
+    let git_dir = cache_dir.join("git");
+    for db in subdirs(&git_dir.join("db")) {
+        let key = file_name(&db);
+        if !keep_sources.contains(&key) {
+            remove(&db, key);
+        }
+    }
+    for source in subdirs(&git_dir.join("checkouts")) {
+        let key = file_name(&source);
+        for checkout in subdirs(&source) {
+            let leaf = file_name(&checkout);
+            // `<commit>` or `<commit>-<triple>`
+            let commit = leaf.split('-').next().unwrap_or_default().to_string();
+            if !keep_checkouts.contains(&(key.clone(), commit)) {
+                remove(&checkout, format!("{} @ {}", key, leaf));
+                // File list recorded for its checksum
+                let _ = fs::remove_file(checkout.with_file_name(format!("{}.files", leaf)));
+            }
+        }
+        if subdirs(&source).is_empty() {
+            let _ = fs::remove_dir_all(&source);
+        }
+    }
+
+    for archive in subdirs(&cache_dir.join(ARCHIVES_DIR)) {
+        let key = file_name(&archive);
+        if !keep_archives.contains(&key) {
+            remove(&archive, key);
+        }
+    }
+
+    for lib in subdirs(&cache_dir.join(LIBS_DIR)) {
+        let key = file_name(&lib);
+        let name = key.rsplit_once('-').map_or(key.as_str(), |(name, _)| name);
+        if !lock.packages.keys().any(|p| sanitize(p) == name) {
+            remove(&lib, key);
+        }
+    }
+
+    // Clones from the old per-name layout
+    for legacy in subdirs(cache_dir) {
+        let name = file_name(&legacy);
+        if ![OBJECTS_DIR, ARCHIVES_DIR, LIBS_DIR, "git"].contains(&name.as_str()) {
+            remove(&legacy, name);
+        }
+    }
+
+    removed_count
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
-    fn test_prune_keeps_listed_deps() {
-        // Create a temp cache directory
-        let temp_dir = std::env::temp_dir().join("caxe_cache_test");
-        let cache_dir = temp_dir.join(".cx").join("cache");
-        std::fs::create_dir_all(&cache_dir).ok();
-
-        // Create fake dep directories
-        std::fs::create_dir_all(cache_dir.join("raylib")).ok();
-        std::fs::create_dir_all(cache_dir.join("json")).ok();
-        std::fs::create_dir_all(cache_dir.join("unused_lib")).ok();
-
-        // Verify directories exist
-        assert!(cache_dir.join("raylib").exists());
-        assert!(cache_dir.join("json").exists());
-        assert!(cache_dir.join("unused_lib").exists());
-
-        // Cleanup
-        std::fs::remove_dir_all(&temp_dir).ok();
+    fn test_source_key() {
+        let fmt = source_key("https://github.com/fmtlib/fmt.git");
+        assert!(fmt.starts_with("fmt-"));
+        assert_eq!(fmt, source_key("https://github.com/fmtlib/fmt/"));
+        // Forks with the same name get their own repository
+        assert_ne!(fmt, source_key("https://github.com/someone/fmt"));
+        assert!(source_key("git@github.com:fmtlib/fmt.git").starts_with("fmt-"));
+    }
+
+    #[test]
+    fn test_archive_key() {
+        let sha256 = "7D5EA1B9CB6AA0B59CA3DDE1C6ADCB57EF83A1BA8E5432C0ECD06BF439B3AD88";
+        assert_eq!(
+            archive_key("https://www.lua.org/ftp/lua-5.4.6.tar.gz", sha256),
+            "lua-5.4.6-7d5ea1b9cb6aa0b5"
+        );
+        assert_eq!(
+            archive_key("https://example.com/dl/zlib.zip?mirror=1", sha256),
+            "zlib-7d5ea1b9cb6aa0b5"
+        );
+    }
+
+    #[test]
+    fn test_lock_entry_is_exclusive() {
+        let dir = std::env::temp_dir()
+            .join(format!("caxe_lock_test_{}", std::process::id()))
+            .join("fmt-0123");
+        let lock_file = dir.with_file_name(".fmt-0123.lock");
+
+        let lock = lock_entry(&dir).unwrap();
+        let other = fs::File::open(&lock_file).unwrap();
+        assert!(matches!(
+            other.try_lock(),
+            Err(fs::TryLockError::WouldBlock)
+        ));
+        drop(lock);
+        assert!(other.try_lock().is_ok());
+
+        let _ = fs::remove_dir_all(dir.parent().unwrap());
+    }
+
+    #[test]
+    fn test_prune_keeps_locked_revisions() {
+        let cache_dir =
+            std::env::temp_dir().join(format!("caxe_cache_test_{}", std::process::id()));
+        let url = "https://github.com/nlohmann/json";
+        let lua = "https://www.lua.org/ftp/lua-5.4.6.tar.gz";
+        let key = source_key(url);
+        let git_dir = cache_dir.join("git");
+        let checkouts = git_dir.join("checkouts").join(&key);
+        for dir in [
+            git_dir.join("db").join(&key),
+            git_dir
+                .join("db")
+                .join(source_key("https://github.com/raysan5/raylib")),
+            checkouts.join("aaaaaaaaaaaa"),
+            checkouts.join("aaaaaaaaaaaa-aarch64-linux-gnu"),
+            checkouts.join("bbbbbbbbbbbb"),
+            cache_dir.join("json"),
+            cache_dir.join(OBJECTS_DIR),
+            cache_dir
+                .join(ARCHIVES_DIR)
+                .join(archive_key(lua, &"1".repeat(64))),
+            cache_dir
+                .join(ARCHIVES_DIR)
+                .join(archive_key(lua, &"2".repeat(64))),
+            cache_dir.join(LIBS_DIR).join("json-0123456789abcdef"),
+            cache_dir.join(LIBS_DIR).join("imgui-0123456789abcdef"),
+        ] {
+            fs::create_dir_all(dir).unwrap();
+        }
+
+        let mut lock = LockFile::default();
+        lock.insert("json".to_string(), url.to_string(), "a".repeat(40));
+        lock.insert_archive("lua".to_string(), lua.to_string(), "1".repeat(64));
+        assert_eq!(prune_dir(&cache_dir, &lock), 5);
+
+        assert!(git_dir.join("db").join(&key).exists());
+        assert!(checkouts.join("aaaaaaaaaaaa").exists());
+        assert!(checkouts.join("aaaaaaaaaaaa-aarch64-linux-gnu").exists());
+        assert!(!checkouts.join("bbbbbbbbbbbb").exists());
+        assert!(!cache_dir.join("json").exists());
+        assert!(cache_dir.join(OBJECTS_DIR).exists());
+        let archives = cache_dir.join(ARCHIVES_DIR);
+        assert!(archives.join(archive_key(lua, &"1".repeat(64))).exists());
+        assert!(!archives.join(archive_key(lua, &"2".repeat(64))).exists());
+        assert!(
+            cache_dir
+                .join(LIBS_DIR)
+                .join("json-0123456789abcdef")
+                .exists()
+        );
+        assert!(
+            !cache_dir
+                .join(LIBS_DIR)
+                .join("imgui-0123456789abcdef")
+                .exists()
+        );
+
+        fs::remove_dir_all(&cache_dir).ok();
     }
 
     #[test]
@@ -8,12 +8,15 @@
 //! - `cx fmt` - Format code using clang-format
 //! - `cx fmt --check` - Check formatting without modifying files
 //! - `cx check` - Run static analysis using clang-tidy
+//! - `cx check --message-format=json` - Report clang-tidy findings as JSON lines
 
+use crate::build::diagnostics::{self, MessageFormat};
 use crate::build::load_config;
 use crate::deps;
+use crate::status;
 use anyhow::Result;
 use colored::*;
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
 use rayon::prelude::*;
 use std::process::Command;
 use walkdir::WalkDir;
@@ -27,8 +30,8 @@ pub fn format_code(check_only: bool) -> Result<()> {
         .output()
         .is_err()
     {
-        println!("{} clang-format not found.", "x".red());
-        println!(
+        status!("{} clang-format not found.", "x".red());
+        status!(
             "   {} Run {} to install it.",
             "💡".yellow(),
             "cx toolchain install".cyan()
@@ -53,7 +56,7 @@ SpaceAfterCStyleCast: false
 SpacesBeforeTrailingComments: 2
 "#;
         fs::write(clang_format_path, default_style)?;
-        println!(
+        status!(
             "{} Created {} with sensible defaults",
             "✓".green(),
             ".clang-format".cyan()
@@ -65,7 +68,7 @@ SpacesBeforeTrailingComments: 2
     } else {
         "Formatting source code..."
     };
-    println!("{} {}", "🎨".magenta(), mode_msg);
+    status!("{} {}", "🎨".magenta(), mode_msg);
 
     let mut files = Vec::new();
     for entry in WalkDir::new("src").into_iter().filter_map(|e| e.ok()) {
@@ -92,7 +95,7 @@ SpacesBeforeTrailingComments: 2
     }
 
     if files.is_empty() {
-        println!("{} No source files found to format.", "!".yellow());
+        status!("{} No source files found to format.", "!".yellow());
         return Ok(());
     }
 
@@ -153,65 +156,73 @@ SpacesBeforeTrailingComments: 2
 
     if check_only {
         if unformatted_files.is_empty() {
-            println!(
+            status!(
                 "{} All {} files are properly formatted.",
                 "✓".green(),
                 files.len()
             );
             Ok(())
         } else {
-            println!(
+            status!(
                 "{} {} file(s) need formatting:",
                 "x".red(),
                 unformatted_files.len()
             );
             for file in &unformatted_files {
-                println!("   {}", file.yellow());
+                status!("   {}", file.yellow());
             }
-            println!("\n   Run {} to fix formatting.", "cx fmt".cyan().bold());
+            status!("\n   Run {} to fix formatting.", "cx fmt".cyan().bold());
             std::process::exit(1);
         }
     } else {
-        println!("{} Formatted {} files.", "✓".green(), formatted_count);
+        status!("{} Formatted {} files.", "✓".green(), formatted_count);
         Ok(())
     }
 }
 
-pub fn check_code() -> Result<()> {
+pub fn check_code(message_format: MessageFormat) -> Result<()> {
+    let json_output = message_format.is_json();
+    diagnostics::set_message_format(message_format);
+
     if Command::new("clang-tidy")
         .arg("--version")
         .output()
         .is_err()
     {
-        println!(
+        status!(
             "{} clang-tidy not found. Please install it first.",
             "x".red()
         );
         return Ok(());
     }
 
-    println!("{} Checking code with clang-tidy...", "🔍".magenta());
+    status!("{} Checking code with clang-tidy...", "🔍".magenta());
 
-    let config = load_config()?;
+    let mut config = load_config()?;
+    let features = crate::features::apply_project(&mut config)?;
 
     // Fetch dependencies for include paths
     let mut include_flags = Vec::new();
     if let Some(deps) = &config.dependencies
         && !deps.is_empty()
-        && let Ok((paths, cflags, _)) = deps::fetch_dependencies(deps)
     {
-        for p in paths {
+        let resolved = deps::fetch_dependencies(deps, &Default::default())?;
+        for p in resolved.include_paths {
             include_flags.push(format!("-I{}", p.display()));
         }
-        include_flags.extend(cflags);
+        include_flags.extend(resolved.cflags);
+        include_flags.extend(resolved.defines.iter().map(|d| format!("-D{}", d)));
     }
+    include_flags.extend(features.defines.iter().map(|d| format!("-D{}", d)));
 
     let mut files = Vec::new();
     for entry in WalkDir::new("src").into_iter().filter_map(|e| e.ok()) {
         let path = entry.path().to_path_buf();
         if let Some(ext) = path.extension() {
             let s = ext.to_string_lossy();
-            if ["cpp", "hpp", "c", "h", "cc", "cxx"].contains(&s.as_ref()) {
+            if ["cpp", "hpp", "c", "h", "cc", "cxx"].contains(&s.as_ref())
+                && !features.is_excluded(&path)
+            {
                 files.push(path);
             }
         }
@@ -227,6 +238,9 @@ pub fn check_code() -> Result<()> {
             .tick_chars("◐◓◑◒")
             .progress_chars("▰▱ "),
     );
+    if json_output {
+        pb.set_draw_target(ProgressDrawTarget::hidden());
+    }
 
     let warnings: usize = files
         .par_iter()
@@ -260,16 +274,24 @@ pub fn check_code() -> Result<()> {
                     || stdout.contains("error:")
                     || !out.status.success();
 
+                if has_issues && json_output {
+                    for diag in diagnostics::parse(&stdout) {
+                        diagnostics::emit_diagnostic(path, &diag);
+                    }
+                    pb.inc(1);
+                    return 1;
+                }
+
                 if has_issues {
                     pb.suspend(|| {
-                        println!("{} Issues in {}", "!".yellow(), name);
+                        status!("{} Issues in {}", "!".yellow(), name);
                         if !stdout.is_empty() {
-                            println!("{}", stdout.trim());
+                            status!("{}", stdout.trim());
                         }
                         if !stderr.is_empty() {
-                            println!("{}", stderr.trim());
+                            status!("{}", stderr.trim());
                         }
-                        println!("{}", "-".repeat(40).dimmed());
+                        status!("{}", "-".repeat(40).dimmed());
                     });
                     pb.inc(1);
                     return 1;
@@ -283,14 +305,19 @@ pub fn check_code() -> Result<()> {
 
     pb.finish_and_clear();
 
+    if json_output {
+        diagnostics::emit_finished(warnings == 0);
+        return Ok(());
+    }
+
     if warnings == 0 {
-        println!(
+        status!(
             "{} Checked {} files. No issues found.",
             "✓".green(),
             files.len()
         );
     } else {
-        println!(
+        status!(
             "{} Checked {} files. Found issues in {} files.",
             "!".yellow(),
             files.len(),
@@ -6,8 +6,10 @@ use anyhow::Result;
 use colored::*;
 
 use crate::build;
+use crate::config::Dependency;
 use crate::deps;
 use crate::lock;
+#[cfg(windows)]
 use crate::toolchain;
 
 /// Run the `cx doctor` command to diagnose system issues
@@ -70,9 +72,17 @@ pub fn handle_lock(update: bool, check: bool) {
             Ok(lockfile) => match build::load_config() {
                 Ok(config) => {
                     let mut success = true;
-                    if let Some(deps) = config.dependencies {
-                        for (name, _) in deps {
-                            if lockfile.get(&name).is_none() {
+
+                    // 1. Every dependency in cx.toml is locked as written
+                    let mut deps: Vec<(String, Dependency)> = config
+                        .dependencies
+                        .unwrap_or_default()
+                        .into_iter()
+                        .collect();
+                    deps.sort_by(|a, b| a.0.cmp(&b.0));
+                    for (name, dep) in &deps {
+                        match lockfile.get(name) {
+                            None => {
                                 println!(
                                     "{} Dependency '{}' missing from cx.lock",
                                     "x".red(),
@@ -80,15 +90,42 @@ pub fn handle_lock(update: bool, check: bool) {
                                 );
                                 success = false;
                             }
+                            Some(entry) if !entry.matches(dep) => {
+                                println!(
+                                    "{} Dependency '{}' changed in cx.toml since it was locked",
+                                    "x".red(),
+                                    name
+                                );
+                                success = false;
+                            }
+                            Some(_) => {}
                         }
                     }
-                    if success {
+
+                    // 2. Cached trees and system packages still match the lock
+                    let mut drifted = false;
+                    for (name, entry) in &lockfile.packages {
+                        if let Some(problem) = deps::check_locked(entry) {
+                            println!("{} {}: {}", "x".red(), name, problem);
+                            drifted = true;
+                        }
+                    }
+
+                    if success && !drifted {
                         println!("{} Lockfile is in sync.", "✓".green());
                     } else {
-                        println!(
-                            "{} Lockfile out of sync. Run 'cx lock --update'.",
-                            "x".red()
-                        );
+                        if !success {
+                            println!(
+                                "{} Lockfile out of sync. Run 'cx build' to re-lock, or 'cx lock --update'.",
+                                "x".red()
+                            );
+                        }
+                        if drifted {
+                            println!(
+                                "{} Cache differs from cx.lock. Run 'cx cache clean' and rebuild to fetch clean copies.",
+                                "x".red()
+                            );
+                        }
                         std::process::exit(1);
                     }
                 }
@@ -125,7 +162,7 @@ pub fn handle_sync() {
             if let Some(deps) = config.dependencies {
                 // 2. Fetch/Sync
                 // fetch_dependencies handles reading cx.lock and checking out specific revisions
-                match deps::fetch_dependencies(&deps) {
+                match deps::fetch_dependencies(&deps, &Default::default()) {
                     Ok(_) => println!("{} Dependencies synchronized.", "✓".green()),
                     Err(e) => {
                         eprintln!("Error synchronizing: {}", e);
@@ -8,7 +8,7 @@ use std::path::Path;
 use walkdir::WalkDir;
 
 use crate::build;
-use crate::config::CxConfig;
+use crate::config::{ArtifactKind, CxConfig};
 
 /// Generate format options
 #[derive(Clone, Debug)]
@@ -53,6 +53,17 @@ fn generate_cmake(config: &CxConfig) -> Result<()> {
     // Convert edition to CMake standard
     let cpp_standard = edition.replace("c++", "").replace("c", "");
 
+    let kind = config
+        .build
+        .as_ref()
+        .map(|b| b.artifact_kind())
+        .unwrap_or_default();
+    let (target_comment, target_decl) = match kind {
+        ArtifactKind::Bin => ("Executable", "add_executable(${PROJECT_NAME}"),
+        ArtifactKind::Staticlib => ("Static library", "add_library(${PROJECT_NAME} STATIC"),
+        ArtifactKind::Sharedlib => ("Shared library", "add_library(${PROJECT_NAME} SHARED"),
+    };
+
     let mut cmake = format!(
         r#"cmake_minimum_required(VERSION 3.16)
 project({name} LANGUAGES CXX)
@@ -64,8 +75,8 @@ set(CMAKE_EXPORT_COMPILE_COMMANDS ON)
 # Source files
 file(GLOB_RECURSE SOURCES "src/*.cpp" "src/*.c")
 
-# Executable
-add_executable(${{PROJECT_NAME}} ${{SOURCES}})
+# {target_comment}
+{target_decl} ${{SOURCES}})
 
 # Include directories
 target_include_directories(${{PROJECT_NAME}} PRIVATE src)
@@ -170,9 +181,9 @@ rule link
                 .and_then(|e| e.to_str())
                 .is_some_and(|e| ["cpp", "cc", "cxx", "c"].contains(&e))
             {
-                let obj_name = path.file_stem().unwrap_or_default().to_string_lossy();
-                let obj_ext = if is_msvc { "obj" } else { "o" };
-                let obj_path = format!("build/{}.{}", obj_name, obj_ext);
+                let obj_path = build::utils::get_object_path(Path::new("build"), path, is_msvc)
+                    .to_string_lossy()
+                    .replace('\\', "/");
 
                 ninja.push_str(&format!("build {}: compile {}\n", obj_path, path.display()));
                 obj_files.push(obj_path);
@@ -2,6 +2,8 @@
 //!
 //! Handles `cx target` subcommands for managing cross-compilation targets.
 
+use crate::manifest::Manifest;
+use crate::toolchain::cross::KNOWN_TARGETS;
 use anyhow::Result;
 use colored::*;
 use std::path::Path;
@@ -53,10 +55,15 @@ pub fn handle_target_command(op: &Option<TargetOp>) -> Result<()> {
 
             // Show configured targets if in a project
             if config_path.exists()
-                && let Ok(content) = std::fs::read_to_string(config_path)
+                && let Ok(manifest) = Manifest::open()
             {
-                if content.contains("[targets]") || content.contains("targets =") {
-                    println!("{} Project targets configured", "✓".green());
+                let targets = manifest.targets();
+                if !targets.is_empty() {
+                    println!(
+                        "{} Project targets: {}",
+                        "✓".green(),
+                        targets.join(", ").cyan()
+                    );
                 } else {
                     println!(
                         "{} No targets configured. Use {} to add one.",
@@ -82,18 +89,7 @@ pub fn handle_target_command(op: &Option<TargetOp>) -> Result<()> {
                 return Ok(());
             }
 
-            let valid_targets = [
-                "windows-x64",
-                "windows-x64-gnu",
-                "linux-x64",
-                "linux-arm64",
-                "macos-x64",
-                "macos-arm64",
-                "wasm32",
-                "esp32",
-            ];
-
-            if !valid_targets.contains(&name.as_str()) {
+            if !KNOWN_TARGETS.iter().any(|(known, _)| known == name) {
                 println!(
                     "{} Unknown target '{}'. Run {} to see available targets.",
                     "x".red(),
@@ -103,23 +99,12 @@ pub fn handle_target_command(op: &Option<TargetOp>) -> Result<()> {
                 return Ok(());
             }
 
-            // Read and update config
-            let mut content = std::fs::read_to_string(config_path)?;
-
-            if content.contains(&format!("\"{}\"", name)) {
+            let mut manifest = Manifest::open()?;
+            if !manifest.add_target(name)? {
                 println!("{} Target '{}' already configured.", "!".yellow(), name);
                 return Ok(());
             }
-
-            // Add targets section if not present
-            if !content.contains("[targets]") {
-                content.push_str(&format!("\n[targets]\nlist = [\"{}\"]\n", name));
-            } else {
-                // Append to existing targets list
-                content = content.replace("list = [", &format!("list = [\"{}\", ", name));
-            }
-
-            std::fs::write(config_path, content)?;
+            manifest.save()?;
             println!("{} Added target: {}", "✓".green(), name.cyan());
             println!(
                 "   Build with: {}",
@@ -132,13 +117,12 @@ pub fn handle_target_command(op: &Option<TargetOp>) -> Result<()> {
                 return Ok(());
             }
 
-            let content = std::fs::read_to_string(config_path)?;
-            let new_content = content
-                .replace(&format!("\"{}\", ", name), "")
-                .replace(&format!(", \"{}\"", name), "")
-                .replace(&format!("\"{}\"", name), "");
-
-            std::fs::write(config_path, new_content)?;
+            let mut manifest = Manifest::open()?;
+            if !manifest.remove_target(name) {
+                println!("{} Target '{}' is not configured.", "!".yellow(), name);
+                return Ok(());
+            }
+            manifest.save()?;
             println!("{} Removed target: {}", "✓".green(), name);
         }
         Some(TargetOp::Default { name }) => {
@@ -147,25 +131,9 @@ pub fn handle_target_command(op: &Option<TargetOp>) -> Result<()> {
                 return Ok(());
             }
 
-            let mut content = std::fs::read_to_string(config_path)?;
-
-            // Add or update default_target
-            if content.contains("default_target") {
-                // Replace existing
-                let re = regex::Regex::new(r#"default_target\s*=\s*"[^"]*""#).unwrap();
-                content = re
-                    .replace(&content, &format!("default_target = \"{}\"", name))
-                    .to_string();
-            } else if content.contains("[targets]") {
-                content = content.replace(
-                    "[targets]",
-                    &format!("[targets]\ndefault_target = \"{}\"", name),
-                );
-            } else {
-                content.push_str(&format!("\n[targets]\ndefault_target = \"{}\"\n", name));
-            }
-
-            std::fs::write(config_path, content)?;
+            let mut manifest = Manifest::open()?;
+            manifest.set_default_target(name)?;
+            manifest.save()?;
             println!("{} Set default target: {}", "✓".green(), name.cyan());
         }
     }
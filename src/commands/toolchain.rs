@@ -4,11 +4,18 @@
 
 use anyhow::Result;
 use colored::*;
+#[cfg(windows)]
 use inquire::Select;
+#[cfg(windows)]
 use std::path::{Path, PathBuf};
 
+#[cfg(windows)]
 use crate::build;
+#[cfg(windows)]
+use crate::manifest::Manifest;
+#[cfg(windows)]
 use crate::toolchain;
+#[cfg(windows)]
 use crate::ui;
 
 /// Toolchain subcommand operations
@@ -152,50 +159,18 @@ pub fn handle_toolchain_command(op: &Option<ToolchainOp>) -> Result<()> {
                             toolchain::CompilerType::GCC => "g++",
                         };
 
-                        // Read current cx.toml
-                        if let Ok(toml_content) = std::fs::read_to_string("cx.toml") {
-                            let new_content = if toml_content.contains("[build]") {
-                                // Update existing [build] section
-                                if toml_content.contains("compiler =") {
-                                    // Replace existing compiler line
-                                    let mut result = String::new();
-                                    for line in toml_content.lines() {
-                                        if line.trim().starts_with("compiler =") {
-                                            result.push_str(&format!(
-                                                "compiler = \"{}\"",
-                                                compiler_str
-                                            ));
-                                        } else {
-                                            result.push_str(line);
-                                        }
-                                        result.push('\n');
-                                    }
-                                    result
-                                } else {
-                                    // Add compiler to existing [build] section
-                                    toml_content.replace(
-                                        "[build]",
-                                        &format!("[build]\ncompiler = \"{}\"", compiler_str),
-                                    )
-                                }
-                            } else {
-                                // Add new [build] section
-                                format!(
-                                    "{}\n[build]\ncompiler = \"{}\"\n",
-                                    toml_content.trim_end(),
-                                    compiler_str
-                                )
-                            };
-
-                            if let Err(e) = std::fs::write("cx.toml", new_content) {
-                                println!("{} Failed to update cx.toml: {}", "x".red(), e);
-                            } else {
-                                println!(
-                                    "  {} Updated cx.toml with compiler = \"{}\"",
-                                    "✓".green(),
-                                    compiler_str.cyan()
-                                );
-                            }
+                        let updated = Manifest::open().and_then(|mut manifest| {
+                            manifest.set_build_value("compiler", compiler_str)?;
+                            manifest.save()
+                        });
+                        if let Err(e) = updated {
+                            println!("{} Failed to update cx.toml: {}", "x".red(), e);
+                        } else {
+                            println!(
+                                "  {} Updated cx.toml with compiler = \"{}\"",
+                                "✓".green(),
+                                compiler_str.cyan()
+                            );
                         }
                     }
                 }
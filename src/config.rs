@@ -20,7 +20,7 @@
 //! ```
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 /// Root configuration structure parsed from `cx.toml`.
 ///
@@ -42,13 +42,21 @@ pub struct CxConfig {
     pub workspace: Option<WorkspaceConfig>,
     /// Optional Arduino/IoT configuration.
     pub arduino: Option<ArduinoConfig>,
-    /// Named profiles for cross-compilation: [profile:name]
+    /// Additional executables: [[bin]]
+    #[serde(rename = "bin", default, skip_serializing_if = "Option::is_none")]
+    pub bins: Option<Vec<BinTarget>>,
+    /// Optional cross-compilation targets: [targets]
+    pub targets: Option<TargetsConfig>,
+    /// Optional parts of the package: [features]
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub features: BTreeMap<String, Feature>,
+    /// Named profiles: [profile.name] (or the older ["profile:name"])
     #[serde(skip)]
     pub profiles: HashMap<String, Profile>,
 }
 
-/// Build profile for cross-compilation
-/// Used with [profile:name] sections in cx.toml
+/// Named build profile (`cx build --profile <name>`)
+/// Used with [profile.name] sections in cx.toml
 #[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct Profile {
     /// Base profile to inherit from (e.g., "release", "debug")
@@ -63,6 +71,93 @@ pub struct Profile {
     pub libs: Option<Vec<String>>,
     /// Output binary name override
     pub bin: Option<String>,
+    /// Optimization level: 0-3, "s" or "z" (default: 3 in release, none in debug)
+    #[serde(default, deserialize_with = "deserialize_opt_level")]
+    pub opt_level: Option<String>,
+    /// Emit debug info (default: on in debug, off in release)
+    pub debug: Option<bool>,
+    /// Treat compiler warnings as errors
+    pub warnings_as_errors: Option<bool>,
+    /// Preprocessor defines, e.g. ["NDEBUG", "LOG_LEVEL=2"]
+    pub defines: Option<Vec<String>>,
+    /// Linker flags
+    pub ldflags: Option<Vec<String>>,
+    /// Link Time Optimization (same as --lto)
+    pub lto: Option<bool>,
+    /// Sanitizers, e.g. "address,undefined" (same as --sanitize)
+    pub sanitize: Option<String>,
+}
+
+impl Profile {
+    /// Layer this profile over `base`: lists are appended, other settings
+    /// replace the base's
+    pub fn inherit(&self, base: &Profile) -> Profile {
+        fn concat(base: &Option<Vec<String>>, own: &Option<Vec<String>>) -> Option<Vec<String>> {
+            match (base, own) {
+                (Some(base), Some(own)) => Some(base.iter().chain(own).cloned().collect()),
+                (base, own) => own.clone().or_else(|| base.clone()),
+            }
+        }
+        Profile {
+            base: self.base.clone(),
+            target: self.target.clone().or_else(|| base.target.clone()),
+            compiler: self.compiler.clone().or_else(|| base.compiler.clone()),
+            flags: concat(&base.flags, &self.flags),
+            libs: concat(&base.libs, &self.libs),
+            bin: self.bin.clone().or_else(|| base.bin.clone()),
+            opt_level: self.opt_level.clone().or_else(|| base.opt_level.clone()),
+            debug: self.debug.or(base.debug),
+            warnings_as_errors: self.warnings_as_errors.or(base.warnings_as_errors),
+            defines: concat(&base.defines, &self.defines),
+            ldflags: concat(&base.ldflags, &self.ldflags),
+            lto: self.lto.or(base.lto),
+            sanitize: self.sanitize.clone().or_else(|| base.sanitize.clone()),
+        }
+    }
+}
+
+/// Accept `opt_level = 2` as well as `opt_level = "s"`
+fn deserialize_opt_level<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Level {
+        Number(u8),
+        Name(String),
+    }
+    Ok(
+        Option::<Level>::deserialize(deserializer)?.map(|level| match level {
+            Level::Number(n) => n.to_string(),
+            Level::Name(name) => name,
+        }),
+    )
+}
+
+/// Cross-compilation targets, managed by `cx target`
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct TargetsConfig {
+    /// Targets added with `cx target add`
+    pub list: Option<Vec<String>>,
+    /// Target used by `cx build` when `--target` is not given
+    pub default_target: Option<String>,
+    /// Per-target toolchain overrides: [targets.<name>]
+    #[serde(flatten)]
+    pub settings: HashMap<String, TargetSettings>,
+}
+
+/// Toolchain overrides for one target, e.g. `[targets.linux-arm64]`
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct TargetSettings {
+    /// Cross compiler (e.g. "aarch64-linux-gnu-g++", "clang++")
+    pub compiler: Option<String>,
+    /// Prefix of the GNU cross tools (defaults to "<triple>-")
+    pub prefix: Option<String>,
+    /// Sysroot passed to the compiler, linker and pkg-config
+    pub sysroot: Option<String>,
+    /// Extra compiler flags for this target
+    pub flags: Option<Vec<String>>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Default, Clone)]
@@ -87,6 +182,8 @@ pub struct TestConfig {
     pub single_binary: Option<bool>,
 }
 
+// Parsed once per cx.toml; boxing the table form isn't worth the churn
+#[allow(clippy::large_enum_variant)]
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(untagged)]
 pub enum Dependency {
@@ -97,16 +194,198 @@ pub enum Dependency {
     Complex {
         git: Option<String>,
         pkg: Option<String>,
+        /// Local directory, relative to the cx.toml declaring it
+        path: Option<String>,
+        /// Release archive (.tar.gz, .tgz, .tar.xz or .zip) to download
+        url: Option<String>,
+        /// Required checksum of the `url` archive
+        sha256: Option<String>,
         // Pinning Features
+        /// Semver requirement (e.g. "^10.2") resolved against the repo's tags
+        version: Option<String>,
         branch: Option<String>,
         tag: Option<String>,
         rev: Option<String>,
         // Build Features
         build: Option<String>,
         output: Option<String>,
+        /// Files or directories compiled into a cached static library
+        sources: Option<Vec<String>>,
+        /// Public header directories (default: `include/`, else the root)
+        include_dirs: Option<Vec<String>>,
+        /// Macros the dependency's headers need (`NAME` or `NAME=VALUE`)
+        defines: Option<Vec<String>>,
+        /// System libraries it needs, linked by name
+        libs: Option<Vec<String>>,
+        /// Library search directories, relative to the dependency
+        link_dirs: Option<Vec<String>>,
+        /// Only used when a feature enables it (`dep:<name>`)
+        optional: Option<bool>,
+        /// Features of the dependency to enable
+        features: Option<Vec<String>>,
+        /// Also enable the dependency's `default` feature (default: true)
+        default_features: Option<bool>,
+        /// Build and install with CMake instead
+        cmake: Option<CMakeConfig>,
+        /// Build and install with Meson instead
+        meson: Option<MesonConfig>,
+        /// Build and install with `./configure && make install` instead
+        autotools: Option<AutotoolsConfig>,
     },
 }
 
+impl Dependency {
+    /// `{ git = "<url>" }`
+    pub fn git(url: impl Into<String>) -> Self {
+        Self::table(Some(url.into()), None, None)
+    }
+
+    /// `{ pkg = "<name>" }` (pkg-config)
+    pub fn pkg(name: impl Into<String>) -> Self {
+        Self::table(None, Some(name.into()), None)
+    }
+
+    /// `{ path = "<dir>" }`
+    pub fn path(dir: impl Into<String>) -> Self {
+        Self::table(None, None, Some(dir.into()))
+    }
+
+    /// Table form with every other key unset; fill the rest in by matching
+    /// on `Dependency::Complex { .. }`
+    fn table(git: Option<String>, pkg: Option<String>, path: Option<String>) -> Self {
+        Self::Complex {
+            git,
+            pkg,
+            path,
+            url: None,
+            sha256: None,
+            version: None,
+            branch: None,
+            tag: None,
+            rev: None,
+            build: None,
+            output: None,
+            sources: None,
+            include_dirs: None,
+            defines: None,
+            libs: None,
+            link_dirs: None,
+            optional: None,
+            features: None,
+            default_features: None,
+            cmake: None,
+            meson: None,
+            autotools: None,
+        }
+    }
+}
+
+/// `cmake = { options = { ... }, targets = [...] }` on a dependency
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct CMakeConfig {
+    /// Cache variables passed as `-D<name>=<value>` (booleans become ON/OFF)
+    #[serde(default)]
+    pub options: BTreeMap<String, toml::Value>,
+    /// Targets to build and link (default: everything installed)
+    #[serde(default)]
+    pub targets: Vec<String>,
+}
+
+impl CMakeConfig {
+    /// `-D` arguments for `options`
+    pub fn define_args(&self) -> Vec<String> {
+        self.options
+            .iter()
+            .map(|(name, value)| {
+                let value = match value {
+                    toml::Value::Boolean(true) => "ON".to_string(),
+                    toml::Value::Boolean(false) => "OFF".to_string(),
+                    toml::Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                format!("-D{}={}", name, value)
+            })
+            .collect()
+    }
+}
+
+/// `meson = { options = { ... } }` on a dependency
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct MesonConfig {
+    /// Build options passed as `-D<name>=<value>`
+    #[serde(default)]
+    pub options: BTreeMap<String, toml::Value>,
+}
+
+impl MesonConfig {
+    /// `-D` arguments for `options`
+    pub fn define_args(&self) -> Vec<String> {
+        self.options
+            .iter()
+            .map(|(name, value)| match value {
+                toml::Value::String(s) => format!("-D{}={}", name, s),
+                other => format!("-D{}={}", name, other),
+            })
+            .collect()
+    }
+}
+
+/// `autotools = { args = [...] }` on a dependency
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct AutotoolsConfig {
+    /// Extra `./configure` arguments (e.g. `--disable-docs`)
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+/// An entry of `[features]`, see `crate::features`
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum Feature {
+    // Case: vulkan = ["dep:volk", "imgui/vulkan"]
+    Enables(Vec<String>),
+
+    // Case: vulkan = { enables = [...], defines = [...], sources = [...] }
+    Detailed(FeatureConfig),
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct FeatureConfig {
+    /// Other features, optional dependencies (`dep:<name>`) and features of
+    /// dependencies (`<dep>/<feature>`)
+    #[serde(default)]
+    pub enables: Vec<String>,
+    /// Preprocessor defines while the feature is on
+    #[serde(default)]
+    pub defines: Vec<String>,
+    /// Files or directories only compiled while the feature is on
+    #[serde(default)]
+    pub sources: Vec<String>,
+}
+
+impl Feature {
+    pub fn enables(&self) -> &[String] {
+        match self {
+            Feature::Enables(list) => list,
+            Feature::Detailed(config) => &config.enables,
+        }
+    }
+
+    pub fn defines(&self) -> &[String] {
+        match self {
+            Feature::Enables(_) => &[],
+            Feature::Detailed(config) => &config.defines,
+        }
+    }
+
+    pub fn sources(&self) -> &[String] {
+        match self {
+            Feature::Enables(_) => &[],
+            Feature::Detailed(config) => &config.sources,
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug, Default, Clone)]
 pub struct PackageConfig {
     pub name: String,
@@ -114,12 +393,63 @@ pub struct PackageConfig {
     pub version: String,
     #[serde(default = "default_edition")]
     pub edition: String,
+    /// C standard for `.c` files (e.g., "c11", "c17", "gnu11")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub c_edition: Option<String>,
+}
+
+impl PackageConfig {
+    /// Get the C standard for `.c` files.
+    /// Falls back to `edition` when it already names a C standard, else "c17".
+    pub fn c_edition(&self) -> String {
+        if let Some(c_edition) = &self.c_edition {
+            return c_edition.clone();
+        }
+        let edition = self.edition.to_lowercase();
+        let is_c_standard =
+            !edition.contains("++") && (edition.starts_with('c') || edition.starts_with("gnu"));
+        if is_c_standard {
+            edition
+        } else {
+            "c17".to_string()
+        }
+    }
+}
+
+/// Kind of artifact produced by `cx build`.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ArtifactKind {
+    /// Executable program (default)
+    #[default]
+    Bin,
+    /// Static library (`lib<name>.a` / `<name>.lib`)
+    Staticlib,
+    /// Shared library (`lib<name>.so` / `lib<name>.dylib` / `<name>.dll`)
+    Sharedlib,
+}
+
+impl ArtifactKind {
+    /// Check if this artifact is a library (static or shared)
+    pub fn is_library(&self) -> bool {
+        matches!(self, ArtifactKind::Staticlib | ArtifactKind::Sharedlib)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ArtifactKind::Bin => "bin",
+            ArtifactKind::Staticlib => "staticlib",
+            ArtifactKind::Sharedlib => "sharedlib",
+        }
+    }
 }
 
 #[derive(Deserialize, Serialize, Debug, Default, Clone)]
 pub struct BuildConfig {
     pub compiler: Option<String>,
     pub bin: Option<String>,
+    /// Artifact kind: "bin" (default), "staticlib" or "sharedlib"
+    pub kind: Option<ArtifactKind>,
     /// Compiler flags (new, preferred)
     pub flags: Option<Vec<String>>,
     /// Deprecated: use `flags` instead
@@ -128,9 +458,23 @@ pub struct BuildConfig {
     /// Linker flags (e.g., /SUBSYSTEM:WINDOWS)
     pub ldflags: Option<Vec<String>>,
     pub sources: Option<Vec<String>>,
+    /// Public header directories for projects depending on this library
+    /// (default: `include/`, else `src/`)
+    pub include_dirs: Option<Vec<String>>,
     pub pch: Option<String>,
     /// Windows subsystem (console or windows)
     pub subsystem: Option<String>,
+    /// Max parallel compile jobs (default: one per CPU core)
+    pub jobs: Option<usize>,
+    /// C++20 modules: scan imports and compile in dependency order
+    /// (default: on when a source declares or imports a module)
+    pub modules: Option<bool>,
+    /// Unity (jumbo) build: compile sources in generated batches
+    pub unity: Option<bool>,
+    /// Sources per unity batch (default: 8)
+    pub unity_batch_size: Option<usize>,
+    /// Files or directories always compiled on their own in unity builds
+    pub unity_exclude: Option<Vec<String>>,
 }
 
 impl BuildConfig {
@@ -143,6 +487,50 @@ impl BuildConfig {
     pub fn uses_deprecated_cflags(&self) -> bool {
         self.cflags.is_some() && self.flags.is_none()
     }
+
+    /// Get the artifact kind, defaulting to an executable
+    pub fn artifact_kind(&self) -> ArtifactKind {
+        self.kind.unwrap_or_default()
+    }
+}
+
+/// Extra executable target declared with a `[[bin]]` table.
+///
+/// Each binary owns its `main` file (and optional extra `sources`) and links
+/// against the objects of every other source in the project.
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct BinTarget {
+    /// Executable name
+    pub name: String,
+    /// Entry point (defaults to `src/bin/<name>.cpp`)
+    pub main: Option<String>,
+    /// Sources compiled only into this binary
+    pub sources: Option<Vec<String>>,
+}
+
+impl BinTarget {
+    /// Get the entry point, probing `src/bin/<name>.{cpp,cc,cxx,c}` when unset
+    pub fn main_path(&self) -> String {
+        if let Some(main) = &self.main {
+            return main.clone();
+        }
+        for ext in ["cpp", "cc", "cxx", "c"] {
+            let candidate = format!("src/bin/{}.{}", self.name, ext);
+            if std::path::Path::new(&candidate).exists() {
+                return candidate;
+            }
+        }
+        format!("src/bin/{}.cpp", self.name)
+    }
+
+    /// All files owned by this binary (main first)
+    pub fn owned_sources(&self) -> Vec<String> {
+        let mut files = vec![self.main_path()];
+        if let Some(sources) = &self.sources {
+            files.extend(sources.iter().cloned());
+        }
+        files
+    }
 }
 
 fn default_edition() -> String {
@@ -170,23 +558,34 @@ pub fn create_ephemeral_config(
             } else {
                 "c23".to_string()
             },
+            c_edition: None,
         },
         build: Some(BuildConfig {
             compiler: Some(compiler.to_string()),
             bin: Some(bin_name.to_string()),
+            kind: None,
             flags: None,
             cflags: None,
             libs: None,
             ldflags: None,
             sources: Some(vec![name.to_string()]),
+            include_dirs: None,
             pch: None,
             subsystem: None,
+            jobs: None,
+            modules: None,
+            unity: None,
+            unity_batch_size: None,
+            unity_exclude: None,
         }),
         dependencies: None,
         scripts: None,
         test: None,
         workspace: None,
         arduino: None,
+        bins: None,
+        targets: None,
+        features: BTreeMap::new(),
         profiles: HashMap::new(),
     }
 }
@@ -287,6 +686,119 @@ libs = ["pthread"]
         );
     }
 
+    #[test]
+    fn test_parse_library_kind() {
+        let toml_str = r#"
+[package]
+name = "mylib"
+version = "0.1.0"
+
+[build]
+kind = "staticlib"
+"#;
+        let config: CxConfig = toml::from_str(toml_str).unwrap();
+        let build = config.build.unwrap();
+        assert_eq!(build.artifact_kind(), ArtifactKind::Staticlib);
+        assert!(build.artifact_kind().is_library());
+        assert_eq!(BuildConfig::default().artifact_kind(), ArtifactKind::Bin);
+    }
+
+    #[test]
+    fn test_package_c_edition() {
+        let mut package = PackageConfig {
+            name: "mix".to_string(),
+            version: "0.1.0".to_string(),
+            edition: "c++20".to_string(),
+            c_edition: None,
+        };
+        assert_eq!(package.c_edition(), "c17");
+
+        package.edition = "gnu11".to_string();
+        assert_eq!(package.c_edition(), "gnu11");
+
+        package.c_edition = Some("c99".to_string());
+        assert_eq!(package.c_edition(), "c99");
+    }
+
+    #[test]
+    fn test_parse_bin_targets() {
+        let toml_str = r#"
+[package]
+name = "tools"
+version = "0.1.0"
+
+[[bin]]
+name = "server"
+main = "apps/server.cpp"
+sources = ["apps/server_routes.cpp"]
+
+[[bin]]
+name = "migrate"
+"#;
+        let config: CxConfig = toml::from_str(toml_str).unwrap();
+        let bins = config.bins.unwrap();
+        assert_eq!(bins.len(), 2);
+        assert_eq!(
+            bins[0].owned_sources(),
+            vec!["apps/server.cpp", "apps/server_routes.cpp"]
+        );
+        assert_eq!(bins[1].main_path(), "src/bin/migrate.cpp");
+    }
+
+    #[test]
+    fn test_profile_inherit() {
+        let base: Profile = toml::from_str(
+            r#"
+opt_level = 2
+defines = ["BOARD=1"]
+lto = true
+"#,
+        )
+        .unwrap();
+        let profile: Profile = toml::from_str(
+            r#"
+base = "fast"
+opt_level = "s"
+defines = ["TRACE"]
+warnings_as_errors = true
+"#,
+        )
+        .unwrap();
+        let resolved = profile.inherit(&base);
+        assert_eq!(resolved.opt_level.as_deref(), Some("s"));
+        assert_eq!(
+            resolved.defines,
+            Some(vec!["BOARD=1".to_string(), "TRACE".to_string()])
+        );
+        assert_eq!(resolved.lto, Some(true));
+        assert_eq!(resolved.warnings_as_errors, Some(true));
+        assert_eq!(base.opt_level.as_deref(), Some("2"));
+    }
+
+    #[test]
+    fn test_parse_targets() {
+        let toml_str = r#"
+[package]
+name = "test"
+version = "1.0.0"
+
+[targets]
+list = ["linux-arm64", "windows-x64-gnu"]
+default_target = "linux-arm64"
+
+[targets.linux-arm64]
+sysroot = "/opt/sysroots/arm64"
+"#;
+        let config: CxConfig = toml::from_str(toml_str).unwrap();
+        let targets = config.targets.unwrap();
+        assert_eq!(targets.list.unwrap().len(), 2);
+        assert_eq!(targets.default_target.as_deref(), Some("linux-arm64"));
+        assert_eq!(
+            targets.settings["linux-arm64"].sysroot.as_deref(),
+            Some("/opt/sysroots/arm64")
+        );
+    }
+
     #[test]
     fn test_dependency_simple() {
         let toml_str = r#"
@@ -325,4 +837,79 @@ sdl2 = { git = "https://github.com/libsdl-org/SDL", tag = "release-2.30.0" }
             _ => panic!("Expected Complex dependency"),
         }
     }
+
+    #[test]
+    fn test_dependency_cmake() {
+        let toml_str = r#"
+[package]
+name = "test"
+version = "1.0.0"
+
+[dependencies]
+glfw = { git = "https://github.com/glfw/glfw", cmake = { options = { GLFW_BUILD_DOCS = false, GLFW_VULKAN_STATIC = "ON", JOBS = 4 }, targets = ["glfw"] } }
+"#;
+        let config: CxConfig = toml::from_str(toml_str).unwrap();
+        let deps = config.dependencies.unwrap();
+        match &deps["glfw"] {
+            Dependency::Complex {
+                cmake: Some(cmake), ..
+            } => {
+                assert_eq!(cmake.targets, vec!["glfw"]);
+                assert_eq!(
+                    cmake.define_args(),
+                    vec![
+                        "-DGLFW_BUILD_DOCS=OFF",
+                        "-DGLFW_VULKAN_STATIC=ON",
+                        "-DJOBS=4"
+                    ]
+                );
+            }
+            _ => panic!("Expected CMake dependency"),
+        }
+    }
+
+    #[test]
+    fn test_dependency_path() {
+        let toml_str = r#"
+[package]
+name = "test"
+version = "1.0.0"
+
+[dependencies]
+common = { path = "../common" }
+"#;
+        let config: CxConfig = toml::from_str(toml_str).unwrap();
+        let deps = config.dependencies.unwrap();
+        match &deps["common"] {
+            Dependency::Complex { path, git, .. } => {
+                assert_eq!(path.as_deref(), Some("../common"));
+                assert!(git.is_none());
+            }
+            _ => panic!("Expected Complex dependency"),
+        }
+    }
+
+    #[test]
+    fn test_dependency_archive() {
+        let toml_str = r#"
+[package]
+name = "test"
+version = "1.0.0"
+
+[dependencies]
+lua = { url = "https://www.lua.org/ftp/lua-5.4.6.tar.gz", sha256 = "7d5ea1b9" }
+"#;
+        let config: CxConfig = toml::from_str(toml_str).unwrap();
+        let deps = config.dependencies.unwrap();
+        match &deps["lua"] {
+            Dependency::Complex { url, sha256, .. } => {
+                assert_eq!(
+                    url.as_deref(),
+                    Some("https://www.lua.org/ftp/lua-5.4.6.tar.gz")
+                );
+                assert_eq!(sha256.as_deref(), Some("7d5ea1b9"));
+            }
+            _ => panic!("Expected Complex dependency"),
+        }
+    }
 }
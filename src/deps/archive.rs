@@ -0,0 +1,232 @@
+//! Release archive dependencies.
+//!
+//! `{ url = ".../lua-5.4.6.tar.gz", sha256 = "..." }` is downloaded, checked
+//! against its SHA256 and extracted into `~/.cx/cache/archives`. The checksum
+//! is mandatory and a mismatch is a hard error; since it is also the cache
+//! key, each archive is downloaded once and shared by every project.
+//!
+//! Supported formats: `.tar.gz`/`.tgz`, `.tar.xz`/`.txz` and `.zip`.
+
+use super::fetch::{move_into_place, temp_sibling, verify_sha256};
+use crate::status;
+use anyhow::{Context, Result, bail};
+use colored::*;
+use std::fs;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    TarGz,
+    TarXz,
+    Zip,
+}
+
+impl Format {
+    /// Format from the URL's file name
+    fn from_url(url: &str) -> Option<Self> {
+        let file = url.split(['?', '#']).next().unwrap_or(url).to_lowercase();
+        if file.ends_with(".tar.gz") || file.ends_with(".tgz") {
+            Some(Format::TarGz)
+        } else if file.ends_with(".tar.xz") || file.ends_with(".txz") {
+            Some(Format::TarXz)
+        } else if file.ends_with(".zip") {
+            Some(Format::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+/// Download, verify and extract an archive dependency into the cache and
+/// return its directory. `variant` as for [`crate::cache::checkout_dir`].
+pub fn fetch_archive(
+    name: &str,
+    url: &str,
+    sha256: &str,
+    variant: Option<&str>,
+) -> Result<PathBuf> {
+    let dir = crate::cache::archive_dir(url, sha256, variant)?;
+    if dir.exists() {
+        status!("   {} Using cached: {}", "⚡".green(), name);
+        return Ok(dir);
+    }
+
+    crate::fetch_mode::ensure_online(&format!("download '{}' ({})", name, url))?;
+    status!("   {} Downloading {}...", "📦".blue(), name);
+    if let Some(parent) = dir.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = temp_sibling(&dir);
+    let _ = fs::remove_dir_all(&tmp);
+    if let Err(e) = download_and_extract(url, sha256, &tmp) {
+        let _ = fs::remove_dir_all(&tmp);
+        if let Some(parent) = dir.parent() {
+            // Only succeeds while empty, i.e. nothing else was cached for it
+            let _ = fs::remove_dir(parent);
+        }
+        return Err(e.context(format!("Failed to fetch archive dependency '{}'", name)));
+    }
+    move_into_place(&tmp, &dir)?;
+    super::checksum::record(&dir)?;
+    status!(
+        "   {} Verified {} (sha256 {:.12})",
+        "✓".green(),
+        name,
+        sha256
+    );
+    Ok(dir)
+}
+
+/// Download `url`, check it against `sha256` and extract it into `dest`,
+/// hoisting a lone top-level directory (`lua-5.4.6/`) into `dest` itself
+pub fn download_and_extract(url: &str, sha256: &str, dest: &Path) -> Result<()> {
+    let Some(format) = Format::from_url(url) else {
+        bail!(
+            "Unsupported archive format: {} (expected .tar.gz, .tgz, .tar.xz, .txz or .zip)",
+            url
+        );
+    };
+
+    let download = dest.with_file_name(format!(
+        "{}.download",
+        dest.file_name().unwrap_or_default().to_string_lossy()
+    ));
+    let result = (|| {
+        let response = ureq::get(url)
+            .call()
+            .with_context(|| format!("Failed to download {}", url))?;
+        let mut reader = response.into_body().into_reader();
+        let mut file = fs::File::create(&download)?;
+        std::io::copy(&mut reader, &mut file)
+            .with_context(|| format!("Failed to download {}", url))?;
+        drop(file);
+
+        verify_sha256(&download, Some(sha256))
+            .with_context(|| format!("{} does not match its sha256 in cx.toml", url))?;
+        extract(format, &download, dest)?;
+        hoist_single_dir(dest)
+    })();
+    let _ = fs::remove_file(&download);
+    result
+}
+
+fn extract(format: Format, archive: &Path, dest: &Path) -> Result<()> {
+    fs::create_dir_all(dest)?;
+    let file = BufReader::new(fs::File::open(archive)?);
+    // Both unpackers refuse entries escaping `dest` (absolute paths, `..`)
+    match format {
+        Format::TarGz => tar::Archive::new(flate2::read::GzDecoder::new(file)).unpack(dest)?,
+        Format::TarXz => tar::Archive::new(lzma_rust2::XzReader::new(file, true)).unpack(dest)?,
+        Format::Zip => zip::ZipArchive::new(file)?.extract(dest)?,
+    }
+    Ok(())
+}
+
+/// Move the contents of a lone top-level directory up into `dir`
+fn hoist_single_dir(dir: &Path) -> Result<()> {
+    let entries: Vec<PathBuf> = fs::read_dir(dir)?.flatten().map(|e| e.path()).collect();
+    let [only] = entries.as_slice() else {
+        return Ok(());
+    };
+    if !only.is_dir() {
+        return Ok(());
+    }
+
+    // Rename first, in case it holds an entry with its own name
+    let staging = dir.join(".cx-hoist");
+    fs::rename(only, &staging)?;
+    for entry in fs::read_dir(&staging)? {
+        let entry = entry?;
+        fs::rename(entry.path(), dir.join(entry.file_name()))?;
+    }
+    fs::remove_dir(&staging)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest, Sha256};
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    /// Serve `body` to a single HTTP request and return its URL
+    fn serve_once(body: Vec<u8>, file: &str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/{}", listener.local_addr().unwrap(), file);
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 4096];
+            let _ = stream.read(&mut request);
+            let header = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            );
+            stream.write_all(header.as_bytes()).unwrap();
+            stream.write_all(&body).unwrap();
+        });
+        url
+    }
+
+    fn tar_gz() -> Vec<u8> {
+        let encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        let content = b"#define LUA_VERSION 546\n";
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "lua-5.4.6/src/lua.h", &content[..])
+            .unwrap();
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    fn temp_dest(label: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("caxe_archive_{}_{}", label, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_format_from_url() {
+        assert_eq!(
+            Format::from_url("https://x/lua-5.4.6.tar.gz"),
+            Some(Format::TarGz)
+        );
+        assert_eq!(Format::from_url("https://x/a.TGZ"), Some(Format::TarGz));
+        assert_eq!(
+            Format::from_url("https://x/xz-5.4.tar.xz"),
+            Some(Format::TarXz)
+        );
+        assert_eq!(Format::from_url("https://x/a.zip?raw=1"), Some(Format::Zip));
+        assert_eq!(Format::from_url("https://x/a.tar.bz2"), None);
+    }
+
+    #[test]
+    fn test_download_verify_and_extract() {
+        let body = tar_gz();
+        let sha256 = format!("{:x}", Sha256::digest(&body));
+        let dest = temp_dest("ok");
+
+        let url = serve_once(body, "lua-5.4.6.tar.gz");
+        download_and_extract(&url, &sha256, &dest).unwrap();
+        let header = fs::read_to_string(dest.join("src").join("lua.h")).unwrap();
+        assert!(header.contains("LUA_VERSION 546"));
+
+        fs::remove_dir_all(&dest).ok();
+    }
+
+    #[test]
+    fn test_checksum_mismatch_is_an_error() {
+        let dest = temp_dest("mismatch");
+        let url = serve_once(tar_gz(), "lua-5.4.6.tar.gz");
+        let err = download_and_extract(&url, &"0".repeat(64), &dest).unwrap_err();
+        assert!(format!("{:#}", err).contains("SHA256 hash mismatch"));
+        assert!(!dest.join("src").exists());
+
+        fs::remove_dir_all(&dest).ok();
+    }
+}
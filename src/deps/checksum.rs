@@ -0,0 +1,160 @@
+//! Content checksums of cached dependency trees.
+//!
+//! When a checkout or archive is first written to the cache, its files are
+//! hashed and listed in a `<dir>.files` manifest next to it. The checksum
+//! goes into `cx.lock`, and `cx lock --check` re-hashes the listed files to
+//! catch caches that were edited or corrupted since. Files added later (e.g.
+//! by a dependency's build script) are not part of the checksum.
+
+use crate::lock::{PackageLock, SourceKind};
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+fn manifest_path(dir: &Path) -> PathBuf {
+    let name = dir.file_name().unwrap_or_default().to_string_lossy();
+    dir.with_file_name(format!("{}.files", name))
+}
+
+/// Hash `files` (relative to `dir`, sorted) into `sha256:<hex>`
+fn checksum(dir: &Path, files: &[String]) -> Result<String> {
+    let mut tree = Sha256::new();
+    for file in files {
+        let path = dir.join(file);
+        let meta = fs::symlink_metadata(&path)
+            .with_context(|| format!("Missing file {}", path.display()))?;
+        let digest = if meta.file_type().is_symlink() {
+            let target = fs::read_link(&path)?;
+            Sha256::digest(target.to_string_lossy().as_bytes())
+        } else {
+            Sha256::digest(fs::read(&path)?)
+        };
+        tree.update(format!("{:x}  {}\n", digest, file));
+    }
+    Ok(format!("sha256:{:x}", tree.finalize()))
+}
+
+/// Files under `dir`, relative and sorted, with `/` separators
+fn list_files(dir: &Path) -> Vec<String> {
+    let mut files: Vec<String> = WalkDir::new(dir)
+        .into_iter()
+        .flatten()
+        .filter(|e| !e.file_type().is_dir())
+        .filter_map(|e| {
+            let rel = e.path().strip_prefix(dir).ok()?;
+            Some(rel.to_string_lossy().replace('\\', "/"))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Checksum a freshly written tree and record its file list
+pub fn record(dir: &Path) -> Result<String> {
+    let files = list_files(dir);
+    let sum = checksum(dir, &files)?;
+    let mut manifest = format!("{}\n", sum);
+    for file in &files {
+        manifest.push_str(file);
+        manifest.push('\n');
+    }
+    fs::write(manifest_path(dir), manifest)?;
+    Ok(sum)
+}
+
+/// Checksum recorded for `dir` when it was written, if any
+pub fn recorded(dir: &Path) -> Option<String> {
+    let manifest = fs::read_to_string(manifest_path(dir)).ok()?;
+    manifest.lines().next().map(str::to_string)
+}
+
+/// Checksum of `dir`'s recorded files as they are now
+pub fn current(dir: &Path) -> Result<String> {
+    let manifest = fs::read_to_string(manifest_path(dir))
+        .with_context(|| format!("No file list recorded for {}", dir.display()))?;
+    let files: Vec<String> = manifest.lines().skip(1).map(str::to_string).collect();
+    checksum(dir, &files)
+}
+
+/// Check a locked package against what is on this machine: the cached
+/// tree's checksum for git and archive sources, the installed version for
+/// pkg-config ones. Returns the problem found, if any; packages that aren't
+/// cached yet are fine.
+pub fn check_locked(entry: &PackageLock) -> Option<String> {
+    match entry.source {
+        SourceKind::Git | SourceKind::Archive => {
+            let locked = entry.checksum.as_ref()?;
+            let dir = crate::cache::locked_dir(entry).ok()?;
+            if !dir.exists() {
+                return None;
+            }
+            match current(&dir) {
+                Ok(sum) if &sum == locked => None,
+                Ok(sum) => Some(format!(
+                    "cached tree {} does not match cx.lock (locked {}, found {})",
+                    dir.display(),
+                    locked,
+                    sum
+                )),
+                Err(e) => Some(format!(
+                    "cached tree {} is incomplete: {}",
+                    dir.display(),
+                    e
+                )),
+            }
+        }
+        SourceKind::Pkg => {
+            let (pkg, locked) = (entry.pkg.as_ref()?, entry.version.as_ref()?);
+            let out = std::process::Command::new("pkg-config")
+                .args(["--modversion", pkg])
+                .output()
+                .ok()?;
+            let found = String::from_utf8_lossy(&out.stdout).trim().to_string();
+            if !out.status.success() {
+                Some(format!(
+                    "pkg-config package '{}' is no longer installed",
+                    pkg
+                ))
+            } else if &found != locked {
+                Some(format!(
+                    "pkg-config reports {} {}, locked {}",
+                    pkg, found, locked
+                ))
+            } else {
+                None
+            }
+        }
+        SourceKind::Path => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_detect_changes() {
+        let root = std::env::temp_dir().join(format!("caxe_checksum_{}", std::process::id()));
+        let dir = root.join("abc123");
+        fs::create_dir_all(dir.join("include")).unwrap();
+        fs::write(dir.join("include").join("lib.h"), "int f();\n").unwrap();
+        fs::write(dir.join("lib.c"), "int f() { return 1; }\n").unwrap();
+
+        let sum = record(&dir).unwrap();
+        assert!(sum.starts_with("sha256:"));
+        assert_eq!(recorded(&dir).as_deref(), Some(sum.as_str()));
+        assert_eq!(current(&dir).unwrap(), sum);
+
+        // Build outputs don't count, edits do
+        fs::write(dir.join("lib.o"), "object").unwrap();
+        assert_eq!(current(&dir).unwrap(), sum);
+        fs::write(dir.join("lib.c"), "int f() { return 2; }\n").unwrap();
+        assert_ne!(current(&dir).unwrap(), sum);
+        fs::remove_file(dir.join("lib.c")).unwrap();
+        assert!(current(&dir).is_err());
+
+        fs::remove_dir_all(&root).ok();
+    }
+}
@@ -0,0 +1,56 @@
+//! Dependencies built with their own build system.
+//!
+//! `{ git = "...", cmake = { ... } }` (or `meson`, or `autotools`) hands the
+//! checkout to that build system instead of compiling its sources directly.
+//! Fetching only records what to build; the build runs once the toolchain is
+//! known, see `build::external`.
+
+use crate::config::{AutotoolsConfig, CMakeConfig, Dependency, MesonConfig};
+use std::path::PathBuf;
+
+/// Build system of an [`ExternalBuild`]
+#[derive(Debug, Clone)]
+pub enum BuildSystem {
+    CMake(CMakeConfig),
+    Meson(MesonConfig),
+    Autotools(AutotoolsConfig),
+}
+
+impl BuildSystem {
+    pub fn label(&self) -> &'static str {
+        match self {
+            BuildSystem::CMake(_) => "CMake",
+            BuildSystem::Meson(_) => "Meson",
+            BuildSystem::Autotools(_) => "Autotools",
+        }
+    }
+}
+
+/// A dependency to configure, build and install into the cache
+#[derive(Debug, Clone)]
+pub struct ExternalBuild {
+    pub name: String,
+    /// Source tree
+    pub dir: PathBuf,
+    pub system: BuildSystem,
+    /// The tree is an immutable cache entry (git checkout or archive), so an
+    /// existing install can be reused without asking the build system again
+    pub pinned: bool,
+}
+
+/// The build system `dep` asks for, if any
+pub fn build_system(dep: &Dependency) -> Option<BuildSystem> {
+    match dep {
+        Dependency::Complex {
+            cmake: Some(cmake), ..
+        } => Some(BuildSystem::CMake(cmake.clone())),
+        Dependency::Complex {
+            meson: Some(meson), ..
+        } => Some(BuildSystem::Meson(meson.clone())),
+        Dependency::Complex {
+            autotools: Some(autotools),
+            ..
+        } => Some(BuildSystem::Autotools(autotools.clone())),
+        _ => None,
+    }
+}
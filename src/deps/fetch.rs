@@ -1,22 +1,39 @@
 //! Dependency fetching and build logic.
 //!
-//! This module handles downloading, building, and caching dependencies from Git.
+//! This module handles downloading, building, and caching dependencies from Git
+//! and local paths.
 //!
 //! ## Features
 //!
-//! - Git clone with tag/branch/rev pinning
+//! - Git clone with tag/branch/rev pinning, or a semver `version` matched
+//!   against tags
+//! - Release archives (`url` + mandatory `sha256`), see [`super::archive`]
 //! - Custom build commands per dependency
-//! - SHA256 hash verification for prebuilt binaries
-//! - Global cache at `~/.cx/cache`
-
-use crate::config::Dependency;
-use anyhow::{Context, Result};
+//! - Source dependencies compiled into cached static libraries, see
+//!   [`super::library`]
+//! - SHA256 hash verification for archives and prebuilt binaries
+//! - Global cache at `~/.cx/cache`: one bare repository per URL and one
+//!   checkout per revision, so projects on different tags don't collide
+//! - Transitive dependencies from dependencies' own `cx.toml`, with the
+//!   `features` requested of them, see [`crate::features`]
+//! - Local `path` dependencies, built with `cx build` when they are caxe libraries
+//! - Cross-compilation: pkg-config and build scripts follow `--target`
+
+use super::external::{self, ExternalBuild};
+use super::library::{self, SourceLib};
+use super::{checksum, version};
+use crate::build::diagnostics;
+use crate::config::{CxConfig, Dependency};
+use crate::features::{self, Enabled};
+use crate::status;
+use crate::toolchain::cross::CrossTarget;
+use anyhow::{Context, Result, bail};
 use colored::*;
 
 use git2::Repository;
 use indicatif::{ProgressBar, ProgressStyle};
 use sha2::{Digest, Sha256};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::fs;
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
@@ -25,7 +42,6 @@ use std::process::Command;
 /// Verify a file's SHA256 hash against an expected value.
 /// Returns Ok(true) if hash matches, Ok(false) if no expected hash,
 /// or Err if file can't be read or hash doesn't match.
-#[allow(dead_code)]
 pub fn verify_sha256(path: &Path, expected_hash: Option<&str>) -> Result<bool> {
     let expected = match expected_hash {
         Some(h) => h,
@@ -157,8 +173,7 @@ fn try_download_prebuilt(
     output_file: &str,
 ) -> Result<bool> {
     // Only works on Windows for now
-    #[cfg(not(windows))]
-    {
+    if cfg!(not(windows)) {
         return Ok(false);
     }
 
@@ -196,7 +211,7 @@ fn try_download_prebuilt(
         return Ok(true);
     }
 
-    println!("   {} Checking for prebuilt {}...", "⚡".cyan(), name);
+    status!("   {} Checking for prebuilt {}...", "⚡".cyan(), name);
 
     // Try to download
     let agent = ureq::agent();
@@ -212,7 +227,7 @@ fn try_download_prebuilt(
         return Ok(false);
     }
 
-    println!(
+    status!(
         "   {} Downloading prebuilt {} (faster!)...",
         "📦".blue(),
         name
@@ -306,7 +321,7 @@ fn try_download_prebuilt(
     // Cleanup
     let _ = fs::remove_file(&temp_zip);
 
-    println!("   {} Prebuilt {} ready!", "✓".green(), name);
+    status!("   {} Prebuilt {} ready!", "✓".green(), name);
 
     Ok(true)
 }
@@ -326,55 +341,716 @@ fn parse_github_url(url: &str) -> Option<(String, String)> {
     None
 }
 
+/// Everything a build needs from the resolved dependency graph
+#[derive(Debug, Default)]
+pub struct ResolvedDeps {
+    /// Pure paths for -I or /I
+    pub include_paths: Vec<PathBuf>,
+    /// pkg-config `--cflags`
+    pub cflags: Vec<String>,
+    /// Library files and pkg-config `--libs`, passed to the linker as is
+    pub link_flags: Vec<String>,
+    /// Dependencies' `libs`, and `[build] libs` of those that are caxe
+    /// projects, linked like the project's own `libs`
+    pub libs: Vec<String>,
+    /// Dependencies to compile into static libraries, in link order
+    pub sources: Vec<SourceLib>,
+    /// Dependencies to build with their own build system, in link order
+    pub external: Vec<ExternalBuild>,
+    /// Dependencies' `defines`, as `NAME` or `NAME=VALUE`
+    pub defines: Vec<String>,
+    /// Dependencies' `link_dirs`
+    pub link_dirs: Vec<PathBuf>,
+}
+
+/// A dependency waiting to be resolved: name, spec, the package asking for
+/// it, and the directory its `path` is relative to
+type Request = (String, Dependency, String, PathBuf);
+
+/// A dependency already resolved, kept to de-duplicate later requests
+struct Resolved {
+    dep: Dependency,
+    /// [`dependency_source`] of `dep`
+    source: String,
+    /// Package that asked for it first (`cx.toml` for the project itself)
+    required_by: String,
+    /// Checkout and resolved commit, for git dependencies
+    checkout: Option<(PathBuf, String)>,
+}
+
+/// Git URL, pkg-config name, absolute path or archive, normalized so `.../fmt.git`
+/// and `.../fmt/` (or `../common` seen from two places) compare equal
+fn dependency_source(dep: &Dependency, base: &Path) -> String {
+    match dep {
+        Dependency::Simple(url) | Dependency::Complex { git: Some(url), .. } => {
+            crate::cache::normalize_git_url(url)
+        }
+        Dependency::Complex { pkg: Some(pkg), .. } => format!("pkg-config {}", pkg),
+        Dependency::Complex {
+            path: Some(path), ..
+        } => {
+            let dir = base.join(path);
+            let dir = fs::canonicalize(&dir).unwrap_or(dir);
+            format!("path {}", dir.display())
+        }
+        Dependency::Complex {
+            url: Some(url),
+            sha256,
+            ..
+        } => format!(
+            "archive {} (sha256 {})",
+            url,
+            sha256.as_deref().unwrap_or("missing").to_lowercase()
+        ),
+        _ => String::new(),
+    }
+}
+
+/// Explicit `rev`, `tag`, `branch` or `version` pin, in checkout priority order
+fn dependency_pin(dep: &Dependency) -> Option<String> {
+    match dep {
+        Dependency::Complex { rev: Some(r), .. } => Some(format!("rev {}", r)),
+        Dependency::Complex { tag: Some(t), .. } => Some(format!("tag {}", t)),
+        Dependency::Complex {
+            branch: Some(b), ..
+        } => Some(format!("branch {}", b)),
+        Dependency::Complex {
+            version: Some(v), ..
+        } => Some(format!("version {}", v)),
+        _ => None,
+    }
+}
+
+/// Commit a `rev`/`tag`/`branch` pin points at, with a description for logs
+fn find_pinned_commit<'r>(
+    repo: &'r Repository,
+    dep: &Dependency,
+) -> Option<(git2::Object<'r>, String)> {
+    let Dependency::Complex {
+        rev, tag, branch, ..
+    } = dep
+    else {
+        return None;
+    };
+
+    if let Some(r) = rev {
+        // 1. Explicit Config Commit (Highest Priority), full or abbreviated
+        let obj = repo.revparse_single(r).ok()?.peel_to_commit().ok()?;
+        Some((obj.into_object(), format!("commit {:.7}", r)))
+    } else if let Some(t) = tag {
+        // 2. Explicit Tag
+        let r_ref = repo.find_reference(&format!("refs/tags/{}", t)).ok()?;
+        let obj = r_ref.peel_to_commit().ok()?;
+        Some((obj.into_object(), format!("tag {}", t)))
+    } else if let Some(b) = branch {
+        // 3. Explicit Branch
+        let r_ref = repo
+            .find_branch(b, git2::BranchType::Local)
+            .or_else(|_| repo.find_branch(&format!("origin/{}", b), git2::BranchType::Remote))
+            .ok()?;
+        let obj = r_ref.get().peel_to_commit().ok()?;
+        Some((obj.into_object(), format!("branch {}", b)))
+    } else {
+        None
+    }
+}
+
+/// Whether the checked-out `commit` satisfies `dep`'s pin: the same commit,
+/// or for a `version` requirement a matching tag on it
+fn pin_accepts(repo: &Repository, dep: &Dependency, commit: &str) -> bool {
+    if let Dependency::Complex {
+        version: Some(req),
+        rev: None,
+        tag: None,
+        branch: None,
+        ..
+    } = dep
+    {
+        let (Ok(oid), Ok(req)) = (git2::Oid::from_str(commit), semver::VersionReq::parse(req))
+        else {
+            return false;
+        };
+        return version::matching_tag(repo, oid, &req).is_some();
+    }
+    find_pinned_commit(repo, dep).is_some_and(|(obj, _)| obj.id().to_string() == commit)
+}
+
+/// Check a repeated request for `name` against the version already resolved.
+///
+/// Unpinned requests accept whatever was chosen. A different pin is only a
+/// conflict if the commit checked out doesn't satisfy it.
+fn check_conflict(
+    name: &str,
+    resolved: &Resolved,
+    dep: &Dependency,
+    base: &Path,
+    by: &str,
+) -> Result<()> {
+    let wanted = dependency_source(dep, base);
+    if resolved.source != wanted {
+        bail!(
+            "Dependency conflict: '{}' comes from {} (required by {}) but {} requires it from {}",
+            name,
+            resolved.source,
+            resolved.required_by,
+            by,
+            wanted
+        );
+    }
+
+    let Some(pin) = dependency_pin(dep) else {
+        return Ok(());
+    };
+    if dependency_pin(&resolved.dep).as_ref() == Some(&pin) {
+        return Ok(());
+    }
+
+    let accepted = resolved.checkout.as_ref().is_some_and(|(path, commit)| {
+        Repository::open(path).is_ok_and(|repo| pin_accepts(&repo, dep, commit))
+    });
+    if accepted {
+        return Ok(());
+    }
+
+    let chosen = match (dependency_pin(&resolved.dep), &resolved.checkout) {
+        (Some(p), _) => p,
+        (None, Some((_, commit))) => format!("commit {:.7}", commit),
+        (None, None) => "the default branch".to_string(),
+    };
+    bail!(
+        "Dependency conflict: '{}' is required at {} by {} and at {} by {}.\n\n\
+        💡 Tip: Pin '{}' in your cx.toml to a version both packages accept.",
+        name,
+        chosen,
+        resolved.required_by,
+        pin,
+        by,
+        name
+    );
+}
+
+/// `include_dirs` declared on `dep`
+fn declared_include_dirs(dep: &Dependency) -> Option<&Vec<String>> {
+    match dep {
+        Dependency::Complex { include_dirs, .. } => include_dirs.as_ref(),
+        Dependency::Simple(_) => None,
+    }
+}
+
+/// Whether `dep` declares its own `sources` or `include_dirs`
+fn is_declared(dep: &Dependency) -> bool {
+    matches!(
+        dep,
+        Dependency::Complex {
+            sources: Some(_),
+            ..
+        } | Dependency::Complex {
+            include_dirs: Some(_),
+            ..
+        }
+    )
+}
+
+/// Add the `defines`, `libs` and `link_dirs` declared on `dep`, checked out
+/// (or, for system packages, declared) in `dir`
+fn add_usage(dep: &Dependency, dir: &Path, result: &mut ResolvedDeps) {
+    let Dependency::Complex {
+        defines,
+        libs,
+        link_dirs,
+        ..
+    } = dep
+    else {
+        return;
+    };
+    for define in defines.iter().flatten() {
+        if !result.defines.contains(define) {
+            result.defines.push(define.clone());
+        }
+    }
+    for lib in libs.iter().flatten() {
+        if !result.libs.contains(lib) {
+            result.libs.push(lib.clone());
+        }
+    }
+    for link_dir in link_dirs.iter().flatten() {
+        let link_dir = dir.join(link_dir);
+        if !result.link_dirs.contains(&link_dir) {
+            result.link_dirs.push(link_dir);
+        }
+    }
+}
+
+/// Parse a dependency's own `cx.toml`, if it is a caxe project
+pub fn read_manifest(dir: &Path) -> Option<CxConfig> {
+    let content = fs::read_to_string(dir.join("cx.toml")).ok()?;
+    match toml::from_str(&content) {
+        Ok(config) => Some(config),
+        Err(e) => {
+            status!(
+                "{} Warning: ignoring invalid {}: {}",
+                "!".yellow(),
+                dir.join("cx.toml").display(),
+                e.message()
+            );
+            None
+        }
+    }
+}
+
+/// Resolve the features `dep` asks of the caxe project dependency `name`,
+/// adding their defines
+fn apply_features(
+    name: &str,
+    manifest: &mut CxConfig,
+    dep: &Dependency,
+    result: &mut ResolvedDeps,
+) -> Result<Enabled> {
+    let enabled = features::apply_dependency(manifest, dep)
+        .with_context(|| format!("Failed to resolve the features of dependency '{}'", name))?;
+    for define in &enabled.defines {
+        if !result.defines.contains(define) {
+            result.defines.push(define.clone());
+        }
+    }
+    Ok(enabled)
+}
+
+/// Take a caxe project dependency's `[build] libs` and queue its own
+/// `[dependencies]`, resolved relative to `dir`
+fn add_manifest(
+    name: &str,
+    dir: &Path,
+    manifest: CxConfig,
+    result: &mut ResolvedDeps,
+    queue: &mut VecDeque<Request>,
+) -> Vec<String> {
+    if let Some(libs) = manifest.build.and_then(|b| b.libs) {
+        for lib in libs {
+            if !result.libs.contains(&lib) {
+                result.libs.push(lib);
+            }
+        }
+    }
+    let mut sub_deps: Vec<(String, Dependency)> = manifest
+        .dependencies
+        .unwrap_or_default()
+        .into_iter()
+        .collect();
+    sub_deps.sort_by(|a, b| a.0.cmp(&b.0));
+    let names = sub_deps.iter().map(|(n, _)| n.clone()).collect();
+    for (sub_name, sub_dep) in sub_deps {
+        queue.push_back((sub_name, sub_dep, name.to_string(), dir.to_path_buf()));
+    }
+    names
+}
+
+/// `cx build` arguments selecting the parent's build mode: its named profile
+/// when the dependency defines one with that name, else `--release`
+fn profile_args<'a>(manifest: &CxConfig, release: bool, profile: Option<&'a str>) -> Vec<&'a str> {
+    match profile {
+        Some(name) if manifest.profiles.contains_key(name) => vec!["--profile", name],
+        _ if release => vec!["--release"],
+        _ => Vec::new(),
+    }
+}
+
+/// Build a path dependency that is a caxe library with `cx build` in its own
+/// directory, so its incremental build applies, and return the built libraries.
+///
+/// The libraries live under the dependency's profile directory, so switching
+/// between debug, release and named profiles changes the parent's link line
+/// and relinks it.
+fn build_path_dependency(
+    name: &str,
+    dir: &Path,
+    manifest: &CxConfig,
+    dep: &Dependency,
+    build: &DepBuild,
+) -> Result<Vec<String>> {
+    let kind = manifest
+        .build
+        .as_ref()
+        .map(|b| b.artifact_kind())
+        .unwrap_or_default();
+    if !kind.is_library() {
+        status!(
+            "{} Warning: path dependency '{}' is not a library; using its headers only",
+            "!".yellow(),
+            name
+        );
+        status!(
+            "   💡 Tip: Set kind = \"staticlib\" under [build] in {}",
+            dir.join("cx.toml").display()
+        );
+        return Ok(Vec::new());
+    }
+
+    status!("   {} Building {}...", "🔨".yellow(), name);
+    let exe = std::env::current_exe().context("Could not locate the cx executable")?;
+    let mut cmd = Command::new(exe);
+    cmd.args(["build", "--message-format=json"])
+        .args(crate::fetch_mode::child_args())
+        .args(features::child_args(dep))
+        .args(profile_args(manifest, build.release, build.profile))
+        .current_dir(dir);
+    if let Some(t) = build.target {
+        cmd.args(t.child_args());
+    }
+    if let Some(jobs) = build.jobs {
+        cmd.arg(format!("--jobs={}", jobs));
+    }
+    let output = cmd
+        .output()
+        .with_context(|| format!("Failed to build path dependency '{}'", name))?;
+
+    let mut artifacts = Vec::new();
+    let mut messages = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let Ok(record) = serde_json::from_str::<serde_json::Value>(line) else {
+            continue;
+        };
+        match record["reason"].as_str() {
+            Some("compiler-artifact") if record["kind"] != "bin" => {
+                if let Some(path) = record["path"].as_str() {
+                    artifacts.push(dir.join(path).to_string_lossy().to_string());
+                }
+            }
+            Some("compiler-message") => {
+                if let Some(rendered) = record["rendered"].as_str() {
+                    messages.push(rendered.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if !output.status.success() {
+        for message in &messages {
+            status!("      {}", message);
+        }
+        eprint!("{}", String::from_utf8_lossy(&output.stderr));
+        bail!(
+            "Failed to build path dependency '{}' ({})",
+            name,
+            dir.display()
+        );
+    }
+    Ok(artifacts)
+}
+
+/// Source of a fetched dependency: `vendor/<name>`, else the checkout of its
+/// locked revision in the global cache
+pub fn dependency_dir(name: &str) -> Option<PathBuf> {
+    let vendor_path = std::env::current_dir().ok()?.join("vendor").join(name);
+    if vendor_path.exists() {
+        return Some(vendor_path);
+    }
+    let lockfile = crate::lock::LockFile::load().ok()?;
+    let dir = crate::cache::locked_dir(lockfile.get(name)?).ok()?;
+    dir.exists().then_some(dir)
+}
+
+/// Hidden sibling of `path` to build it in before moving it into place, so
+/// an interrupted or concurrent fetch never leaves a half-written directory
+pub(super) fn temp_sibling(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(".{}.tmp-{}", name, std::process::id()))
+}
+
+/// Move a finished `tmp` directory to `dir`; losing the race to another
+/// process that created `dir` first is fine
+pub(super) fn move_into_place(tmp: &Path, dir: &Path) -> Result<()> {
+    if let Err(e) = fs::rename(tmp, dir) {
+        let _ = fs::remove_dir_all(tmp);
+        if !dir.exists() {
+            return Err(e).with_context(|| format!("Failed to create {}", dir.display()));
+        }
+    }
+    Ok(())
+}
+
+/// Open the shared bare repository for `url`, cloning it on first use
+fn open_db(name: &str, url: &str) -> Result<(PathBuf, Repository)> {
+    let db_path = crate::cache::git_db_dir(url)?;
+    if db_path.exists() {
+        status!("   {} Using cached: {}", "⚡".green(), name);
+        let repo = Repository::open_bare(&db_path)?;
+        return Ok((db_path, repo));
+    }
+
+    crate::fetch_mode::ensure_online(&format!("download '{}' ({})", name, url))?;
+    let pb = ProgressBar::new_spinner();
+    pb.set_style(
+        ProgressStyle::default_spinner()
+            .template("{spinner:.blue} {msg}")
+            .unwrap_or_else(|_| ProgressStyle::default_spinner())
+            .tick_chars("⣾⣽⣻⢿⡿⣟⣯⣷"),
+    );
+    pb.set_message(format!("Downloading {}...", name));
+    pb.enable_steady_tick(std::time::Duration::from_millis(100));
+
+    let tmp = temp_sibling(&db_path);
+    let _ = fs::remove_dir_all(&tmp);
+    if let Some(parent) = db_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let cloned = git2::build::RepoBuilder::new()
+        .bare(true)
+        .clone(url, &tmp)
+        .map_err(anyhow::Error::from)
+        .and_then(|repo| fetch_remote(&repo));
+    if let Err(e) = cloned {
+        pb.finish_with_message(format!("{} Failed {}", "x".red(), name));
+        let _ = fs::remove_dir_all(&tmp);
+        return Err(e);
+    }
+    move_into_place(&tmp, &db_path)?;
+    pb.finish_with_message(format!("{} Downloaded {}", "✓".green(), name));
+
+    let repo = Repository::open_bare(&db_path)?;
+    Ok((db_path, repo))
+}
+
+/// Fetch every branch and tag from `origin` into a cached bare repository
+pub(super) fn fetch_remote(repo: &Repository) -> Result<()> {
+    let mut remote = repo.find_remote("origin")?;
+    remote
+        .fetch(
+            &["+refs/heads/*:refs/heads/*", "+refs/tags/*:refs/tags/*"],
+            None,
+            None,
+        )
+        .context("Failed to fetch from origin")?;
+    Ok(())
+}
+
+/// Write `commit`'s files to `dir`
+fn checkout_commit(repo: &Repository, commit: &git2::Commit, dir: &Path) -> Result<()> {
+    let tmp = temp_sibling(dir);
+    let _ = fs::remove_dir_all(&tmp);
+    fs::create_dir_all(&tmp)?;
+
+    let mut checkout_opts = git2::build::CheckoutBuilder::new();
+    checkout_opts.force().update_index(false).target_dir(&tmp);
+    repo.checkout_tree(commit.as_object(), Some(&mut checkout_opts))
+        .with_context(|| format!("Failed to checkout {}", commit.id()))?;
+    move_into_place(&tmp, dir)
+}
+
+/// Checksum of a cached tree, recorded on first use (before any build
+/// script runs in it)
+fn tree_checksum(dir: &Path) -> Option<String> {
+    checksum::recorded(dir).or_else(|| checksum::record(dir).ok())
+}
+
+/// Commit chosen for a git dependency
+pub(super) struct Selected<'r> {
+    pub commit: git2::Commit<'r>,
+    /// How it was chosen, e.g. `tag v1.0` (`None` for the default branch)
+    pub reason: Option<String>,
+    /// Tag version picked for a `version` requirement
+    pub version: Option<String>,
+    /// Tag the commit was found through
+    pub tag: Option<String>,
+}
+
+/// Pick the commit to build: the `rev`/`tag`/`branch` pin, the best tag for a
+/// `version` requirement, the locked commit, or the default branch.
+///
+/// Fails if a pin (or the locked commit) isn't in the repository, so callers
+/// can fetch and retry.
+pub(super) fn select_commit<'r>(
+    repo: &'r Repository,
+    name: &str,
+    dep: &Dependency,
+    locked: Option<git2::Oid>,
+) -> Result<Selected<'r>> {
+    if let Some((obj, reason)) = find_pinned_commit(repo, dep) {
+        let tag = match dep {
+            Dependency::Complex {
+                rev: None,
+                tag: Some(tag),
+                ..
+            } => Some(tag.clone()),
+            _ => None,
+        };
+        return Ok(Selected {
+            commit: obj.peel_to_commit()?,
+            reason: Some(reason),
+            version: None,
+            tag,
+        });
+    }
+
+    if let Dependency::Complex {
+        version: Some(req),
+        rev: None,
+        tag: None,
+        branch: None,
+        ..
+    } = dep
+    {
+        // 4. Version Requirement: stay on the locked tag while it still
+        // matches, otherwise take the highest matching tag
+        let req = version::parse_req(name, req)?;
+        let locked = locked.and_then(|oid| {
+            let (found, tag) = version::matching_tag(repo, oid, &req)?;
+            Some((found, tag, oid))
+        });
+        let (found, tag, oid) = match locked {
+            Some(hit) => hit,
+            None => version::resolve(repo, name, &req)?,
+        };
+        return Ok(Selected {
+            commit: repo.find_commit(oid)?,
+            reason: Some(format!("version {}", found)),
+            version: Some(found.to_string()),
+            tag: Some(tag),
+        });
+    }
+
+    if let Some(pin) = dependency_pin(dep) {
+        bail!("Could not find {} in '{}'", pin, name);
+    }
+
+    if let Some(oid) = locked {
+        // 5. Lockfile Commit (Zero Config Reproducibility)
+        let commit = repo.find_commit(oid).with_context(|| {
+            format!(
+                "Locked commit {} of '{}' not found; run 'cx update' to re-lock",
+                oid, name
+            )
+        })?;
+        let rev = oid.to_string();
+        return Ok(Selected {
+            commit,
+            reason: Some(format!("locked {}", &rev[..7])),
+            version: None,
+            tag: None,
+        });
+    }
+
+    // 6. Default Branch
+    Ok(Selected {
+        commit: repo.head()?.peel_to_commit()?,
+        reason: None,
+        version: None,
+        tag: None,
+    })
+}
+
+/// The project build that dependencies are fetched for
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DepBuild<'a> {
+    /// Cross target, when a cross toolchain is used
+    pub target: Option<&'a CrossTarget>,
+    pub release: bool,
+    /// Named profile
+    pub profile: Option<&'a str>,
+    /// Parallel job limit (`None` = one per core)
+    pub jobs: Option<usize>,
+}
+
+/// Fetch dependencies for the host, or for `build.target` when cross-compiling.
+///
+/// Dependencies that are caxe projects bring their own `[dependencies]`,
+/// which are resolved too. Each library is fetched once; the resolved graph
+/// is recorded in `cx.lock`. Path dependencies are built with the project's
+/// profile, target and job limit.
 pub fn fetch_dependencies(
     deps: &HashMap<String, Dependency>,
-) -> Result<(Vec<PathBuf>, Vec<String>, Vec<String>)> {
-    let home_dir = dirs::home_dir().context("Could not find home directory")?;
-    let cache_dir = home_dir.join(".cx").join("cache");
-    fs::create_dir_all(&cache_dir)?;
-
-    let mut lockfile = crate::lock::LockFile::load().unwrap_or_default();
+    build: &DepBuild,
+) -> Result<ResolvedDeps> {
+    let target = build.target;
+    let mut lockfile = crate::lock::LockFile::load()?;
+    let original = lockfile.clone();
+    let pkg_config = || match target {
+        Some(t) => t.pkg_config(),
+        None => Command::new("pkg-config"),
+    };
 
-    let mut include_paths = Vec::new(); // Pure paths for -I or /I
-    let mut extra_cflags = Vec::new(); // pkg-config flags
-    let mut link_flags = Vec::new();
+    let mut result = ResolvedDeps::default();
 
     if !deps.is_empty() {
-        println!("{} Checking {} dependencies...", "📦".blue(), deps.len());
+        status!("{} Checking {} dependencies...", "📦".blue(), deps.len());
     }
 
-    for (name, dep_data) in deps {
+    // Breadth-first, so the project's own requirements are seen first
+    let mut names: Vec<&String> = deps.keys().collect();
+    names.sort();
+    let project_dir = std::env::current_dir()?;
+    let mut queue: VecDeque<Request> = names
+        .into_iter()
+        .map(|name| {
+            let dep = deps[name].clone();
+            (
+                name.clone(),
+                dep,
+                "cx.toml".to_string(),
+                project_dir.clone(),
+            )
+        })
+        .collect();
+    let mut resolved: HashMap<String, Resolved> = HashMap::new();
+
+    while let Some((name, dep_data, required_by, base)) = queue.pop_front() {
+        if let Some(existing) = resolved.get(&name) {
+            check_conflict(&name, existing, &dep_data, &base, &required_by)?;
+            let missing = features::missing(&existing.dep, &dep_data);
+            if !missing.is_empty() {
+                status!(
+                    "{} Warning: {} needs feature(s) {} of '{}', which is already resolved without them (for {})",
+                    "!".yellow(),
+                    required_by,
+                    missing.join(", "),
+                    name,
+                    existing.required_by
+                );
+                status!("   💡 Tip: Add them to '{}' in your cx.toml", name);
+            }
+            continue;
+        }
+        resolved.insert(
+            name.clone(),
+            Resolved {
+                source: dependency_source(&dep_data, &base),
+                dep: dep_data.clone(),
+                required_by: required_by.clone(),
+                checkout: None,
+            },
+        );
+        let name = &name;
+        let dep_data = &dep_data;
+        if required_by != "cx.toml" {
+            status!("   {} {} (required by {})", "↳".dimmed(), name, required_by);
+        }
+
         // --- CASE 1: System Package (pkg-config) ---
         if let Dependency::Complex {
             pkg: Some(pkg_name),
             ..
         } = dep_data
         {
-            println!("   {} Resolving system pkg: {}", "🔎".cyan(), pkg_name);
+            status!("   {} Resolving system pkg: {}", "🔎".cyan(), pkg_name);
 
             // 1. Get CFLAGS (Include paths)
-            match Command::new("pkg-config")
-                .args(["--cflags", pkg_name])
-                .output()
-            {
+            match pkg_config().args(["--cflags", pkg_name]).output() {
                 Ok(out) => {
                     let out_str = String::from_utf8_lossy(&out.stdout).trim().to_string();
                     if !out_str.is_empty() {
                         for flag in out_str.split_whitespace() {
-                            extra_cflags.push(flag.to_string());
+                            result.cflags.push(flag.to_string());
                         }
                     }
                 }
-                Err(_) => println!("{} Warning: pkg-config tool not found", "!".yellow()),
+                Err(_) => status!("{} Warning: pkg-config tool not found", "!".yellow()),
             }
 
             // 2. Get LIBS (Link paths)
-            if let Ok(out) = Command::new("pkg-config")
-                .args(["--libs", pkg_name])
-                .output()
-            {
+            if let Ok(out) = pkg_config().args(["--libs", pkg_name]).output() {
                 if !out.status.success() {
-                    println!(
+                    status!(
                         "{} Package '{}' not found via pkg-config",
                         "x".red(),
                         pkg_name
@@ -383,218 +1059,298 @@ pub fn fetch_dependencies(
                 let out_str = String::from_utf8_lossy(&out.stdout).trim().to_string();
                 if !out_str.is_empty() {
                     for flag in out_str.split_whitespace() {
-                        link_flags.push(flag.to_string());
+                        result.link_flags.push(flag.to_string());
                     }
                 }
             }
+
+            // 3. Lock the installed version, so drift shows up in `cx lock --check`
+            let modversion = pkg_config()
+                .args(["--modversion", pkg_name])
+                .output()
+                .ok()
+                .filter(|out| out.status.success())
+                .map(|out| String::from_utf8_lossy(&out.stdout).trim().to_string());
+            lockfile.insert_pkg(name.clone(), pkg_name.clone(), modversion);
+            add_usage(dep_data, &base, &mut result);
             continue;
         }
 
-        // --- CASE 2: Git Dependency ---
-        let (url, build_script, output_file, tag, branch, rev) = match dep_data {
-            Dependency::Simple(u) => (u.clone(), None, None, None, None, None),
+        // --- CASE 2: Local Path Dependency ---
+        if let Dependency::Complex {
+            path: Some(rel_path),
+            ..
+        } = dep_data
+        {
+            let dir = base.join(rel_path);
+            if !dir.is_dir() {
+                bail!(
+                    "Path dependency '{}' not found: {} (required by {})",
+                    name,
+                    dir.display(),
+                    required_by
+                );
+            }
+            status!("   {} Using path: {} ({})", "📂".blue(), name, rel_path);
+
+            lockfile.insert_path(name.clone(), rel_path.clone());
+            add_usage(dep_data, &dir, &mut result);
+            if let Some(system) = external::build_system(dep_data) {
+                result.external.push(ExternalBuild {
+                    name: name.clone(),
+                    dir,
+                    system,
+                    pinned: false,
+                });
+            } else if let Some(mut manifest) = read_manifest(&dir) {
+                apply_features(name, &mut manifest, dep_data, &mut result)?;
+                match declared_include_dirs(dep_data) {
+                    Some(list) => result
+                        .include_paths
+                        .extend(list.iter().map(|d| dir.join(d))),
+                    None => {
+                        result.include_paths.push(dir.clone());
+                        result.include_paths.push(dir.join("include"));
+                        result.include_paths.push(dir.join("src"));
+                    }
+                }
+                let built = build_path_dependency(name, &dir, &manifest, dep_data, build)?;
+                result.link_flags.extend(built);
+                let sub_deps = add_manifest(name, &dir, manifest, &mut result, &mut queue);
+                lockfile.set_dependencies(name, sub_deps);
+            } else if is_declared(dep_data) {
+                let layout = library::layout(name, &dir, dep_data, None, None)?;
+                result.include_paths.extend(layout.include_dirs);
+                result.sources.extend(layout.lib);
+            } else {
+                result.include_paths.push(dir.clone());
+                result.include_paths.push(dir.join("include"));
+                result.include_paths.push(dir.join("src"));
+            }
+            continue;
+        }
+
+        // --- CASE 3 & 4: Archive or Git Dependency ---
+        let (git_url, build_script, output_file, tag) = match dep_data {
+            Dependency::Simple(u) => (Some(u.clone()), None, None, None),
             Dependency::Complex {
-                git: Some(u),
+                git,
                 build,
                 output,
                 tag,
-                branch,
-                rev,
                 ..
-            } => (
-                u.clone(),
-                build.clone(),
-                output.clone(),
-                tag.clone(),
-                branch.clone(),
-                rev.clone(),
-            ),
-            _ => continue,
-        };
-
-        // Check for local vendor override
-        let vendor_path = std::env::current_dir()?.join("vendor").join(name);
-
-        let (lib_path, is_vendor) = if vendor_path.exists() {
-            (vendor_path, true)
-        } else {
-            (cache_dir.join(name), false)
+            } => (git.clone(), build.clone(), output.clone(), tag.clone()),
         };
-
-        // A. Download (Clone) or Open Existing
-        let repo = if !lib_path.exists() {
-            // Cannot download if we expected vendor but it's missing (should have fallen back to cache)
-            // Logic: If vendor exists, use it. If not, use cache.
-            // If cache missing, download to cache.
-
-            let pb = ProgressBar::new_spinner();
-            pb.set_style(
-                ProgressStyle::default_spinner()
-                    .template("{spinner:.blue} {msg}")
-                    .unwrap_or_else(|_| ProgressStyle::default_spinner())
-                    .tick_chars("⣾⣽⣻⢿⡿⣟⣯⣷"),
-            );
-            pb.set_message(format!("Downloading {}...", name));
-            pb.enable_steady_tick(std::time::Duration::from_millis(100));
-
-            match Repository::clone(&url, &lib_path) {
-                Ok(r) => {
-                    pb.finish_with_message(format!("{} Downloaded {}", "✓".green(), name));
-                    r
+        let has_script = build_script.is_some() || output_file.is_some();
+        // Build scripts write into the tree, so cross builds get their own copy
+        let variant = target
+            .filter(|_| build_script.is_some())
+            .map(|t| t.triple.as_str());
+
+        // Local vendor override, used as is
+        let vendor_path = project_dir.join("vendor").join(name);
+        let vendored = vendor_path.exists();
+        let lib_path = if vendored {
+            status!("   {} Using vendor: {}", "📦".blue(), name);
+            vendor_path
+        } else if let Dependency::Complex {
+            url: Some(archive_url),
+            sha256,
+            ..
+        } = dep_data
+        {
+            // CASE 3: Release Archive (checksum required)
+            let Some(sha256) = sha256 else {
+                bail!(
+                    "Archive dependency '{}' has no sha256 checksum.\n\n\
+                    💡 Tip: Add sha256 = \"<hash>\" next to its url in cx.toml \
+                    (e.g. from 'sha256sum {}').",
+                    name,
+                    archive_url.rsplit('/').next().unwrap_or(archive_url)
+                );
+            };
+            let dir = super::archive::fetch_archive(name, archive_url, sha256, variant)?;
+            lockfile.insert_archive(name.clone(), archive_url.clone(), sha256.to_lowercase());
+            if let Some(sum) = tree_checksum(&dir) {
+                lockfile.set_checksum(name, sum);
+            }
+            dir
+        } else if let Some(url) = &git_url {
+            // CASE 4: Git Dependency
+            // A. Shared bare repository per URL, cloned on first use
+            let (db_path, db) = open_db(name, url)
+                .with_context(|| format!("Failed to fetch dependency '{}' ({})", name, url))?;
+
+            // B. Pinning (v0.1.5 + v0.1.8 Lockfile)
+            let locked = lockfile
+                .get(name)
+                .filter(|lock_entry| &lock_entry.git == url)
+                .and_then(|lock_entry| git2::Oid::from_str(&lock_entry.rev).ok());
+            let selected = match select_commit(&db, name, dep_data, locked) {
+                Ok(selected) => selected,
+                Err(e) if crate::fetch_mode::is_offline() => {
+                    return Err(e.context(format!(
+                        "'{}' is not in the cached repository and --offline forbids fetching it",
+                        name
+                    )));
                 }
-                Err(e) => {
-                    pb.finish_with_message(format!("{} Failed {}", "x".red(), name));
-                    println!("Error: {}", e);
-                    continue;
+                // Pinned to something newer than the cached repository
+                Err(_) => {
+                    fetch_remote(&db)?;
+                    select_commit(&db, name, dep_data, locked)?
                 }
+            };
+            if let Some(reason) = &selected.reason {
+                status!("   {} Locked to {}", "📌".blue(), reason);
             }
-        } else {
-            if is_vendor {
-                println!("   {} Using vendor: {}", "📦".blue(), name);
-            } else {
-                println!("   {} Using cached: {}", "⚡".green(), name);
-            }
-            match Repository::open(&lib_path) {
-                Ok(r) => r,
-                Err(_) => continue,
-            }
-        };
 
-        // B. Pinning / Checkout Logic (v0.1.5 + v0.1.8 Lockfile)
-        let mut obj_to_checkout = None;
-        let mut checkout_msg = String::new();
-
-        // Lockfile Check
-        let mut locked_commit = None;
-        if let Some(lock_entry) = lockfile.get(name)
-            && lock_entry.git == url
-        {
-            locked_commit = Some(lock_entry.rev.clone());
-        }
+            // C. Per-revision checkout
+            let commit = selected.commit.id().to_string();
+            let dir = crate::cache::checkout_dir(url, &commit, variant)?;
+            if !dir.exists() {
+                checkout_commit(&db, &selected.commit, &dir)?;
+            }
 
-        if let Some(r) = rev {
-            // 1. Explicit Config Commit (Highest Priority)
-            if let Ok(oid) = git2::Oid::from_str(&r)
-                && let Ok(obj) = repo.find_object(oid, None)
-            {
-                obj_to_checkout = Some(obj);
-                checkout_msg = format!("commit {}", &r[..7]);
+            lockfile.insert(name.clone(), url.clone(), commit.clone());
+            if let Some(found) = selected.version {
+                lockfile.set_version(name, found);
             }
-        } else if let Some(ref t) = tag {
-            // 2. Explicit Tag
-            let refname = format!("refs/tags/{}", t);
-            if let Ok(r_ref) = repo.find_reference(&refname)
-                && let Ok(obj) = r_ref.peel_to_commit()
-            {
-                obj_to_checkout = Some(obj.into_object());
-                checkout_msg = format!("tag {}", t);
+            if let Some(tag) = selected.tag {
+                lockfile.set_tag(name, tag);
             }
-        } else if let Some(b) = branch {
-            // 3. Explicit Branch
-            if let Ok(r_ref) = repo.find_branch(&b, git2::BranchType::Local) {
-                if let Ok(obj) = r_ref.get().peel_to_commit() {
-                    obj_to_checkout = Some(obj.into_object());
-                    checkout_msg = format!("branch {}", b);
-                }
-            } else {
-                let remote_ref = format!("origin/{}", b);
-                if let Ok(r_ref) = repo.find_branch(&remote_ref, git2::BranchType::Remote)
-                    && let Ok(obj) = r_ref.get().peel_to_commit()
-                {
-                    obj_to_checkout = Some(obj.into_object());
-                    checkout_msg = format!("branch {}", b);
-                }
+            if let Some(sum) = tree_checksum(&dir) {
+                lockfile.set_checksum(name, sum);
             }
-        } else if let Some(rev) = locked_commit {
-            // 4. Lockfile Commit (Zero Config Reproducibility)
-            if let Ok(oid) = git2::Oid::from_str(&rev)
-                && let Ok(obj) = repo.find_object(oid, None)
-            {
-                obj_to_checkout = Some(obj);
-                checkout_msg = format!("locked {}", &rev[..7]);
+            if let Some(entry) = resolved.get_mut(name) {
+                entry.checkout = Some((db_path, commit));
             }
-        }
+            dir
+        } else {
+            continue;
+        };
 
-        if let Some(obj) = obj_to_checkout {
-            repo.set_head_detached(obj.id())?;
-            let mut checkout_opts = git2::build::CheckoutBuilder::new();
-            checkout_opts.force();
-            repo.checkout_tree(&obj, Some(&mut checkout_opts))
-                .context(format!("Failed to checkout {}", checkout_msg))?;
-            println!("   {} Locked to {}", "📌".blue(), checkout_msg);
-        }
+        add_usage(dep_data, &lib_path, &mut result);
 
-        // Update Lockfile with current HEAD
-        if let Ok(head) = repo.head()
-            && let Ok(target) = head.peel_to_commit()
-        {
-            let current_hash = target.id().to_string();
-            lockfile.insert(name.clone(), url.clone(), current_hash);
-        }
+        // Prebuilt downloads and build scripts write into the shared checkout
+        let _lock = if has_script && !vendored {
+            Some(crate::cache::lock_entry(&lib_path)?)
+        } else {
+            None
+        };
 
-        // C. Try Prebuilt Binary (Skip slow source build!)
+        // D. Try Prebuilt Binary (Skip slow source build!)
         let tag_ref = tag.as_deref();
         let out_filename = output_file.as_deref().unwrap_or("");
 
-        // Try prebuilt first (for known libraries like GLFW, SDL2)
-        let prebuilt_success = if !out_filename.is_empty() {
-            try_download_prebuilt(name, &url, tag_ref, &lib_path, out_filename).unwrap_or(false)
-        } else {
-            false
+        // Try prebuilt first (for known libraries like GLFW, SDL2); those are
+        // host binaries, so never when cross-compiling
+        let prebuilt_success = match &git_url {
+            Some(url)
+                if !out_filename.is_empty()
+                    && target.is_none()
+                    && !crate::fetch_mode::is_offline() =>
+            {
+                try_download_prebuilt(name, url, tag_ref, &lib_path, out_filename).unwrap_or(false)
+            }
+            _ => false,
         };
 
-        // D. Build Custom Script (If prebuilt failed and script exists)
+        // E. Build Custom Script (If prebuilt failed and script exists)
         if !prebuilt_success && let Some(cmd_str) = build_script {
-            let should_build = if !out_filename.is_empty() {
-                !lib_path.join(out_filename).exists()
-            } else {
-                true
+            // Skip once the files the script generates exist
+            let should_build = match dep_data {
+                _ if !out_filename.is_empty() => !lib_path.join(out_filename).exists(),
+                Dependency::Complex {
+                    sources: Some(sources),
+                    ..
+                } => sources.iter().any(|s| !lib_path.join(s).exists()),
+                _ => true,
             };
 
             if should_build {
-                println!("   {} Building {}...", "🔨".yellow(), name);
-                let status = if cfg!(target_os = "windows") {
-                    Command::new("cmd")
-                        .args(["/C", &cmd_str])
-                        .current_dir(&lib_path)
-                        .status()
+                status!("   {} Building {}...", "🔨".yellow(), name);
+                let mut cmd = if cfg!(target_os = "windows") {
+                    let mut cmd = Command::new("cmd");
+                    cmd.args(["/C", &cmd_str]);
+                    cmd
                 } else {
-                    Command::new("sh")
-                        .args(["-c", &cmd_str])
-                        .current_dir(&lib_path)
-                        .status()
+                    let mut cmd = Command::new("sh");
+                    cmd.args(["-c", &cmd_str]);
+                    cmd
                 };
-
-                match status {
-                    Ok(s) if s.success() => {}
-                    _ => {
-                        println!("{} Build script failed for {}", "x".red(), name);
-                        continue;
-                    }
+                if let Some(t) = target {
+                    cmd.envs(t.build_env());
+                }
+                let status = cmd
+                    .current_dir(&lib_path)
+                    .stdout(diagnostics::child_stdout())
+                    .status()
+                    .with_context(|| format!("Failed to run the build script of '{}'", name))?;
+                if !status.success() {
+                    bail!(
+                        "Build script of '{}' failed ({}): {}",
+                        name,
+                        status,
+                        cmd_str
+                    );
                 }
             }
         }
 
-        // D. Register Includes Flags (Return Paths)
-        include_paths.push(lib_path.clone());
-        include_paths.push(lib_path.join("include"));
-        include_paths.push(lib_path.join("src"));
-        // CMake-built dependencies often generate headers in the build directory
-        include_paths.push(lib_path.join("build").join("include"));
-        include_paths.push(lib_path.join("build").join("include").join("SDL2"));
-        // GLAD 2.0 outputs to dist/ directory
-        include_paths.push(lib_path.join("dist"));
-        include_paths.push(lib_path.join("dist").join("include"));
-
-        // E. Smart Linking Logic (Zero Config Header-Only Support)
+        // F. Public headers, and sources to compile into a library
+        let mut manifest = read_manifest(&lib_path);
+        let enabled = match manifest.as_mut() {
+            Some(m) => Some(apply_features(name, m, dep_data, &mut result)?),
+            None => None,
+        };
+        if let Some(system) = external::build_system(dep_data) {
+            // Headers and libraries come from the install tree
+            result.external.push(ExternalBuild {
+                name: name.clone(),
+                dir: lib_path.clone(),
+                system,
+                pinned: !vendored,
+            });
+        } else if has_script && !is_declared(dep_data) {
+            // Script-built: headers may be generated anywhere
+            result.include_paths.push(lib_path.clone());
+            result.include_paths.push(lib_path.join("include"));
+            result.include_paths.push(lib_path.join("src"));
+            // CMake-built dependencies often generate headers in the build directory
+            result
+                .include_paths
+                .push(lib_path.join("build").join("include"));
+            result
+                .include_paths
+                .push(lib_path.join("build").join("include").join("SDL2"));
+            // GLAD 2.0 outputs to dist/ directory
+            result.include_paths.push(lib_path.join("dist"));
+            result
+                .include_paths
+                .push(lib_path.join("dist").join("include"));
+        } else {
+            let layout = library::layout(
+                name,
+                &lib_path,
+                dep_data,
+                manifest.as_ref(),
+                enabled.as_ref(),
+            )?;
+            result.include_paths.extend(layout.include_dirs);
+            result.sources.extend(layout.lib);
+        }
+
+        // G. Smart Linking Logic (Zero Config Header-Only Support)
         if let Some(out_file) = output_file {
             // Support comma-separated output files
             for single_output in out_file.split(',').map(|s| s.trim()) {
                 let full_lib_path = lib_path.join(single_output);
                 if full_lib_path.exists() {
-                    link_flags.push(full_lib_path.to_string_lossy().to_string());
+                    result
+                        .link_flags
+                        .push(full_lib_path.to_string_lossy().to_string());
                 } else {
-                    println!(
+                    status!(
                         "{} Warning: Output file not found: {}",
                         "!".yellow(),
                         full_lib_path.display()
@@ -602,8 +1358,149 @@ pub fn fetch_dependencies(
                 }
             }
         }
+
+        // H. Transitive Dependencies (the dependency is a caxe project)
+        if let Some(manifest) = manifest {
+            let sub_deps = add_manifest(name, &lib_path, manifest, &mut result, &mut queue);
+            lockfile.set_dependencies(name, sub_deps);
+        }
+    }
+
+    // Dependencies removed from the graph leave the lockfile
+    lockfile.retain(|name| resolved.contains_key(name));
+
+    if crate::fetch_mode::is_locked() {
+        let changes = lockfile.changes_from(&original);
+        if !changes.is_empty() {
+            let reason = if Path::new("cx.lock").exists() {
+                "cx.lock needs to be updated"
+            } else {
+                "cx.lock is missing"
+            };
+            bail!(
+                "{} but --locked was passed:\n  - {}\n\n\
+                💡 Tip: Run the build without --locked/--frozen and commit cx.lock.",
+                reason,
+                changes.join("\n  - ")
+            );
+        }
+    } else {
+        lockfile.save()?;
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git_dep(url: &str, tag: Option<&str>) -> Dependency {
+        let mut dep = Dependency::git(url);
+        if let Dependency::Complex { tag: t, .. } = &mut dep {
+            *t = tag.map(str::to_string);
+        }
+        dep
     }
 
-    lockfile.save()?;
-    Ok((include_paths, extra_cflags, link_flags))
+    #[test]
+    fn test_check_conflict() {
+        let base = Path::new(".");
+        let dep = git_dep("https://github.com/fmtlib/fmt", Some("10.1.1"));
+        let resolved = Resolved {
+            source: dependency_source(&dep, base),
+            dep,
+            required_by: "cx.toml".to_string(),
+            checkout: None,
+        };
+
+        // Same pin, or no pin at all, reuses the resolved version
+        let same = git_dep("https://github.com/fmtlib/fmt.git", Some("10.1.1"));
+        assert!(check_conflict("fmt", &resolved, &same, base, "spdlog").is_ok());
+        let unpinned = Dependency::Simple("https://github.com/fmtlib/fmt/".to_string());
+        assert!(check_conflict("fmt", &resolved, &unpinned, base, "spdlog").is_ok());
+
+        let older = git_dep("https://github.com/fmtlib/fmt", Some("9.1.0"));
+        let err = check_conflict("fmt", &resolved, &older, base, "spdlog").unwrap_err();
+        assert!(err.to_string().contains("tag 10.1.1 by cx.toml"));
+        assert!(err.to_string().contains("tag 9.1.0 by spdlog"));
+
+        let fork = git_dep("https://github.com/someone/fmt", Some("10.1.1"));
+        assert!(check_conflict("fmt", &resolved, &fork, base, "spdlog").is_err());
+    }
+
+    #[test]
+    fn test_path_dependency_source() {
+        let dir = std::env::temp_dir().join(format!("cx_path_dep_{}", std::process::id()));
+        fs::create_dir_all(dir.join("libs").join("common")).unwrap();
+        fs::create_dir_all(dir.join("libs").join("util")).unwrap();
+        let path_dep = |path: &str| Dependency::path(path);
+
+        // The same directory seen from the project and from a sibling library
+        let from_root = dependency_source(&path_dep("libs/common"), &dir);
+        let from_sibling =
+            dependency_source(&path_dep("../common"), &dir.join("libs").join("util"));
+        assert!(from_root.starts_with("path "));
+        assert_eq!(from_root, from_sibling);
+        assert_ne!(from_root, dependency_source(&path_dep("libs/other"), &dir));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_profile_args() {
+        let mut manifest = CxConfig::default();
+        manifest
+            .profiles
+            .insert("esp32".to_string(), Default::default());
+
+        assert!(profile_args(&manifest, false, None).is_empty());
+        assert_eq!(profile_args(&manifest, true, None), vec!["--release"]);
+        assert_eq!(
+            profile_args(&manifest, false, Some("esp32")),
+            vec!["--profile", "esp32"]
+        );
+        // A profile the dependency doesn't define falls back to its build mode
+        assert_eq!(
+            profile_args(&manifest, true, Some("fast")),
+            vec!["--release"]
+        );
+    }
+
+    #[test]
+    fn test_add_usage() {
+        let list = |l: &[&str]| Some(l.iter().map(|s| s.to_string()).collect());
+        let mut spdlog = git_dep("https://github.com/gabime/spdlog", None);
+        if let Dependency::Complex {
+            defines,
+            libs,
+            link_dirs,
+            ..
+        } = &mut spdlog
+        {
+            *defines = list(&["SPDLOG_FMT_EXTERNAL", "SPDLOG_LEVEL=2"]);
+            *libs = list(&["pthread"]);
+            *link_dirs = list(&["lib", "/opt/spdlog/lib"]);
+        }
+        let mut result = ResolvedDeps::default();
+        add_usage(&spdlog, Path::new("/cache/spdlog"), &mut result);
+        add_usage(&spdlog, Path::new("/cache/spdlog"), &mut result);
+        add_usage(
+            &git_dep("https://github.com/fmtlib/fmt", None),
+            Path::new("/cache/fmt"),
+            &mut result,
+        );
+
+        assert_eq!(
+            result.defines,
+            vec!["SPDLOG_FMT_EXTERNAL", "SPDLOG_LEVEL=2"]
+        );
+        assert_eq!(result.libs, vec!["pthread"]);
+        assert_eq!(
+            result.link_dirs,
+            vec![
+                PathBuf::from("/cache/spdlog/lib"),
+                PathBuf::from("/opt/spdlog/lib")
+            ]
+        );
+    }
 }
@@ -0,0 +1,235 @@
+//! Dependencies compiled from source into static libraries.
+//!
+//! What to compile and which headers are public comes from, in order:
+//!
+//! 1. `sources` / `include_dirs` on the dependency in `cx.toml`
+//! 2. the dependency's own `cx.toml`, when it is a caxe library
+//!    (`[build] sources`, or everything under `src/`, and `[build] include_dirs`),
+//!    plus the `sources` of its enabled features and without those of the
+//!    disabled ones
+//! 3. nothing to compile (header-only), with `include/` as the public headers,
+//!    or the root (and `src/`) when there is none
+//!
+//! The sources are compiled by the build once the toolchain is known, see
+//! `build::dep_libs`.
+
+use crate::config::{CxConfig, Dependency};
+use crate::features::Enabled;
+use anyhow::{Result, bail};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+const SOURCE_EXTENSIONS: &[&str] = &["c", "cc", "cpp", "cxx"];
+
+/// A dependency to compile into `lib<name>.a` / `<name>.lib`
+#[derive(Debug, Clone)]
+pub struct SourceLib {
+    pub name: String,
+    /// Dependency root, on the include path while compiling it
+    pub dir: PathBuf,
+    /// Absolute, sorted source files
+    pub sources: Vec<PathBuf>,
+    /// Standards from the dependency's cx.toml, else the project's
+    pub edition: Option<String>,
+    pub c_edition: Option<String>,
+}
+
+/// Public headers of a dependency, and its sources if it has any
+#[derive(Debug)]
+pub struct Layout {
+    pub include_dirs: Vec<PathBuf>,
+    pub lib: Option<SourceLib>,
+}
+
+/// Work out the layout of the dependency `name` checked out at `dir`, with
+/// the `features` enabled in its `manifest`
+pub fn layout(
+    name: &str,
+    dir: &Path,
+    dep: &Dependency,
+    manifest: Option<&CxConfig>,
+    features: Option<&Enabled>,
+) -> Result<Layout> {
+    let (declared_sources, declared_includes) = match dep {
+        Dependency::Complex {
+            sources,
+            include_dirs,
+            ..
+        } => (sources.as_ref(), include_dirs.as_ref()),
+        Dependency::Simple(_) => (None, None),
+    };
+    let build = manifest.and_then(|m| m.build.as_ref());
+    let is_library = build.is_some_and(|b| b.artifact_kind().is_library());
+
+    let sources = match (declared_sources, build) {
+        (Some(list), _) => collect_sources(name, dir, list)?,
+        (None, Some(b)) if is_library => {
+            let mut entries = b.sources.clone().unwrap_or_else(|| vec!["src".to_string()]);
+            if let Some(features) = features {
+                entries.extend(features.sources.iter().cloned());
+            }
+            let mut files = collect_sources(name, dir, &entries)?;
+            if let Some(features) = features {
+                files.retain(|f| !f.strip_prefix(dir).is_ok_and(|f| features.is_excluded(f)));
+            }
+            files
+        }
+        _ => Vec::new(),
+    };
+
+    let include_dirs = match declared_includes.or(build.and_then(|b| b.include_dirs.as_ref())) {
+        Some(list) => list.iter().map(|d| dir.join(d)).collect(),
+        None if dir.join("include").is_dir() => vec![dir.join("include")],
+        None if is_library => vec![dir.join("src")],
+        None => {
+            let mut dirs = vec![dir.to_path_buf()];
+            if dir.join("src").is_dir() {
+                dirs.push(dir.join("src"));
+            }
+            dirs
+        }
+    };
+
+    let lib = (!sources.is_empty()).then(|| SourceLib {
+        name: name.to_string(),
+        dir: dir.to_path_buf(),
+        sources,
+        edition: manifest.map(|m| m.package.edition.clone()),
+        c_edition: manifest.map(|m| m.package.c_edition()),
+    });
+    Ok(Layout { include_dirs, lib })
+}
+
+/// Expand `sources` entries (files, or directories searched recursively)
+/// relative to `dir`
+fn collect_sources(name: &str, dir: &Path, entries: &[String]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in entries {
+        let path = dir.join(entry);
+        if path.is_dir() {
+            files.extend(
+                WalkDir::new(&path)
+                    .into_iter()
+                    .flatten()
+                    .map(|e| e.into_path())
+                    .filter(|p| {
+                        p.extension()
+                            .is_some_and(|e| SOURCE_EXTENSIONS.contains(&&*e.to_string_lossy()))
+                    }),
+            );
+        } else if path.is_file() {
+            files.push(path);
+        } else {
+            bail!(
+                "Source '{}' of dependency '{}' not found in {}",
+                entry,
+                name,
+                dir.display()
+            );
+        }
+    }
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn declared(sources: Option<&[&str]>, include_dirs: Option<&[&str]>) -> Dependency {
+        let list = |l: &[&str]| l.iter().map(|s| s.to_string()).collect();
+        let mut dep = Dependency::git("https://github.com/ocornut/imgui.git");
+        if let Dependency::Complex {
+            sources: s,
+            include_dirs: i,
+            ..
+        } = &mut dep
+        {
+            *s = sources.map(list);
+            *i = include_dirs.map(list);
+        }
+        dep
+    }
+
+    #[test]
+    fn test_layout() {
+        let dir = std::env::temp_dir().join(format!("caxe_library_{}", std::process::id()));
+        fs::create_dir_all(dir.join("backends")).unwrap();
+        fs::create_dir_all(dir.join("src").join("detail")).unwrap();
+        for file in [
+            "imgui.h",
+            "imgui.cpp",
+            "imgui_draw.cpp",
+            "backends/imgui_impl_glfw.cpp",
+            "src/lib.c",
+            "src/detail/util.cpp",
+            "src/notes.txt",
+        ] {
+            fs::write(dir.join(file), "").unwrap();
+        }
+
+        // Header-only by default, rooted at the checkout (and src/)
+        let simple = Dependency::Simple("https://github.com/nothings/stb.git".to_string());
+        let layout_of = |dep: &Dependency, manifest: Option<&CxConfig>| {
+            layout("imgui", &dir, dep, manifest, None).unwrap()
+        };
+        let plain = layout_of(&simple, None);
+        assert!(plain.lib.is_none());
+        assert_eq!(plain.include_dirs, vec![dir.clone(), dir.join("src")]);
+
+        // Declared files and directories
+        let dep = declared(Some(&["imgui.cpp", "imgui_draw.cpp", "src"]), Some(&["."]));
+        let lib = layout_of(&dep, None).lib.unwrap();
+        assert_eq!(
+            lib.sources,
+            vec![
+                dir.join("imgui.cpp"),
+                dir.join("imgui_draw.cpp"),
+                dir.join("src").join("detail").join("util.cpp"),
+                dir.join("src").join("lib.c"),
+            ]
+        );
+        assert!(
+            layout(
+                "imgui",
+                &dir,
+                &declared(Some(&["missing.cpp"]), None),
+                None,
+                None
+            )
+            .is_err()
+        );
+
+        // A caxe library compiles src/ and exports include/ (else src/)
+        let manifest: CxConfig =
+            toml::from_str("[package]\nname = \"util\"\nversion = \"1.0.0\"\nedition = \"c++20\"\n[build]\nkind = \"staticlib\"\n")
+                .unwrap();
+        let caxe = layout_of(&simple, Some(&manifest));
+        assert_eq!(caxe.include_dirs, vec![dir.join("src")]);
+        let lib = caxe.lib.unwrap();
+        assert_eq!(lib.sources.len(), 2);
+        assert_eq!(lib.edition.as_deref(), Some("c++20"));
+
+        // Sources of disabled features are left out, enabled ones added
+        let features = Enabled {
+            sources: vec!["backends".to_string()],
+            excluded: vec!["src/detail".to_string()],
+            ..Default::default()
+        };
+        let lib = layout("imgui", &dir, &simple, Some(&manifest), Some(&features))
+            .unwrap()
+            .lib
+            .unwrap();
+        assert_eq!(
+            lib.sources,
+            vec![
+                dir.join("backends").join("imgui_impl_glfw.cpp"),
+                dir.join("src").join("lib.c"),
+            ]
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -6,16 +6,17 @@
 //!
 //! - `cx add <lib>` - Add a dependency
 //! - `cx remove <lib>` - Remove a dependency
-//! - `cx update` - Update all dependencies to latest
+//! - `cx update` - Update all dependencies to latest (within `version` ranges)
 
+use super::fetch;
 use crate::config::Dependency;
-use anyhow::{Context, Result};
+use crate::lock::LockFile;
+use crate::manifest::Manifest;
+use anyhow::{Result, bail};
 use colored::*;
 
-use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
-use std::process::Command;
 
 // Needed imports for add/remove/update logic
 
@@ -31,9 +32,10 @@ pub fn add_dependency(
     }
 
     // 1. Parse Input (Alias -> Short format -> URL)
-    let (name, url) = if let Some(resolved_url) = crate::registry::resolve_alias(lib_input) {
+    let entry = crate::registry::Registry::get_entry(lib_input);
+    let (name, url) = if let Some(entry) = &entry {
         // Case A: Alias found (e.g. "raylib")
-        (lib_input.to_string(), resolved_url)
+        (lib_input.to_string(), entry.url.clone())
     } else if lib_input.contains("http") || lib_input.contains("git@") {
         // Case B: Direct URL
         let name = lib_input
@@ -59,45 +61,53 @@ pub fn add_dependency(
 
     println!("{} Adding dependency: {}...", "📦".blue(), name.bold());
 
-    // 2. Load Config
-    let config_str = fs::read_to_string("cx.toml")?;
-    let mut config: crate::config::CxConfig = toml::from_str(&config_str)?;
-
-    if config.dependencies.is_none() {
-        config.dependencies = Some(HashMap::new());
-    }
-
-    // 3. Construct Dependency Entry
-    let dep_entry = if tag.is_none() && branch.is_none() && rev.is_none() {
+    // 2. Open cx.toml for editing (keeps comments and unknown sections)
+    let mut manifest = Manifest::open()?;
+
+    // 3. Construct Dependency Entry (with the registry's library layout)
+    let (sources, include_dirs) = entry
+        .map(|e| (e.sources, e.include_dirs))
+        .unwrap_or_default();
+    let dep_entry = if tag.is_none()
+        && branch.is_none()
+        && rev.is_none()
+        && sources.is_none()
+        && include_dirs.is_none()
+    {
         Dependency::Simple(url.clone())
     } else {
-        Dependency::Complex {
-            git: Some(url.clone()),
-            pkg: None,
-            branch,
-            tag,
-            rev,
-            build: None,
-            output: None,
+        let mut dep = Dependency::git(url.clone());
+        if let Dependency::Complex {
+            branch: b,
+            tag: t,
+            rev: r,
+            sources: s,
+            include_dirs: i,
+            ..
+        } = &mut dep
+        {
+            *b = branch;
+            *t = tag;
+            *r = rev;
+            *s = sources;
+            *i = include_dirs;
         }
+        dep
     };
 
     // 4. Insert & Save
-    if let Some(deps) = &mut config.dependencies {
-        if deps.contains_key(&name) {
-            println!("! Dependency '{}' updated.", name);
-        }
-        deps.insert(name.clone(), dep_entry);
+    if manifest.has_dependency(&name) {
+        println!("! Dependency '{}' updated.", name);
     }
-
-    let new_toml = toml::to_string_pretty(&config)?;
-    fs::write("cx.toml", new_toml)?;
+    manifest.set_dependency(&name, &dep_entry)?;
+    manifest.save()?;
 
     println!("{} Added {} to cx.toml", "✓".green(), name);
 
     // 5. Fetch immediately
+    let config = crate::build::load_config()?;
     if let Some(deps) = &config.dependencies {
-        let _ = super::fetch::fetch_dependencies(deps)?;
+        let _ = super::fetch::fetch_dependencies(deps, &Default::default())?;
     }
 
     Ok(())
@@ -109,19 +119,10 @@ pub fn remove_dependency(name: &str) -> Result<()> {
         return Ok(());
     }
 
-    let config_str = fs::read_to_string("cx.toml")?;
-    let mut config: crate::config::CxConfig = toml::from_str(&config_str)?;
-
-    let mut found = false;
-    if let Some(deps) = &mut config.dependencies
-        && deps.remove(name).is_some()
-    {
-        found = true;
-    }
+    let mut manifest = Manifest::open()?;
 
-    if found {
-        let new_toml = toml::to_string_pretty(&config)?;
-        fs::write("cx.toml", new_toml)?;
+    if manifest.remove_dependency(name) {
+        manifest.save()?;
         println!("{} Removed dependency: {}", "🗑️".red(), name.bold());
     } else {
         println!(
@@ -140,66 +141,95 @@ pub fn update_dependencies() -> Result<()> {
         return Ok(());
     }
 
+    if crate::fetch_mode::is_locked() {
+        bail!("Cannot update dependencies with --locked/--frozen: that would change cx.lock");
+    }
+    crate::fetch_mode::ensure_online("update dependencies")?;
+
     println!("{} Checking for updates...", "📦".blue());
 
     let config_str = fs::read_to_string("cx.toml")?;
     let config: crate::config::CxConfig = toml::from_str(&config_str)?;
 
-    let home_dir = dirs::home_dir().context("Could not find home directory")?;
-    let cache_dir = home_dir.join(".cx").join("cache");
+    let mut lockfile = LockFile::load()?;
+    let mut relocked = false;
 
     if let Some(deps) = config.dependencies {
         for (name, dep_data) in deps {
-            let is_git = matches!(
-                dep_data,
-                crate::config::Dependency::Simple(_)
-                    | crate::config::Dependency::Complex { git: Some(_), .. }
-            );
+            let url = match &dep_data {
+                Dependency::Simple(url) | Dependency::Complex { git: Some(url), .. } => url,
+                _ => continue,
+            };
+            let db_path = crate::cache::git_db_dir(url)?;
+            if !db_path.exists() {
+                continue;
+            }
+            print!("   Updating {} ... ", name);
 
-            if is_git {
-                let lib_path = cache_dir.join(&name);
-                if lib_path.exists() {
-                    print!("   Updating {} ... ", name);
-
-                    if let Ok(repo) = git2::Repository::open(&lib_path) {
-                        // Fetch origin
-                        let mut remote = repo.find_remote("origin")?;
-                        remote.fetch(&["HEAD"], None, None)?;
-
-                        // Force checking out correct HEAD
-                        // Note: For 'update', we typically want to pull latest.
-                        // Use fetch + reset --hard to ensure we match upstream exactly, discarding local changes (it's a cache)
-                        let command = "git fetch origin && git reset --hard origin/HEAD";
-                        let status = if cfg!(target_os = "windows") {
-                            Command::new("cmd")
-                                .args(["/C", command])
-                                .current_dir(&lib_path)
-                                .output()
-                        } else {
-                            Command::new("sh")
-                                .args(["-c", command])
-                                .current_dir(&lib_path)
-                                .output()
-                        };
-
-                        if let Ok(out) = status {
-                            if out.status.success() {
-                                println!("{}", "✓".green());
-                            } else {
-                                let err = String::from_utf8_lossy(&out.stderr);
-                                println!("{} (git update failed: {})", "x".red(), err.trim());
-                            }
-                        } else {
-                            println!("{}", "Error executing git".red());
-                        }
-                    } else {
-                        println!("{}", "Not a valid git repo".yellow());
-                    }
+            match update_lock(&db_path, &name, url, &dep_data, &mut lockfile) {
+                Ok(Some(change)) => {
+                    relocked = true;
+                    println!("{} {}", "✓".green(), change);
                 }
+                Ok(None) => println!("{}", "✓".green()),
+                Err(e) => println!("{} ({})", "x".red(), e),
             }
         }
     }
 
+    if relocked {
+        lockfile.save()?;
+    }
     println!("{} Dependencies updated.", "✓".green());
     Ok(())
 }
+
+/// Fetch the cached repository and re-lock `name` to its newest allowed
+/// commit (the highest matching tag for `version` ranges), returning e.g.
+/// `10.2.1 -> 10.3.0` if the lock moved
+fn update_lock(
+    db_path: &Path,
+    name: &str,
+    url: &str,
+    dep: &Dependency,
+    lockfile: &mut LockFile,
+) -> Result<Option<String>> {
+    let repo = git2::Repository::open_bare(db_path)?;
+    fetch::fetch_remote(&repo)?;
+    let selected = fetch::select_commit(&repo, name, dep, None)?;
+    let rev = selected.commit.id().to_string();
+
+    // Update in place to keep the recorded dependency edges
+    let (old_rev, old_version) = match lockfile.packages.get_mut(name) {
+        Some(entry) if entry.git == url => {
+            entry.tag = selected.tag.clone();
+            if entry.rev != rev {
+                // The next build records the new checkout's checksum
+                entry.checksum = None;
+            }
+            (
+                std::mem::replace(&mut entry.rev, rev.clone()),
+                std::mem::replace(&mut entry.version, selected.version.clone()),
+            )
+        }
+        _ => {
+            lockfile.insert(name.to_string(), url.to_string(), rev.clone());
+            if let Some(found) = &selected.version {
+                lockfile.set_version(name, found.clone());
+            }
+            if let Some(tag) = &selected.tag {
+                lockfile.set_tag(name, tag.clone());
+            }
+            return Ok(Some(
+                selected.version.unwrap_or_else(|| rev[..7].to_string()),
+            ));
+        }
+    };
+    if old_rev == rev {
+        return Ok(None);
+    }
+    Ok(Some(match (old_version, selected.version) {
+        (Some(old), Some(new)) => format!("{} -> {}", old, new),
+        _ => format!("{:.7} -> {:.7}", old_rev, rev),
+    }))
+}
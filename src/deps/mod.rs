@@ -2,7 +2,9 @@
 //!
 //! This module handles all dependency-related operations including:
 //!
-//! - **Fetching**: Download dependencies from Git repositories
+//! - **Fetching**: Download dependencies from Git repositories and release archives
+//! - **Libraries**: Work out which sources and headers of a dependency to use
+//! - **External builds**: Dependencies built with CMake, Meson or Autotools
 //! - **Management**: Add, remove, and update dependencies in `cx.toml`
 //! - **Vendoring**: Copy dependencies locally for offline builds
 //!
@@ -13,10 +15,18 @@
 //! - `cx update` - Update all dependencies to latest versions
 //! - `cx vendor` - Copy dependencies into `vendor/` directory
 
+mod archive;
+mod checksum;
+mod external;
 mod fetch;
+mod library;
 mod manage;
 mod vendor;
+mod version;
 
-pub use fetch::fetch_dependencies;
+pub use checksum::check_locked;
+pub use external::{BuildSystem, ExternalBuild};
+pub use fetch::{DepBuild, ResolvedDeps, dependency_dir, fetch_dependencies, read_manifest};
+pub use library::SourceLib;
 pub use manage::{add_dependency, remove_dependency, update_dependencies};
 pub use vendor::vendor_dependencies;
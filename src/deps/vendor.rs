@@ -6,12 +6,13 @@
 //! ## Usage
 //!
 //! ```bash
-//! cx vendor  # Copies ~/.cx/cache/* to ./vendor/
+//! cx vendor  # Copies the locked checkouts from ~/.cx/cache to ./vendor/
 //! ```
 
 use crate::build::load_config;
 use crate::config::Dependency;
-use anyhow::{Context, Result};
+use crate::lock::LockFile;
+use anyhow::Result;
 use colored::*;
 use std::fs;
 use std::path::Path;
@@ -38,9 +39,8 @@ pub fn vendor_dependencies() -> Result<()> {
         fs::create_dir(vendor_dir)?;
     }
 
-    // 3. Resolve Cache Path
-    let home_dir = dirs::home_dir().context("Could not find home directory")?;
-    let cache_dir = home_dir.join(".cx").join("cache");
+    // 3. Locked revisions pick the cached checkouts
+    let lockfile = LockFile::load()?;
 
     println!(
         "{} Vendoring {} dependencies to ./vendor...",
@@ -49,17 +49,29 @@ pub fn vendor_dependencies() -> Result<()> {
     );
 
     for (name, dep) in deps {
-        // Skip pkg-config deps
-        if let Dependency::Complex { pkg: Some(_), .. } = dep {
+        // Skip pkg-config and local path deps
+        if let Dependency::Complex { pkg: Some(_), .. }
+        | Dependency::Complex { path: Some(_), .. } = dep
+        {
             continue;
         }
 
-        let source_path = cache_dir.join(&name);
         let dest_path = vendor_dir.join(&name);
+        let source_path = match lockfile.get(&name) {
+            Some(entry) => crate::cache::locked_dir(entry)?,
+            None => {
+                println!(
+                    "{} {} is not in cx.lock. Run 'cx build' first.",
+                    "x".red(),
+                    name
+                );
+                continue;
+            }
+        };
 
         if !source_path.exists() {
             println!(
-                "{} Source not found in cache: {}. Run 'cx update' first.",
+                "{} Source not found in cache: {}. Run 'cx build' first.",
                 "x".red(),
                 name
             );
@@ -0,0 +1,120 @@
+//! Semver requirements for git dependencies.
+//!
+//! `version = "^10.2"` is resolved against the repository's tags: each tag
+//! is read as a version (`v1.12.0`, `release-2.30.0` and `10.2` all work) and
+//! the highest one matching the requirement is checked out.
+
+use anyhow::{Context, Result, bail};
+use git2::{Oid, Repository};
+use semver::{Version, VersionReq};
+
+/// Parse a `version` requirement from cx.toml
+pub fn parse_req(name: &str, req: &str) -> Result<VersionReq> {
+    VersionReq::parse(req)
+        .with_context(|| format!("Invalid version requirement '{}' for '{}'", req, name))
+}
+
+/// Read a tag as a version, skipping any prefix before the first digit and
+/// padding `10.2` to `10.2.0`
+pub fn parse_tag(tag: &str) -> Option<Version> {
+    let start = tag.find(|c: char| c.is_ascii_digit())?;
+    let raw = &tag[start..];
+    if let Ok(version) = Version::parse(raw) {
+        return Some(version);
+    }
+
+    let mut parts: Vec<&str> = raw.split('.').collect();
+    let numeric = |p: &&str| !p.is_empty() && p.bytes().all(|b| b.is_ascii_digit());
+    if parts.len() < 3 && parts.iter().all(numeric) {
+        parts.resize(3, "0");
+        return Version::parse(&parts.join(".")).ok();
+    }
+    None
+}
+
+/// Tags that read as versions, with the commit each points at
+pub fn tagged_versions(repo: &Repository) -> Vec<(Version, String, Oid)> {
+    let Ok(names) = repo.tag_names(None) else {
+        return Vec::new();
+    };
+    names
+        .iter()
+        .flatten()
+        .filter_map(|tag| {
+            let version = parse_tag(tag)?;
+            let reference = repo.find_reference(&format!("refs/tags/{}", tag)).ok()?;
+            let commit = reference.peel_to_commit().ok()?;
+            Some((version, tag.to_string(), commit.id()))
+        })
+        .collect()
+}
+
+/// Highest tagged version matching `req`
+pub fn resolve(repo: &Repository, name: &str, req: &VersionReq) -> Result<(Version, String, Oid)> {
+    let mut tags = tagged_versions(repo);
+    tags.sort_by(|a, b| a.0.cmp(&b.0));
+    if let Some(best) = tags
+        .iter()
+        .rev()
+        .find(|(version, _, _)| req.matches(version))
+    {
+        return Ok(best.clone());
+    }
+
+    let available: Vec<String> = tags
+        .iter()
+        .rev()
+        .take(5)
+        .map(|(version, _, _)| version.to_string())
+        .collect();
+    bail!(
+        "No tag of '{}' matches version {} (latest: {})",
+        name,
+        req,
+        if available.is_empty() {
+            "no version tags".to_string()
+        } else {
+            available.join(", ")
+        }
+    );
+}
+
+/// Highest version (and its tag) of a tag on `commit` that satisfies `req`
+pub fn matching_tag(repo: &Repository, commit: Oid, req: &VersionReq) -> Option<(Version, String)> {
+    tagged_versions(repo)
+        .into_iter()
+        .filter(|(version, _, oid)| *oid == commit && req.matches(version))
+        .map(|(version, tag, _)| (version, tag))
+        .max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tag() {
+        assert_eq!(parse_tag("v1.12.0"), Some(Version::new(1, 12, 0)));
+        assert_eq!(parse_tag("10.2.1"), Some(Version::new(10, 2, 1)));
+        assert_eq!(parse_tag("release-2.30.0"), Some(Version::new(2, 30, 0)));
+        assert_eq!(parse_tag("glfw-3.4"), Some(Version::new(3, 4, 0)));
+        assert_eq!(
+            parse_tag("v3.0.0-rc1"),
+            Some(Version::parse("3.0.0-rc1").unwrap())
+        );
+        assert_eq!(parse_tag("latest"), None);
+        assert_eq!(parse_tag("v1.2.x"), None);
+    }
+
+    #[test]
+    fn test_requirement_matching() {
+        let req = parse_req("fmt", "^10.2").unwrap();
+        assert!(req.matches(&parse_tag("10.2.1").unwrap()));
+        assert!(req.matches(&parse_tag("10.9.0").unwrap()));
+        assert!(!req.matches(&parse_tag("11.0.0").unwrap()));
+        assert!(!req.matches(&parse_tag("10.1.1").unwrap()));
+        // Pre-releases only match when asked for
+        assert!(!req.matches(&parse_tag("10.3.0-rc1").unwrap()));
+        assert!(parse_req("fmt", "not a version").is_err());
+    }
+}
@@ -0,0 +1,372 @@
+//! Cargo-style features.
+//!
+//! ```toml
+//! [features]
+//! default = ["opengl"]
+//! opengl = ["dep:glad"]
+//! vulkan = { enables = ["dep:volk", "imgui/vulkan"], defines = ["USE_VULKAN"], sources = ["src/backends/vulkan"] }
+//!
+//! [dependencies]
+//! glad = { path = "../glad", optional = true }
+//! volk = { git = "https://github.com/zeux/volk", optional = true }
+//! imgui = { path = "../imgui", features = ["docking"] }
+//! ```
+//!
+//! An enabled feature defines `CX_FEATURE_<NAME>` and its own `defines`,
+//! compiles its `sources` (which are left out of the build otherwise) and
+//! turns on what it `enables`: other features, optional dependencies
+//! (`dep:<name>`, or just `<name>`) and features of dependencies
+//! (`<dep>/<feature>`). Optional dependencies nothing enables are not fetched.
+//!
+//! The project's features come from `--features`, `--all-features` and its
+//! `default` feature (unless `--no-default-features`); a dependency's from
+//! `features = [...]` where it is declared, and its `default` unless
+//! `default_features = false`.
+
+use crate::config::{CxConfig, Dependency};
+use anyhow::{Result, bail};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::OnceLock;
+
+/// Features picked on the command line
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Selection {
+    pub features: Vec<String>,
+    pub all: bool,
+    pub no_default: bool,
+}
+
+static SELECTION: OnceLock<Selection> = OnceLock::new();
+
+/// Set the selection from `--features` (comma or space separated),
+/// `--all-features` and `--no-default-features`
+pub fn init(features: Vec<String>, all: bool, no_default: bool) {
+    let features = features
+        .iter()
+        .flat_map(|f| f.split([',', ' ']))
+        .filter(|f| !f.is_empty())
+        .map(str::to_string)
+        .collect();
+    let _ = SELECTION.set(Selection {
+        features,
+        all,
+        no_default,
+    });
+}
+
+pub fn get() -> Selection {
+    SELECTION.get().cloned().unwrap_or_default()
+}
+
+/// What the enabled features of a package add to its build
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Enabled {
+    /// Enabled features, sorted
+    pub features: Vec<String>,
+    /// `CX_FEATURE_<NAME>` for each feature, plus its `defines`
+    pub defines: Vec<String>,
+    /// `sources` of enabled features
+    pub sources: Vec<String>,
+    /// `sources` of disabled features, left out of the build
+    pub excluded: Vec<String>,
+}
+
+impl Enabled {
+    /// Whether `path` (relative to the package root) belongs to a disabled feature
+    pub fn is_excluded(&self, path: &std::path::Path) -> bool {
+        let path = normalize(&path.to_string_lossy());
+        self.excluded.iter().any(|entry| {
+            let entry = normalize(entry);
+            path == entry || path.starts_with(&format!("{}/", entry))
+        })
+    }
+}
+
+fn normalize(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    path.trim_start_matches("./")
+        .trim_end_matches('/')
+        .to_string()
+}
+
+/// Apply the command line selection to the project's `config`: drop the
+/// optional dependencies it doesn't enable and pass features on to the rest
+pub fn apply_project(config: &mut CxConfig) -> Result<Enabled> {
+    let selection = get();
+    let requested = if selection.all {
+        let optional = config
+            .dependencies
+            .iter()
+            .flatten()
+            .filter(|(_, dep)| is_optional(dep))
+            .map(|(name, _)| format!("dep:{}", name));
+        config.features.keys().cloned().chain(optional).collect()
+    } else {
+        selection.features
+    };
+    resolve(config, &requested, !selection.no_default)
+}
+
+/// Apply the features `dep` asks for to the dependency's own cx.toml
+pub fn apply_dependency(manifest: &mut CxConfig, dep: &Dependency) -> Result<Enabled> {
+    let (requested, default) = requested(dep);
+    resolve(manifest, &requested, default)
+}
+
+/// Features requested on `dep`, and whether its `default` feature is on
+fn requested(dep: &Dependency) -> (Vec<String>, bool) {
+    match dep {
+        Dependency::Complex {
+            features,
+            default_features,
+            ..
+        } => (
+            features.clone().unwrap_or_default(),
+            default_features.unwrap_or(true),
+        ),
+        Dependency::Simple(_) => (Vec::new(), true),
+    }
+}
+
+/// Arguments selecting `dep`'s features for its own `cx build`
+pub fn child_args(dep: &Dependency) -> Vec<String> {
+    let (features, default) = requested(dep);
+    let mut args = Vec::new();
+    if !features.is_empty() {
+        args.push(format!("--features={}", features.join(",")));
+    }
+    if !default {
+        args.push("--no-default-features".to_string());
+    }
+    args
+}
+
+/// Features `dep` asks for that aren't enabled on `resolved`, the same
+/// dependency as first requested
+pub fn missing(resolved: &Dependency, dep: &Dependency) -> Vec<String> {
+    let (have, _) = requested(resolved);
+    let (want, _) = requested(dep);
+    want.into_iter().filter(|f| !have.contains(f)).collect()
+}
+
+fn is_optional(dep: &Dependency) -> bool {
+    matches!(
+        dep,
+        Dependency::Complex {
+            optional: Some(true),
+            ..
+        }
+    )
+}
+
+/// `CX_FEATURE_<NAME>` for the feature `name`
+pub fn define_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("CX_FEATURE_{}", name)
+}
+
+fn resolve(config: &mut CxConfig, requested: &[String], default: bool) -> Result<Enabled> {
+    let package = config.package.name.clone();
+    let has_dep = |name: &str| {
+        config
+            .dependencies
+            .as_ref()
+            .is_some_and(|deps| deps.contains_key(name))
+    };
+    let optional_dep = |name: &str| {
+        config
+            .dependencies
+            .as_ref()
+            .and_then(|deps| deps.get(name))
+            .is_some_and(is_optional)
+    };
+
+    let mut queue: Vec<String> = requested.iter().rev().cloned().collect();
+    if default && config.features.contains_key("default") {
+        queue.insert(0, "default".to_string());
+    }
+    let mut features = BTreeSet::new();
+    // Enabled dependencies, with the features to turn on in each
+    let mut deps: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    while let Some(item) = queue.pop() {
+        if let Some(name) = item.strip_prefix("dep:") {
+            if !optional_dep(name) {
+                bail!(
+                    "Feature '{}' of package '{}' enables '{}', which is not an optional dependency",
+                    item,
+                    package,
+                    name
+                );
+            }
+            deps.entry(name.to_string()).or_default();
+        } else if let Some((name, feature)) = item.split_once('/') {
+            if !has_dep(name) {
+                bail!(
+                    "Feature '{}' of package '{}' refers to unknown dependency '{}'",
+                    item,
+                    package,
+                    name
+                );
+            }
+            let enabled = deps.entry(name.to_string()).or_default();
+            if !enabled.iter().any(|f| f == feature) {
+                enabled.push(feature.to_string());
+            }
+        } else if let Some(feature) = config.features.get(&item) {
+            if features.insert(item.clone()) {
+                queue.extend(feature.enables().iter().rev().cloned());
+            }
+        } else if optional_dep(&item) {
+            deps.entry(item).or_default();
+        } else {
+            let available: Vec<&str> = config.features.keys().map(String::as_str).collect();
+            bail!(
+                "Package '{}' has no feature '{}'{}",
+                package,
+                item,
+                if available.is_empty() {
+                    String::new()
+                } else {
+                    format!(" (available: {})", available.join(", "))
+                }
+            );
+        }
+    }
+
+    if let Some(dependencies) = config.dependencies.as_mut() {
+        dependencies.retain(|name, dep| !is_optional(dep) || deps.contains_key(name));
+        for (name, extra) in deps {
+            if let Some(dep) = dependencies.get_mut(&name) {
+                add_features(dep, extra);
+            }
+        }
+    }
+
+    let mut enabled = Enabled::default();
+    for name in &features {
+        let feature = &config.features[name];
+        enabled.defines.push(define_name(name));
+        enabled.defines.extend(feature.defines().iter().cloned());
+        enabled.sources.extend(feature.sources().iter().cloned());
+    }
+    for (name, feature) in &config.features {
+        if features.contains(name) {
+            continue;
+        }
+        for source in feature.sources() {
+            if !enabled.sources.contains(source) && !enabled.excluded.contains(source) {
+                enabled.excluded.push(source.clone());
+            }
+        }
+    }
+    enabled.features = features.into_iter().collect();
+    Ok(enabled)
+}
+
+/// Turn on `extra` features of `dep`
+fn add_features(dep: &mut Dependency, extra: Vec<String>) {
+    if extra.is_empty() {
+        return;
+    }
+    if let Dependency::Simple(url) = dep {
+        *dep = Dependency::git(url.clone());
+    }
+    if let Dependency::Complex { features, .. } = dep {
+        let features = features.get_or_insert_with(Vec::new);
+        for feature in extra {
+            if !features.contains(&feature) {
+                features.push(feature);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+[package]
+name = "engine"
+version = "0.1.0"
+
+[features]
+default = ["opengl"]
+opengl = ["dep:glad"]
+vulkan = { enables = ["dep:volk", "imgui/vulkan"], defines = ["USE_VULKAN=1"], sources = ["src/backends/vulkan"] }
+tools = { sources = ["src/tools.cpp"] }
+
+[dependencies]
+glad = { path = "../glad", optional = true }
+volk = { git = "https://github.com/zeux/volk", optional = true }
+imgui = "https://github.com/ocornut/imgui"
+"#;
+
+    fn deps(config: &CxConfig) -> Vec<&str> {
+        let mut names: Vec<&str> = config
+            .dependencies
+            .iter()
+            .flatten()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_resolve() {
+        let manifest: CxConfig = toml::from_str(MANIFEST).unwrap();
+
+        // `default` only
+        let mut config = manifest.clone();
+        let enabled = resolve(&mut config, &[], true).unwrap();
+        assert_eq!(enabled.features, vec!["default", "opengl"]);
+        assert_eq!(deps(&config), vec!["glad", "imgui"]);
+        assert_eq!(
+            enabled.excluded,
+            vec!["src/tools.cpp", "src/backends/vulkan"]
+        );
+        assert!(enabled.is_excluded(std::path::Path::new("src/backends/vulkan/device.cpp")));
+        assert!(!enabled.is_excluded(std::path::Path::new("src/main.cpp")));
+
+        // Optional dependencies and features of dependencies
+        let mut config = manifest.clone();
+        let enabled = resolve(&mut config, &["vulkan".to_string()], false).unwrap();
+        assert_eq!(enabled.features, vec!["vulkan"]);
+        assert_eq!(enabled.defines, vec!["CX_FEATURE_VULKAN", "USE_VULKAN=1"]);
+        assert_eq!(enabled.sources, vec!["src/backends/vulkan"]);
+        assert_eq!(deps(&config), vec!["imgui", "volk"]);
+        let imgui = &config.dependencies.as_ref().unwrap()["imgui"];
+        assert_eq!(requested(imgui).0, vec!["vulkan"]);
+        assert_eq!(child_args(imgui), vec!["--features=vulkan"]);
+
+        // An optional dependency by name
+        let mut config = manifest.clone();
+        resolve(&mut config, &["volk".to_string()], false).unwrap();
+        assert_eq!(deps(&config), vec!["imgui", "volk"]);
+
+        // Unknown names
+        let err = resolve(&mut manifest.clone(), &["metal".to_string()], true).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("available: default, opengl, tools, vulkan")
+        );
+        assert!(resolve(&mut manifest.clone(), &["dep:imgui".to_string()], true).is_err());
+        assert!(resolve(&mut manifest.clone(), &["sdl/audio".to_string()], true).is_err());
+    }
+
+    #[test]
+    fn test_define_name() {
+        assert_eq!(define_name("vulkan"), "CX_FEATURE_VULKAN");
+        assert_eq!(define_name("open-gl.es"), "CX_FEATURE_OPEN_GL_ES");
+    }
+}
@@ -0,0 +1,72 @@
+//! `--offline`, `--locked` and `--frozen`.
+//!
+//! - `--offline`: never touch the network; dependencies must already be in
+//!   the cache (or `vendor/`), and the registry is read from its local copy
+//! - `--locked`: fail instead of changing `cx.lock` (missing entries, a new
+//!   revision, a changed pin, a drifted system package)
+//! - `--frozen`: both
+//!
+//! The mode is set once from the command line, or from `CX_OFFLINE=1` /
+//! `CX_LOCKED=1` for CI environments, and read wherever cx would download
+//! something or rewrite the lockfile.
+
+use anyhow::{Result, bail};
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FetchMode {
+    pub offline: bool,
+    pub locked: bool,
+}
+
+static MODE: OnceLock<FetchMode> = OnceLock::new();
+
+fn env_flag(name: &str) -> bool {
+    std::env::var(name).is_ok_and(|v| !v.is_empty() && v != "0" && v != "false")
+}
+
+/// Set the mode from the command line flags (plus `CX_OFFLINE` / `CX_LOCKED`)
+pub fn init(offline: bool, locked: bool, frozen: bool) {
+    let _ = MODE.set(FetchMode {
+        offline: offline || frozen || env_flag("CX_OFFLINE"),
+        locked: locked || frozen || env_flag("CX_LOCKED"),
+    });
+}
+
+pub fn get() -> FetchMode {
+    MODE.get().copied().unwrap_or_default()
+}
+
+pub fn is_offline() -> bool {
+    get().offline
+}
+
+pub fn is_locked() -> bool {
+    get().locked
+}
+
+/// Fail with a clear message if `action` (e.g. "download 'fmt'") would need
+/// the network in offline mode
+pub fn ensure_online(action: &str) -> Result<()> {
+    if is_offline() {
+        bail!(
+            "Cannot {} in offline mode.\n\n\
+            💡 Tip: Run the same command once without --offline/--frozen to fill the cache, or 'cx vendor' the dependency.",
+            action
+        );
+    }
+    Ok(())
+}
+
+/// Flags to pass on to nested `cx` invocations (path dependency builds)
+pub fn child_args() -> Vec<&'static str> {
+    let mode = get();
+    let mut args = Vec::new();
+    if mode.offline {
+        args.push("--offline");
+    }
+    if mode.locked {
+        args.push("--locked");
+    }
+    args
+}
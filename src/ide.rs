@@ -30,6 +30,7 @@ pub fn generate_ide_config() -> Result<()> {
             name: "app".to_string(),
             version: "0.1.0".to_string(),
             edition: "c++17".to_string(),
+            c_edition: None,
         },
         build: None,
         dependencies: None,
@@ -37,6 +38,9 @@ pub fn generate_ide_config() -> Result<()> {
         test: None,
         workspace: None,
         arduino: None,
+        bins: None,
+        targets: None,
+        features: Default::default(),
         profiles: std::collections::HashMap::new(),
     });
 
@@ -116,23 +116,34 @@ pub fn scan_project(path: &Path) -> Result<Option<CxConfig>> {
             } else {
                 "c17".to_string()
             },
+            c_edition: None,
         },
         build: Some(BuildConfig {
             compiler: Some(compiler),
             bin: Some("app".to_string()),
+            kind: None,
             flags: Some(cflags),
             cflags: None,
             libs: None, // Hard to guess libs from source
             ldflags: None,
             sources: None,
+            include_dirs: None,
             pch: None,
             subsystem: None,
+            jobs: None,
+            modules: None,
+            unity: None,
+            unity_batch_size: None,
+            unity_exclude: None,
         }),
         dependencies: None, // Hard to guess deps
         scripts: None,
         test: None,
         workspace: None,
         arduino: None,
+        bins: None,
+        targets: None,
+        features: Default::default(),
         profiles: std::collections::HashMap::new(),
     };
 
@@ -55,6 +55,12 @@ pub mod doc;
 /// Docker configuration generator.
 pub mod docker;
 
+/// Offline and locked modes (`--offline`, `--locked`, `--frozen`).
+pub mod fetch_mode;
+
+/// Cargo-style `[features]` (`--features`, `--all-features`).
+pub mod features;
+
 /// IDE configuration generators (VSCode).
 pub mod ide;
 
@@ -64,6 +70,9 @@ pub mod import;
 /// Lockfile (`cx.lock`) management.
 pub mod lock;
 
+/// Format-preserving `cx.toml` editing.
+pub mod manifest;
+
 /// Project packaging and distribution.
 pub mod package;
 
@@ -1,31 +1,215 @@
-use anyhow::Result;
+//! `cx.lock`: the resolved dependency graph.
+//!
+//! Version 2 records, per package, its source kind, where it came from, the
+//! resolved revision and tag, a checksum of the cached tree and the packages
+//! it depends on. Version 1 files (`git` + `rev` only) are migrated on load
+//! and rewritten as version 2 on the next save; until then, `--locked` only
+//! compares where their packages come from, as they lack the newer fields.
+
+use crate::config::Dependency;
+use anyhow::{Result, bail};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::fs;
 use std::path::Path;
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+/// Current `cx.lock` schema version
+pub const LOCK_VERSION: u32 = 2;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct LockFile {
+    /// Schema version; files without one are version 1
+    #[serde(default = "legacy_version")]
+    pub version: u32,
     #[serde(rename = "package")]
     pub packages: BTreeMap<String, PackageLock>,
+    /// Loaded from an older version, so tags, checksums and dependency edges
+    /// were never recorded
+    #[serde(skip)]
+    pub migrated: bool,
+}
+
+fn legacy_version() -> u32 {
+    1
+}
+
+impl Default for LockFile {
+    fn default() -> Self {
+        Self {
+            version: LOCK_VERSION,
+            packages: BTreeMap::new(),
+            migrated: false,
+        }
+    }
+}
+
+/// Where a locked package comes from
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceKind {
+    #[default]
+    Git,
+    Archive,
+    Path,
+    Pkg,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct PackageLock {
+    #[serde(default)]
+    pub source: SourceKind,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub git: String,
+    /// Archive URL, for `url` dependencies
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Checksum of the archive
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    /// Directory of a `path` dependency, as written in cx.toml
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// pkg-config package name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pkg: Option<String>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub rev: String,
+    /// Tag the locked commit was resolved from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    /// Tag version chosen for a `version` requirement, or the pkg-config
+    /// `--modversion` of a system package
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Content checksum of the cached tree as first fetched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+    /// Names of the packages this one depends on (from its own cx.toml)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<String>,
+}
+
+impl PackageLock {
+    /// Whether both lock the same source at the same revision, ignoring the
+    /// tag, version, checksum and dependencies recorded along with it
+    fn same_source(&self, other: &PackageLock) -> bool {
+        self.source == other.source
+            && self.git == other.git
+            && self.url == other.url
+            && self.sha256 == other.sha256
+            && self.path == other.path
+            && self.pkg == other.pkg
+            && self.rev == other.rev
+    }
+
+    fn new(source: SourceKind) -> Self {
+        Self {
+            source,
+            git: String::new(),
+            url: None,
+            sha256: None,
+            path: None,
+            pkg: None,
+            rev: String::new(),
+            tag: None,
+            version: None,
+            checksum: None,
+            dependencies: Vec::new(),
+        }
+    }
+
+    /// Whether this entry still satisfies `dep` as written in cx.toml
+    pub fn matches(&self, dep: &Dependency) -> bool {
+        let same_url = |a: &str, b: &str| {
+            crate::cache::normalize_git_url(a) == crate::cache::normalize_git_url(b)
+        };
+        match dep {
+            Dependency::Simple(url) => self.source == SourceKind::Git && same_url(&self.git, url),
+            Dependency::Complex {
+                git: Some(url),
+                version,
+                tag,
+                rev,
+                ..
+            } => {
+                self.source == SourceKind::Git
+                    && same_url(&self.git, url)
+                    && rev
+                        .as_ref()
+                        .is_none_or(|r| self.rev.starts_with(r.as_str()))
+                    // Version 1 entries don't know their tag
+                    && tag
+                        .as_ref()
+                        .is_none_or(|t| self.tag.as_ref().is_none_or(|locked| locked == t))
+                    && version.as_ref().is_none_or(|req| {
+                        let locked = self.version.as_deref().map(semver::Version::parse);
+                        match (semver::VersionReq::parse(req), locked) {
+                            (Ok(req), Some(Ok(locked))) => req.matches(&locked),
+                            _ => false,
+                        }
+                    })
+            }
+            Dependency::Complex {
+                url: Some(url),
+                sha256,
+                ..
+            } => {
+                self.source == SourceKind::Archive
+                    && self.url.as_ref() == Some(url)
+                    && match (&self.sha256, sha256) {
+                        (Some(locked), Some(wanted)) => locked.eq_ignore_ascii_case(wanted),
+                        _ => false,
+                    }
+            }
+            Dependency::Complex {
+                path: Some(path), ..
+            } => self.source == SourceKind::Path && self.path.as_ref() == Some(path),
+            Dependency::Complex { pkg: Some(pkg), .. } => {
+                self.source == SourceKind::Pkg && self.pkg.as_ref() == Some(pkg)
+            }
+            Dependency::Complex { .. } => false,
+        }
+    }
 }
 
 impl LockFile {
     pub fn load() -> Result<Self> {
         if Path::new("cx.lock").exists() {
             let content = fs::read_to_string("cx.lock")?;
-            Ok(toml::from_str(&content)?)
+            Self::parse(&content)
         } else {
             Ok(Self::default())
         }
     }
 
+    /// Parse `cx.lock` content, migrating older versions
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut lock: Self = toml::from_str(content)?;
+        if lock.version > LOCK_VERSION {
+            bail!(
+                "cx.lock was written by a newer caxe (lockfile version {}, this caxe reads up to {}). Please upgrade caxe.",
+                lock.version,
+                LOCK_VERSION
+            );
+        }
+        if lock.version < LOCK_VERSION {
+            lock.migrate();
+        }
+        Ok(lock)
+    }
+
+    /// Upgrade a version 1 lockfile: every entry was a git checkout, apart
+    /// from archives (which only ever had `url` + `sha256`)
+    fn migrate(&mut self) {
+        for entry in self.packages.values_mut() {
+            if entry.url.is_some() {
+                entry.source = SourceKind::Archive;
+            }
+        }
+        self.version = LOCK_VERSION;
+        self.migrated = true;
+    }
+
     pub fn save(&self) -> Result<()> {
         let content = toml::to_string_pretty(self)?;
         fs::write("cx.lock", content)?;
@@ -37,7 +221,84 @@ impl LockFile {
     }
 
     pub fn insert(&mut self, name: String, git: String, rev: String) {
-        self.packages.insert(name, PackageLock { git, rev });
+        let mut entry = PackageLock::new(SourceKind::Git);
+        entry.git = git;
+        entry.rev = rev;
+        self.packages.insert(name, entry);
+    }
+
+    pub fn insert_archive(&mut self, name: String, url: String, sha256: String) {
+        let mut entry = PackageLock::new(SourceKind::Archive);
+        entry.url = Some(url);
+        entry.sha256 = Some(sha256);
+        self.packages.insert(name, entry);
+    }
+
+    pub fn insert_path(&mut self, name: String, path: String) {
+        let mut entry = PackageLock::new(SourceKind::Path);
+        entry.path = Some(path);
+        self.packages.insert(name, entry);
+    }
+
+    pub fn insert_pkg(&mut self, name: String, pkg: String, version: Option<String>) {
+        let mut entry = PackageLock::new(SourceKind::Pkg);
+        entry.pkg = Some(pkg);
+        entry.version = version;
+        self.packages.insert(name, entry);
+    }
+
+    pub fn set_version(&mut self, name: &str, version: String) {
+        if let Some(entry) = self.packages.get_mut(name) {
+            entry.version = Some(version);
+        }
+    }
+
+    pub fn set_tag(&mut self, name: &str, tag: String) {
+        if let Some(entry) = self.packages.get_mut(name) {
+            entry.tag = Some(tag);
+        }
+    }
+
+    pub fn set_checksum(&mut self, name: &str, checksum: String) {
+        if let Some(entry) = self.packages.get_mut(name) {
+            entry.checksum = Some(checksum);
+        }
+    }
+
+    /// Drop the packages `keep` rejects, e.g. those no longer in the graph
+    pub fn retain(&mut self, keep: impl Fn(&str) -> bool) {
+        self.packages.retain(|name, _| keep(name));
+    }
+
+    pub fn set_dependencies(&mut self, name: &str, dependencies: Vec<String>) {
+        if let Some(entry) = self.packages.get_mut(name) {
+            entry.dependencies = dependencies;
+        }
+    }
+
+    /// Packages added, changed or removed relative to `old`, e.g. for
+    /// reporting why `--locked` failed
+    pub fn changes_from(&self, old: &LockFile) -> Vec<String> {
+        let mut changes = Vec::new();
+        for (name, entry) in &self.packages {
+            match old.packages.get(name) {
+                None => changes.push(format!("{} is not locked", name)),
+                Some(prev) if prev.rev != entry.rev => changes.push(format!(
+                    "{} moves from {:.7} to {:.7}",
+                    name, prev.rev, entry.rev
+                )),
+                // Migrated entries never recorded the newer fields
+                Some(prev) if old.migrated && prev.same_source(entry) => {}
+                Some(prev) if prev != entry => changes.push(format!("{} changed", name)),
+                Some(_) => {}
+            }
+        }
+        for name in old.packages.keys() {
+            if !self.packages.contains_key(name) {
+                changes.push(format!("{} is no longer needed", name));
+            }
+        }
+        changes
     }
 }
 
@@ -87,4 +348,187 @@ fmt = { git = "https://github.com/fmtlib/fmt", rev = "abc123" }
         let entry = lock.get("fmt").unwrap();
         assert_eq!(entry.rev, "abc123");
     }
+
+    #[test]
+    fn test_lockfile_dependency_edges() {
+        let mut lock = LockFile::default();
+        lock.insert(
+            "spdlog".to_string(),
+            "https://github.com/gabime/spdlog".to_string(),
+            "abc123".to_string(),
+        );
+        lock.set_dependencies("spdlog", vec!["fmt".to_string()]);
+        lock.set_dependencies("missing", vec!["fmt".to_string()]);
+        assert!(lock.get("missing").is_none());
+        assert_eq!(lock.get("spdlog").unwrap().dependencies, vec!["fmt"]);
+
+        let toml_str = toml::to_string_pretty(&lock).unwrap();
+        assert!(toml_str.contains("dependencies = [\"fmt\"]"));
+        let parsed: LockFile = toml::from_str(&toml_str).unwrap();
+        assert_eq!(parsed.get("spdlog").unwrap().dependencies, vec!["fmt"]);
+    }
+
+    #[test]
+    fn test_lockfile_v1_migration() {
+        let v1 = r#"
+[package.fmt]
+git = "https://github.com/fmtlib/fmt"
+rev = "abc123"
+
+[package.lua]
+url = "https://www.lua.org/ftp/lua-5.4.6.tar.gz"
+sha256 = "7d5ea1b9"
+"#;
+        let lock = LockFile::parse(v1).unwrap();
+        assert_eq!(lock.version, LOCK_VERSION);
+        assert_eq!(lock.get("fmt").unwrap().source, SourceKind::Git);
+        assert_eq!(lock.get("lua").unwrap().source, SourceKind::Archive);
+
+        let saved = toml::to_string_pretty(&lock).unwrap();
+        assert!(saved.starts_with("version = 2\n"));
+        assert!(saved.contains("source = \"archive\""));
+
+        assert!(LockFile::parse("version = 3\n[package]\n").is_err());
+
+        // The first --locked build records the newer fields without failing
+        let mut rebuilt = LockFile::default();
+        rebuilt.insert(
+            "fmt".to_string(),
+            "https://github.com/fmtlib/fmt".to_string(),
+            "abc123".to_string(),
+        );
+        rebuilt.set_tag("fmt", "10.1.1".to_string());
+        rebuilt.set_checksum("fmt", "0f0f".to_string());
+        rebuilt.insert_archive(
+            "lua".to_string(),
+            "https://www.lua.org/ftp/lua-5.4.6.tar.gz".to_string(),
+            "7d5ea1b9".to_string(),
+        );
+        assert!(rebuilt.changes_from(&lock).is_empty());
+        rebuilt.insert(
+            "fmt".to_string(),
+            "https://github.com/fmtlib/fmt".to_string(),
+            "def456".to_string(),
+        );
+        assert_eq!(
+            rebuilt.changes_from(&lock),
+            vec!["fmt moves from abc123 to def456"]
+        );
+    }
+
+    #[test]
+    fn test_lockfile_matches_manifest() {
+        let mut lock = LockFile::default();
+        lock.insert(
+            "spdlog".to_string(),
+            "https://github.com/gabime/spdlog.git".to_string(),
+            "abc123".to_string(),
+        );
+        lock.set_version("spdlog", "1.12.0".to_string());
+        lock.set_tag("spdlog", "v1.12.0".to_string());
+        lock.insert_pkg(
+            "gtk".to_string(),
+            "gtk4".to_string(),
+            Some("4.8.3".to_string()),
+        );
+        let spdlog = lock.get("spdlog").unwrap();
+
+        let parse = |line: &str| -> Dependency {
+            toml::from_str::<toml::Table>(&format!("d = {}", line))
+                .unwrap()
+                .remove("d")
+                .unwrap()
+                .try_into()
+                .unwrap()
+        };
+        assert!(spdlog.matches(&parse(
+            r#"{ git = "https://github.com/gabime/spdlog", version = "^1.12" }"#
+        )));
+        assert!(!spdlog.matches(&parse(
+            r#"{ git = "https://github.com/gabime/spdlog", version = "^1.13" }"#
+        )));
+        assert!(spdlog.matches(&parse(
+            r#"{ git = "https://github.com/gabime/spdlog", tag = "v1.12.0" }"#
+        )));
+        assert!(!spdlog.matches(&parse(
+            r#"{ git = "https://github.com/someone/spdlog", tag = "v1.12.0" }"#
+        )));
+        assert!(!spdlog.matches(&parse(r#"{ pkg = "spdlog" }"#)));
+        assert!(
+            lock.get("gtk")
+                .unwrap()
+                .matches(&parse(r#"{ pkg = "gtk4" }"#))
+        );
+    }
+
+    #[test]
+    fn test_lockfile_changes() {
+        let mut old = LockFile::default();
+        old.insert(
+            "fmt".to_string(),
+            "https://x/fmt".to_string(),
+            "a".repeat(40),
+        );
+        old.insert(
+            "json".to_string(),
+            "https://x/json".to_string(),
+            "b".repeat(40),
+        );
+        old.insert_pkg(
+            "gtk".to_string(),
+            "gtk4".to_string(),
+            Some("4.8".to_string()),
+        );
+
+        let mut new = LockFile::default();
+        new.insert(
+            "fmt".to_string(),
+            "https://x/fmt".to_string(),
+            "c".repeat(40),
+        );
+        new.insert(
+            "json".to_string(),
+            "https://x/json".to_string(),
+            "b".repeat(40),
+        );
+        new.insert_pkg(
+            "gtk".to_string(),
+            "gtk4".to_string(),
+            Some("4.10".to_string()),
+        );
+        new.insert_path("common".to_string(), "../common".to_string());
+        assert_eq!(
+            new.changes_from(&old),
+            vec![
+                "common is not locked",
+                "fmt moves from aaaaaaa to ccccccc",
+                "gtk changed"
+            ]
+        );
+        assert!(old.changes_from(&old).is_empty());
+
+        // Removed dependencies are dropped before comparing
+        let mut removed = old.clone();
+        removed.retain(|name| name != "json");
+        assert_eq!(removed.changes_from(&old), vec!["json is no longer needed"]);
+    }
+
+    #[test]
+    fn test_lockfile_archive() {
+        let mut lock = LockFile::default();
+        lock.insert_archive(
+            "lua".to_string(),
+            "https://www.lua.org/ftp/lua-5.4.6.tar.gz".to_string(),
+            "7d5ea1b9".to_string(),
+        );
+        let toml_str = toml::to_string_pretty(&lock).unwrap();
+        assert!(toml_str.contains("sha256 = \"7d5ea1b9\""));
+        assert!(!toml_str.contains("git ="));
+        assert!(!toml_str.contains("rev ="));
+
+        let parsed: LockFile = toml::from_str(&toml_str).unwrap();
+        let entry = parsed.get("lua").unwrap();
+        assert_eq!(entry.sha256.as_deref(), Some("7d5ea1b9"));
+        assert!(entry.git.is_empty());
+    }
 }
@@ -19,9 +19,12 @@ use clap_complete::{Shell, generate};
 use colored::*;
 use inquire::{Select, Text};
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::Path;
+#[cfg(windows)]
+use std::path::PathBuf;
 
 use caxe::build;
+use caxe::build::diagnostics::{self, MessageFormat};
 use caxe::cache;
 use caxe::checker;
 use caxe::ci;
@@ -34,7 +37,9 @@ use caxe::import;
 use caxe::package;
 use caxe::registry;
 use caxe::stats;
+use caxe::status;
 use caxe::templates;
+#[cfg(windows)]
 use caxe::toolchain;
 use caxe::tree;
 use caxe::ui;
@@ -50,6 +55,24 @@ use caxe::upgrade;
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+    /// Never access the network; dependencies must already be cached or vendored
+    #[arg(long, global = true)]
+    offline: bool,
+    /// Fail instead of updating cx.lock
+    #[arg(long, global = true)]
+    locked: bool,
+    /// Same as --offline --locked
+    #[arg(long, global = true)]
+    frozen: bool,
+    /// Features to enable (comma separated)
+    #[arg(long, global = true, value_delimiter = ',')]
+    features: Vec<String>,
+    /// Enable every feature and optional dependency
+    #[arg(long, global = true)]
+    all_features: bool,
+    /// Don't enable the `default` feature
+    #[arg(long, global = true)]
+    no_default_features: bool,
 }
 
 #[derive(Subcommand)]
@@ -94,6 +117,27 @@ enum Commands {
         /// Use a named profile (e.g., --profile esp32)
         #[arg(long)]
         profile: Option<String>,
+        /// Only build the named [[bin]] target
+        #[arg(long)]
+        bin: Option<String>,
+        /// Number of parallel compile jobs (default: CX_JOBS, [build] jobs, or all cores)
+        #[arg(short, long)]
+        jobs: Option<usize>,
+        /// Compile every file and report all failures at the end
+        #[arg(long)]
+        keep_going: bool,
+        /// Unity build: compile sources in generated batches
+        #[arg(long)]
+        unity: bool,
+        /// Cross-compile for a target (e.g., linux-arm64 or aarch64-linux-gnu)
+        #[arg(long)]
+        target: Option<String>,
+        /// Toolchain for --target as JSON (passed to path dependency builds)
+        #[arg(long, hide = true)]
+        target_settings: Option<String>,
+        /// Report diagnostics as human-readable text or JSON lines
+        #[arg(long, value_enum, default_value_t = MessageFormat::Human)]
+        message_format: MessageFormat,
     },
     /// Compile and run the output binary
     Run {
@@ -106,6 +150,9 @@ enum Commands {
         /// Show what would be executed without running
         #[arg(long)]
         dry_run: bool,
+        /// Name of the [[bin]] target to run
+        #[arg(long)]
+        bin: Option<String>,
         /// Arguments passed to the target program
         #[arg(num_args = 0.., allow_hyphen_values = true)]
         args: Vec<String>,
@@ -172,6 +219,9 @@ enum Commands {
         /// Filter tests by name
         #[arg(long)]
         filter: Option<String>,
+        /// Report diagnostics as human-readable text or JSON lines
+        #[arg(long, value_enum, default_value_t = MessageFormat::Human)]
+        message_format: MessageFormat,
     },
     /// Show system and project setup info
     Info,
@@ -184,7 +234,11 @@ enum Commands {
     /// Generate documentation using Doxygen
     Doc,
     /// Static analysis using clang-tidy / cppcheck
-    Check,
+    Check {
+        /// Report diagnostics as human-readable text or JSON lines
+        #[arg(long, value_enum, default_value_t = MessageFormat::Human)]
+        message_format: MessageFormat,
+    },
     /// Update dependencies to latest versions
     Update,
     /// Upgrade caxe itself (if installed via cargo)
@@ -249,7 +303,17 @@ enum Commands {
 #[derive(Subcommand)]
 enum CacheOp {
     /// Clean the cache
-    Clean,
+    Clean {
+        /// Only remove compiled objects (~/.cx/cache/objects)
+        #[arg(long)]
+        objects: bool,
+        /// Only remove objects not used for this long (e.g. 30d, 12h; implies --objects)
+        #[arg(long, value_name = "AGE")]
+        older_than: Option<String>,
+        /// Evict the least recently used objects until the cache fits (e.g. 2G; implies --objects)
+        #[arg(long, value_name = "SIZE")]
+        max_size: Option<String>,
+    },
     /// List cached items
     Ls,
     /// Print cache directory path
@@ -305,7 +369,13 @@ enum GenerateFormat {
 }
 
 fn main() -> Result<()> {
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
+    caxe::fetch_mode::init(cli.offline, cli.locked, cli.frozen);
+    caxe::features::init(
+        std::mem::take(&mut cli.features),
+        cli.all_features,
+        cli.no_default_features,
+    );
 
     match &cli.command {
         Some(Commands::New {
@@ -352,6 +422,13 @@ fn main() -> Result<()> {
             sanitize,
             arduino,
             profile,
+            bin,
+            jobs,
+            keep_going,
+            unity,
+            target,
+            target_settings,
+            message_format,
         }) => {
             // Auto-detect Arduino projects: check for .ino files or [arduino] config
             let has_ino_files = std::fs::read_dir(".")
@@ -381,11 +458,29 @@ fn main() -> Result<()> {
                 lto: *lto,
                 sanitize: sanitize.clone(),
                 profile: profile.clone(),
+                bin: bin.clone(),
+                jobs: *jobs,
+                keep_going: *keep_going,
+                message_format: *message_format,
+                unity: *unity,
+                // `cx target default <name>` applies when --target isn't given
+                target: target.clone().or_else(|| {
+                    config
+                        .targets
+                        .as_ref()
+                        .and_then(|t| t.default_target.clone())
+                }),
+                target_settings: target_settings
+                    .as_deref()
+                    .map(serde_json::from_str)
+                    .transpose()
+                    .context("Invalid --target-settings")?,
             };
 
             // Workspace Support
             if let Some(ws) = &config.workspace {
-                println!(
+                diagnostics::set_message_format(*message_format);
+                status!(
                     "{} Building Workspace ({} members)...",
                     "🚀".cyan(),
                     ws.members.len()
@@ -395,25 +490,28 @@ fn main() -> Result<()> {
                 for member in &ws.members {
                     let member_path = root_dir.join(member);
                     if !member_path.exists() {
-                        println!("{} Member '{}' not found", "x".red(), member);
+                        status!("{} Member '{}' not found", "x".red(), member);
                         continue;
                     }
 
-                    println!("\n{} Building member: {}", "📦".blue(), member);
+                    status!("\n{} Building member: {}", "📦".blue(), member);
                     std::env::set_current_dir(&member_path)?;
 
                     // Reload config for member
                     match build::load_config() {
                         Ok(member_config) => {
                             if let Err(e) = build::build_project(&member_config, &options) {
-                                println!("{} Build failed for {}: {}", "x".red(), member, e);
+                                if message_format.is_json() {
+                                    diagnostics::emit_finished(false);
+                                }
+                                status!("{} Build failed for {}: {}", "x".red(), member, e);
                                 // Continue or exit? Usually fail fast?
                                 std::env::set_current_dir(&root_dir)?;
                                 std::process::exit(1);
                             }
                         }
                         Err(e) => {
-                            println!("{} Failed to load config for {}: {}", "x".red(), member, e);
+                            status!("{} Failed to load config for {}: {}", "x".red(), member, e);
                             std::env::set_current_dir(&root_dir)?;
                             std::process::exit(1);
                         }
@@ -421,6 +519,9 @@ fn main() -> Result<()> {
                 }
                 // Restore root (though we are done/exiting)
                 std::env::set_current_dir(&root_dir)?;
+                if message_format.is_json() {
+                    diagnostics::emit_finished(true);
+                }
 
                 // Also build root if it has sources?
                 // Logic: If [package] exists (mandatory currently) and has sources, build it?
@@ -437,7 +538,11 @@ fn main() -> Result<()> {
                 // Let's just finish here. The loop built the members.
                 Ok(())
             } else {
-                build::build_project(&config, &options).map(|_| ())
+                let result = build::build_project(&config, &options);
+                if message_format.is_json() {
+                    diagnostics::emit_finished(matches!(result, Ok(true)));
+                }
+                result.map(|_| ())
             }
         }
 
@@ -445,12 +550,23 @@ fn main() -> Result<()> {
             release,
             verbose,
             dry_run,
+            bin,
             args,
-        }) => build::build_and_run(*release, *verbose, *dry_run, args.clone(), None),
+        }) => build::build_and_run(
+            *release,
+            *verbose,
+            *dry_run,
+            bin.clone(),
+            args.clone(),
+            None,
+        ),
 
         Some(Commands::Watch { test }) => build::watch(*test),
         Some(Commands::Clean { cache, all, unused }) => build::clean(*cache, *all, *unused),
-        Some(Commands::Test { filter }) => build::run_tests(filter.clone()),
+        Some(Commands::Test {
+            filter,
+            message_format,
+        }) => build::run_tests(filter.clone(), *message_format),
         Some(Commands::Add {
             lib,
             tag,
@@ -461,12 +577,16 @@ fn main() -> Result<()> {
         Some(Commands::Info) => print_info(),
         Some(Commands::Fmt { check }) => checker::format_code(*check),
         Some(Commands::Doc) => doc::generate_docs(),
-        Some(Commands::Check) => checker::check_code(),
+        Some(Commands::Check { message_format }) => checker::check_code(*message_format),
         Some(Commands::Update) => deps::update_dependencies(),
         Some(Commands::Upgrade) => upgrade::check_and_upgrade(),
         Some(Commands::Init) => init_project(),
         Some(Commands::Cache { op }) => match op {
-            CacheOp::Clean => cache::clean(),
+            CacheOp::Clean {
+                objects,
+                older_than,
+                max_size,
+            } => cache::clean(*objects, older_than.as_deref(), max_size.as_deref()),
             CacheOp::Ls => cache::list(),
             CacheOp::Path => cache::print_path(),
         },
@@ -530,7 +650,7 @@ fn main() -> Result<()> {
             let run_args = args[1..].to_vec();
 
             // Script mode defaults: release=false, verbose=false, dry_run=false
-            build::build_and_run(false, false, false, run_args, script_path)
+            build::build_and_run(false, false, false, None, run_args, script_path)
         }
         None => {
             print_splash();
@@ -0,0 +1,343 @@
+//! Format-preserving editing of `cx.toml`.
+//!
+//! Commands that change the manifest (`cx add`, `cx remove`, `cx target`,
+//! `cx toolchain select`) go through [`Manifest`] instead of re-serializing
+//! [`CxConfig`](crate::config::CxConfig), so comments, key order and sections
+//! cx doesn't model (like `[profile.*]`) survive every edit.
+
+use crate::config::Dependency;
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml_edit::{Array, DocumentMut, InlineTable, Item, Table, Value, value};
+
+/// An editable `cx.toml` document
+pub struct Manifest {
+    path: PathBuf,
+    doc: DocumentMut,
+}
+
+impl Manifest {
+    /// Open `cx.toml` in the current directory
+    pub fn open() -> Result<Self> {
+        Self::open_at(Path::new("cx.toml"))
+    }
+
+    pub fn open_at(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let mut manifest = Self::parse(&content)?;
+        manifest.path = path.to_path_buf();
+        Ok(manifest)
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let doc = content
+            .parse::<DocumentMut>()
+            .context("Failed to parse cx.toml - check for syntax errors")?;
+        Ok(Self {
+            path: PathBuf::from("cx.toml"),
+            doc,
+        })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        fs::write(&self.path, self.doc.to_string())
+            .with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+
+    /// Get a top-level table, creating it (at the end of the file) if missing
+    fn table_mut(&mut self, name: &str) -> Result<&mut Table> {
+        self.doc
+            .entry(name)
+            .or_insert_with(|| Item::Table(Table::new()))
+            .as_table_mut()
+            .with_context(|| format!("[{}] in cx.toml is not a table", name))
+    }
+
+    pub fn has_dependency(&self, name: &str) -> bool {
+        self.doc
+            .get("dependencies")
+            .and_then(|deps| deps.as_table_like())
+            .is_some_and(|deps| deps.contains_key(name))
+    }
+
+    /// Insert or replace a dependency, keeping the rest of `[dependencies]` as is
+    pub fn set_dependency(&mut self, name: &str, dep: &Dependency) -> Result<()> {
+        let deps = self.table_mut("dependencies")?;
+        let item = dependency_item(dep);
+        match deps.get_mut(name) {
+            // Keep the existing key's position and comments
+            Some(existing) => *existing = item,
+            None => {
+                deps.insert(name, item);
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove a dependency; returns whether it was present
+    pub fn remove_dependency(&mut self, name: &str) -> bool {
+        self.doc
+            .get_mut("dependencies")
+            .and_then(|deps| deps.as_table_like_mut())
+            .is_some_and(|deps| deps.remove(name).is_some())
+    }
+
+    /// Set a string key in `[build]`, e.g. `compiler = "clang"`
+    pub fn set_build_value(&mut self, key: &str, val: &str) -> Result<()> {
+        let build = self.table_mut("build")?;
+        match build.get_mut(key).and_then(|item| item.as_value_mut()) {
+            Some(existing) => {
+                // Keep trailing comments on the line
+                let decor = existing.decor().clone();
+                *existing = Value::from(val);
+                *existing.decor_mut() = decor;
+            }
+            None => {
+                build.insert(key, value(val));
+            }
+        }
+        Ok(())
+    }
+
+    /// Targets in `[targets] list`
+    pub fn targets(&self) -> Vec<String> {
+        self.doc
+            .get("targets")
+            .and_then(|t| t.get("list"))
+            .and_then(|list| list.as_array())
+            .map(|list| {
+                list.iter()
+                    .filter_map(|v| v.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Append to `[targets] list`; returns false if already listed
+    pub fn add_target(&mut self, name: &str) -> Result<bool> {
+        let targets = self.table_mut("targets")?;
+        let list = targets
+            .entry("list")
+            .or_insert_with(|| value(Array::new()))
+            .as_array_mut()
+            .context("[targets] list in cx.toml is not an array")?;
+        if list.iter().any(|v| v.as_str() == Some(name)) {
+            return Ok(false);
+        }
+        list.push(name);
+        Ok(true)
+    }
+
+    /// Remove from `[targets] list` (and `default_target` if it pointed
+    /// there); returns whether it was listed
+    pub fn remove_target(&mut self, name: &str) -> bool {
+        let Some(targets) = self.doc.get_mut("targets").and_then(|t| t.as_table_mut()) else {
+            return false;
+        };
+        let mut found = false;
+        if let Some(list) = targets.get_mut("list").and_then(|l| l.as_array_mut()) {
+            let before = list.len();
+            list.retain(|v| v.as_str() != Some(name));
+            found = list.len() != before;
+        }
+        if targets.get("default_target").and_then(|d| d.as_str()) == Some(name) {
+            targets.remove("default_target");
+            found = true;
+        }
+        found
+    }
+
+    pub fn set_default_target(&mut self, name: &str) -> Result<()> {
+        self.table_mut("targets")?
+            .insert("default_target", value(name));
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Manifest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.doc)
+    }
+}
+
+/// `"url"` for simple deps, an inline table for everything else
+fn dependency_item(dep: &Dependency) -> Item {
+    match dep {
+        Dependency::Simple(url) => value(url.as_str()),
+        Dependency::Complex {
+            git,
+            pkg,
+            path,
+            url,
+            sha256,
+            version,
+            branch,
+            tag,
+            rev,
+            build,
+            output,
+            sources,
+            include_dirs,
+            defines,
+            libs,
+            link_dirs,
+            optional,
+            features,
+            default_features,
+            cmake,
+            meson,
+            autotools,
+        } => {
+            let mut table = InlineTable::new();
+            let fields = [
+                ("git", git),
+                ("pkg", pkg),
+                ("path", path),
+                ("url", url),
+                ("sha256", sha256),
+                ("version", version),
+                ("branch", branch),
+                ("tag", tag),
+                ("rev", rev),
+                ("build", build),
+                ("output", output),
+            ];
+            for (key, field) in fields {
+                if let Some(field) = field {
+                    table.insert(key, Value::from(field.as_str()));
+                }
+            }
+            let lists = [
+                ("sources", sources),
+                ("include_dirs", include_dirs),
+                ("defines", defines),
+                ("libs", libs),
+                ("link_dirs", link_dirs),
+                ("features", features),
+            ];
+            for (key, list) in lists {
+                if let Some(list) = list {
+                    table.insert(key, Value::Array(list.iter().collect::<Array>()));
+                }
+            }
+            for (key, flag) in [
+                ("optional", optional),
+                ("default_features", default_features),
+            ] {
+                if let Some(flag) = flag {
+                    table.insert(key, Value::from(*flag));
+                }
+            }
+            if let Some(cmake) = cmake {
+                let mut cmake_table = options_table(&cmake.options);
+                if !cmake.targets.is_empty() {
+                    cmake_table.insert(
+                        "targets",
+                        Value::Array(cmake.targets.iter().collect::<Array>()),
+                    );
+                }
+                table.insert("cmake", Value::InlineTable(cmake_table));
+            }
+            if let Some(meson) = meson {
+                table.insert("meson", Value::InlineTable(options_table(&meson.options)));
+            }
+            if let Some(autotools) = autotools {
+                let mut autotools_table = InlineTable::new();
+                if !autotools.args.is_empty() {
+                    autotools_table.insert(
+                        "args",
+                        Value::Array(autotools.args.iter().collect::<Array>()),
+                    );
+                }
+                table.insert("autotools", Value::InlineTable(autotools_table));
+            }
+            value(table)
+        }
+    }
+}
+
+/// `{ options = { ... } }` of a build system adapter (empty without options)
+fn options_table(options: &BTreeMap<String, toml::Value>) -> InlineTable {
+    let mut table = InlineTable::new();
+    let mut values = InlineTable::new();
+    for (name, option) in options {
+        if let Ok(option) = option.to_string().parse::<Value>() {
+            values.insert(name, option);
+        }
+    }
+    if !values.is_empty() {
+        table.insert("options", Value::InlineTable(values));
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"# My project
+[package]
+name = "app"   # keep me
+version = "0.1.0"
+
+[dependencies]
+# Formatting
+fmt = "https://github.com/fmtlib/fmt"
+
+[profile.esp32]
+base = "release"
+"#;
+
+    #[test]
+    fn test_add_and_remove_dependency_preserves_formatting() {
+        let mut json = Dependency::git("https://github.com/nlohmann/json");
+        if let Dependency::Complex { tag, .. } = &mut json {
+            *tag = Some("v3.11.2".to_string());
+        }
+        let mut manifest = Manifest::parse(MANIFEST).unwrap();
+        manifest.set_dependency("json", &json).unwrap();
+        let out = manifest.to_string();
+        assert!(out.starts_with("# My project\n"));
+        assert!(out.contains("name = \"app\"   # keep me"));
+        assert!(out.contains("# Formatting\nfmt = "));
+        assert!(
+            out.contains(
+                "json = { git = \"https://github.com/nlohmann/json\", tag = \"v3.11.2\" }"
+            )
+        );
+        assert!(out.contains("[profile.esp32]\nbase = \"release\""));
+
+        assert!(manifest.remove_dependency("fmt"));
+        assert!(!manifest.remove_dependency("fmt"));
+        assert!(!manifest.has_dependency("fmt"));
+        assert!(manifest.to_string().contains("[profile.esp32]"));
+    }
+
+    #[test]
+    fn test_targets() {
+        let mut manifest = Manifest::parse(MANIFEST).unwrap();
+        assert!(manifest.add_target("linux-arm64").unwrap());
+        assert!(manifest.add_target("wasm32").unwrap());
+        assert!(!manifest.add_target("wasm32").unwrap());
+        manifest.set_default_target("wasm32").unwrap();
+        assert_eq!(manifest.targets(), vec!["linux-arm64", "wasm32"]);
+
+        assert!(manifest.remove_target("wasm32"));
+        assert_eq!(manifest.targets(), vec!["linux-arm64"]);
+        assert!(!manifest.to_string().contains("default_target"));
+    }
+
+    #[test]
+    fn test_set_build_value() {
+        let mut manifest =
+            Manifest::parse("[build]\ncompiler = \"g++\" # pinned\nflags = []\n").unwrap();
+        manifest.set_build_value("compiler", "clang").unwrap();
+        assert_eq!(
+            manifest.to_string(),
+            "[build]\ncompiler = \"clang\" # pinned\nflags = []\n"
+        );
+    }
+}
@@ -38,6 +38,13 @@ pub fn package_project(output_name: Option<String>, release: bool) -> Result<()>
         lto: true, // optimize for size/speed for package
         sanitize: None,
         profile: None,
+        bin: None,
+        jobs: None,
+        keep_going: false,
+        message_format: build::diagnostics::MessageFormat::Human,
+        unity: false,
+        target: None,
+        target_settings: None,
     };
 
     if let Err(e) = build::build_project(&config, &build_opts) {
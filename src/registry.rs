@@ -1,3 +1,5 @@
+use crate::config::Dependency;
+use crate::manifest::Manifest;
 use anyhow::{Context, Result};
 use colored::*;
 use serde::Deserialize;
@@ -15,6 +17,12 @@ const CACHE_TTL_SECS: u64 = 86400; // 24 hours
 pub struct RegistryEntry {
     pub url: String,
     pub description: Option<String>,
+    /// Sources to compile into a library, copied into cx.toml by `cx add`
+    #[serde(default)]
+    pub sources: Option<Vec<String>>,
+    /// Public header directories, copied into cx.toml by `cx add`
+    #[serde(default)]
+    pub include_dirs: Option<Vec<String>>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -26,7 +34,6 @@ impl Registry {
         registry.0.get(name).map(|entry| entry.url.clone())
     }
 
-    #[allow(dead_code)]
     pub fn get_entry(name: &str) -> Option<RegistryEntry> {
         let registry = Self::load().unwrap_or_else(|_| Self::default());
         registry.0.get(name).cloned()
@@ -42,6 +49,8 @@ impl Registry {
                 description: Some(
                     "A simple and easy-to-use library to enjoy videogames programming".to_string(),
                 ),
+                sources: None,
+                include_dirs: None,
             },
         );
         m.insert(
@@ -49,6 +58,8 @@ impl Registry {
             RegistryEntry {
                 url: "https://github.com/nlohmann/json.git".to_string(),
                 description: Some("JSON for Modern C++".to_string()),
+                sources: None,
+                include_dirs: None,
             },
         );
         m.insert(
@@ -56,6 +67,8 @@ impl Registry {
             RegistryEntry {
                 url: "https://github.com/fmtlib/fmt.git".to_string(),
                 description: Some("A modern formatting library".to_string()),
+                sources: Some(vec!["src/format.cc".to_string(), "src/os.cc".to_string()]),
+                include_dirs: None,
             },
         );
         Self(m)
@@ -75,7 +88,16 @@ impl Registry {
             return Ok(Self(reg));
         }
 
-        // 2. Fetch from Remote
+        // 2. Offline: any local copy, however old
+        if crate::fetch_mode::is_offline() {
+            return Ok(fs::read_to_string(&cache_path)
+                .ok()
+                .and_then(|content| serde_json::from_str(&content).ok())
+                .map(Self)
+                .unwrap_or_else(Self::default));
+        }
+
+        // 3. Fetch from Remote
         print!("{} Fetching registry... ", "⚡".yellow());
         match ureq::get(REGISTRY_URL).call() {
             Ok(mut response) => {
@@ -153,32 +175,16 @@ pub fn add_package(name: &str) -> Result<()> {
         )
     })?;
 
-    // Read existing cx.toml
-    let content = fs::read_to_string("cx.toml")?;
+    let mut manifest = Manifest::open()?;
 
     // Check if package already exists
-    let name_lower = name.to_lowercase();
-    if content
-        .to_lowercase()
-        .contains(&format!("{} =", name_lower))
-    {
+    if manifest.has_dependency(name) {
         println!("   {} {} is already in dependencies", "⚡".yellow(), name);
         return Ok(());
     }
 
-    // Build the dependency line
-    let dep_line = format!("{} = \"{}\"", name, url);
-
-    // Check if [dependencies] section exists
-    let new_content = if content.contains("[dependencies]") {
-        // Add after [dependencies] line
-        content.replace("[dependencies]", &format!("[dependencies]\n{}", dep_line))
-    } else {
-        // Add new [dependencies] section at the end
-        format!("{}\n\n[dependencies]\n{}\n", content.trim(), dep_line)
-    };
-
-    fs::write("cx.toml", new_content)?;
+    manifest.set_dependency(name, &Dependency::Simple(url.clone()))?;
+    manifest.save()?;
 
     println!("   {} Added {} to dependencies", "✓".green(), name.cyan());
     println!("   {} {}", "📦".blue(), url);
@@ -194,30 +200,12 @@ pub fn remove_package(name: &str) -> Result<()> {
         anyhow::bail!("No cx.toml found in current directory");
     }
 
-    let content = fs::read_to_string("cx.toml")?;
-    let name_lower = name.to_lowercase();
-
-    // Find and remove the line containing the package
-    let mut found = false;
-    let new_lines: Vec<&str> = content
-        .lines()
-        .filter(|line| {
-            let line_lower = line.to_lowercase().trim_start().to_string();
-            let matches = line_lower.starts_with(&format!("{} =", name_lower))
-                || line_lower.starts_with(&format!("\"{}\"", name_lower));
-            if matches {
-                found = true;
-            }
-            !matches
-        })
-        .collect();
-
-    if !found {
+    let mut manifest = Manifest::open()?;
+    if !manifest.remove_dependency(name) {
         println!("   {} {} not found in dependencies", "⚠".yellow(), name);
         return Ok(());
     }
-
-    fs::write("cx.toml", new_lines.join("\n"))?;
+    manifest.save()?;
 
     println!(
         "   {} Removed {} from dependencies",
@@ -0,0 +1,363 @@
+//! Cross-compilation targets.
+//!
+//! Maps `cx target` names (`linux-arm64`, `windows-x64-gnu`, ...) or raw
+//! target triples to a cross toolchain: a prefixed GCC such as
+//! `aarch64-linux-gnu-g++` when one is installed, otherwise Clang with
+//! `--target=<triple>`. An optional sysroot from `[targets.<name>]` is passed
+//! to the compiler, the linker and pkg-config.
+
+use super::types::{CompilerType, Toolchain};
+use crate::config::{TargetSettings, TargetsConfig};
+use anyhow::{Result, bail};
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Named targets accepted by `cx target add` and `cx build --target`
+pub const KNOWN_TARGETS: &[(&str, &str)] = &[
+    ("windows-x64", "x86_64-pc-windows-msvc"),
+    ("windows-x64-gnu", "x86_64-w64-mingw32"),
+    ("linux-x64", "x86_64-linux-gnu"),
+    ("linux-arm64", "aarch64-linux-gnu"),
+    ("macos-x64", "x86_64-apple-darwin"),
+    ("macos-arm64", "aarch64-apple-darwin"),
+    ("wasm32", "wasm32-unknown-emscripten"),
+    ("esp32", "xtensa-esp32-elf"),
+];
+
+/// Operating system a target produces binaries for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetOs {
+    Linux,
+    Windows,
+    MacOs,
+    Wasm,
+    /// Bare metal (e.g. `xtensa-esp32-elf`)
+    None,
+}
+
+impl TargetOs {
+    /// The OS cx itself is running on
+    pub fn host() -> Self {
+        if cfg!(target_os = "windows") {
+            TargetOs::Windows
+        } else if cfg!(target_os = "macos") {
+            TargetOs::MacOs
+        } else {
+            TargetOs::Linux
+        }
+    }
+
+    pub fn from_triple(triple: &str) -> Self {
+        let parts: Vec<&str> = triple.split('-').collect();
+        let has = |names: &[&str]| parts.iter().any(|p| names.contains(p));
+        if has(&["wasm32", "wasm64", "emscripten", "wasi"]) {
+            TargetOs::Wasm
+        } else if has(&["windows", "mingw32", "w64"]) {
+            TargetOs::Windows
+        } else if has(&["apple", "darwin", "macos"]) {
+            TargetOs::MacOs
+        } else if has(&["linux"]) {
+            TargetOs::Linux
+        } else {
+            TargetOs::None
+        }
+    }
+}
+
+/// A resolved `--target`
+#[derive(Debug, Clone)]
+pub struct CrossTarget {
+    /// Name as given (`linux-arm64` or a triple); used for the build directory
+    pub name: String,
+    pub triple: String,
+    pub os: TargetOs,
+    /// Prefix of the GNU cross tools, e.g. `aarch64-linux-gnu-`
+    pub prefix: String,
+    /// Compiler from `[targets.<name>] compiler`
+    pub compiler: Option<String>,
+    pub sysroot: Option<PathBuf>,
+    /// Extra compiler flags from `[targets.<name>] flags`
+    pub flags: Vec<String>,
+    /// Cross toolchain, filled by [`CrossTarget::detect_toolchain`]
+    pub toolchain: Option<Toolchain>,
+}
+
+impl CrossTarget {
+    /// Resolve a target name or triple, applying `[targets.<name>]` overrides
+    pub fn resolve(name: &str, targets: Option<&TargetsConfig>) -> Result<Self> {
+        let settings = targets
+            .and_then(|t| t.settings.get(name))
+            .cloned()
+            .unwrap_or_default();
+        Self::with_settings(name, settings)
+    }
+
+    /// Resolve a target name or triple with the given toolchain settings
+    pub fn with_settings(name: &str, settings: TargetSettings) -> Result<Self> {
+        let triple = match KNOWN_TARGETS.iter().find(|(known, _)| *known == name) {
+            Some((_, triple)) => triple.to_string(),
+            None if name.split('-').count() >= 2 => name.to_string(),
+            None => bail!(
+                "Unknown target '{}'. Run 'cx target list' to see available targets, or pass a target triple (e.g. aarch64-linux-gnu).",
+                name
+            ),
+        };
+
+        Ok(Self {
+            name: name.to_string(),
+            os: TargetOs::from_triple(&triple),
+            prefix: settings.prefix.unwrap_or_else(|| format!("{}-", triple)),
+            compiler: settings.compiler,
+            sysroot: settings.sysroot.map(PathBuf::from),
+            flags: settings.flags.unwrap_or_default(),
+            triple,
+            toolchain: None,
+        })
+    }
+
+    pub fn is_wasm(&self) -> bool {
+        self.os == TargetOs::Wasm
+    }
+
+    /// Whether the target is the machine cx runs on, so the regular host
+    /// toolchain can build for it
+    pub fn is_native(&self) -> bool {
+        let arch = match self.triple.split('-').next().unwrap_or_default() {
+            "arm64" => "aarch64",
+            "amd64" => "x86_64",
+            arch => arch,
+        };
+        arch == std::env::consts::ARCH
+            && self.os == TargetOs::host()
+            && self.compiler.is_none()
+            && self.sysroot.is_none()
+    }
+
+    /// Find a cross compiler: the configured one, then `<prefix>g++`, then
+    /// `clang++ --target=<triple>`
+    pub fn detect_toolchain(&mut self) -> Result<&Toolchain> {
+        let (compiler_type, cxx) = if let Some(compiler) = &self.compiler {
+            let compiler_type = if compiler.contains("clang") {
+                CompilerType::Clang
+            } else {
+                CompilerType::GCC
+            };
+            (compiler_type, compiler.clone())
+        } else {
+            let gxx = format!("{}g++", self.prefix);
+            if probe(&gxx).is_some() {
+                (CompilerType::GCC, gxx)
+            } else if probe("clang++").is_some() {
+                (CompilerType::Clang, "clang++".to_string())
+            } else {
+                bail!(
+                    "No cross compiler found for target '{}' ({}).\n\n\
+                    💡 Tip: Install {} or clang, or set `compiler` under [targets.{}] in cx.toml.",
+                    self.name,
+                    self.triple,
+                    gxx,
+                    self.name
+                );
+            }
+        };
+
+        let Some(version) = probe(&cxx) else {
+            bail!(
+                "Cross compiler '{}' for target '{}' not found",
+                cxx,
+                self.name
+            );
+        };
+        Ok(self.toolchain.insert(Toolchain::new_simple(
+            compiler_type,
+            PathBuf::from(cxx),
+            version,
+        )))
+    }
+
+    /// Arguments that make a nested `cx build` (a path dependency) build for
+    /// this target with the same toolchain, whatever its own `[targets]` say
+    pub fn child_args(&self) -> Vec<String> {
+        let settings = TargetSettings {
+            compiler: self
+                .toolchain
+                .as_ref()
+                .map(|tc| tc.cxx_path.to_string_lossy().to_string())
+                .or_else(|| self.compiler.clone()),
+            prefix: Some(self.prefix.clone()),
+            sysroot: self
+                .sysroot
+                .as_ref()
+                .map(|s| s.to_string_lossy().to_string()),
+            flags: Some(self.flags.clone()),
+        };
+        vec![
+            "--target".to_string(),
+            self.triple.clone(),
+            format!(
+                "--target-settings={}",
+                serde_json::to_string(&settings).unwrap_or_default()
+            ),
+        ]
+    }
+
+    /// `--target=` (Clang) and `--sysroot=`, needed when compiling and linking
+    pub fn toolchain_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if self
+            .toolchain
+            .as_ref()
+            .is_some_and(|tc| tc.compiler_type == CompilerType::Clang)
+        {
+            args.push(format!("--target={}", self.triple));
+        }
+        if let Some(sysroot) = &self.sysroot {
+            args.push(format!("--sysroot={}", sysroot.display()));
+        }
+        args
+    }
+
+    /// pkg-config for the target: `<prefix>pkg-config` when installed,
+    /// otherwise pkg-config pointed at the target's `.pc` directories
+    pub fn pkg_config(&self) -> Command {
+        let prefixed = format!("{}pkg-config", self.prefix);
+        if self.sysroot.is_none() && probe(&prefixed).is_some() {
+            return Command::new(prefixed);
+        }
+
+        let mut cmd = Command::new("pkg-config");
+        if std::env::var_os("PKG_CONFIG_LIBDIR").is_none() {
+            let root = self.sysroot.clone().unwrap_or_else(|| PathBuf::from("/"));
+            let dirs = [
+                root.join("usr/lib").join(&self.triple).join("pkgconfig"),
+                root.join("usr/lib/pkgconfig"),
+                root.join("usr/share/pkgconfig"),
+            ];
+            if let Ok(libdir) = std::env::join_paths(dirs) {
+                cmd.env("PKG_CONFIG_LIBDIR", libdir);
+            }
+        }
+        if let Some(sysroot) = &self.sysroot {
+            cmd.env("PKG_CONFIG_SYSROOT_DIR", sysroot);
+        }
+        cmd
+    }
+
+    /// Environment for dependency build scripts so they build for the target
+    pub fn build_env(&self) -> Vec<(String, String)> {
+        let mut env = vec![("CX_TARGET".to_string(), self.triple.clone())];
+        if let Some(tc) = &self.toolchain {
+            env.push(("CC".into(), tc.cc_path.to_string_lossy().to_string()));
+            env.push(("CXX".into(), tc.cxx_path.to_string_lossy().to_string()));
+            if tc.compiler_type == CompilerType::GCC && self.compiler.is_none() {
+                env.push(("AR".into(), format!("{}ar", self.prefix)));
+            }
+        }
+        let args = self.toolchain_args().join(" ");
+        if !args.is_empty() {
+            for var in ["CFLAGS", "CXXFLAGS", "LDFLAGS"] {
+                env.push((var.to_string(), args.clone()));
+            }
+        }
+        env
+    }
+}
+
+/// First line of `<cmd> --version`, if the command runs
+fn probe(cmd: &str) -> Option<String> {
+    let output = Command::new(cmd).arg("--version").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .next()
+            .unwrap_or("unknown")
+            .to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_known_and_triple() {
+        let arm = CrossTarget::resolve("linux-arm64", None).unwrap();
+        assert_eq!(arm.triple, "aarch64-linux-gnu");
+        assert_eq!(arm.prefix, "aarch64-linux-gnu-");
+        assert_eq!(arm.os, TargetOs::Linux);
+
+        let mingw = CrossTarget::resolve("windows-x64-gnu", None).unwrap();
+        assert_eq!(mingw.os, TargetOs::Windows);
+        assert!(CrossTarget::resolve("wasm32", None).unwrap().is_wasm());
+
+        let riscv = CrossTarget::resolve("riscv64-linux-gnu", None).unwrap();
+        assert_eq!(riscv.triple, "riscv64-linux-gnu");
+        assert_eq!(
+            CrossTarget::resolve("esp32", None).unwrap().os,
+            TargetOs::None
+        );
+
+        assert!(CrossTarget::resolve("playstation", None).is_err());
+    }
+
+    #[test]
+    fn test_resolve_applies_settings() {
+        let mut targets = TargetsConfig::default();
+        targets.settings.insert(
+            "linux-arm64".to_string(),
+            TargetSettings {
+                compiler: Some("clang++".to_string()),
+                sysroot: Some("/opt/arm64".to_string()),
+                ..Default::default()
+            },
+        );
+        let mut target = CrossTarget::resolve("linux-arm64", Some(&targets)).unwrap();
+        assert!(!target.is_native());
+        target.toolchain = Some(Toolchain::new_simple(
+            CompilerType::Clang,
+            PathBuf::from("clang++"),
+            "test".to_string(),
+        ));
+        assert_eq!(
+            target.toolchain_args(),
+            vec![
+                "--target=aarch64-linux-gnu".to_string(),
+                "--sysroot=/opt/arm64".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_child_args_carry_the_toolchain() {
+        let settings = TargetSettings {
+            prefix: Some("arm-none-eabi-".to_string()),
+            sysroot: Some("/opt/board".to_string()),
+            flags: Some(vec!["-mcpu=cortex-m4".to_string()]),
+            ..Default::default()
+        };
+        let mut target = CrossTarget::with_settings("arm-none-eabi", settings).unwrap();
+        target.toolchain = Some(Toolchain::new_simple(
+            CompilerType::GCC,
+            PathBuf::from("/usr/bin/arm-none-eabi-g++"),
+            "test".to_string(),
+        ));
+
+        // The child resolves the triple without the parent's [targets] table
+        let args = target.child_args();
+        assert_eq!(args[..2], ["--target", "arm-none-eabi"]);
+        let json = args[2].strip_prefix("--target-settings=").unwrap();
+        let child =
+            CrossTarget::with_settings(&args[1], serde_json::from_str(json).unwrap()).unwrap();
+        assert_eq!(child.triple, "arm-none-eabi");
+        assert_eq!(
+            child.compiler.as_deref(),
+            Some("/usr/bin/arm-none-eabi-g++")
+        );
+        assert_eq!(child.prefix, target.prefix);
+        assert_eq!(child.sysroot, target.sysroot);
+        assert_eq!(child.flags, target.flags);
+    }
+}
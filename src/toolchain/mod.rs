@@ -9,9 +9,10 @@ pub mod types;
 #[cfg(windows)]
 pub mod windows;
 
+pub mod cross; // Cross-compilation targets
 pub mod install; // Toolchain installer
 
-pub use types::{CompilerType, Toolchain, ToolchainError};
+pub use types::{CompilerType, Toolchain, ToolchainError, c_compiler_for};
 
 use std::path::PathBuf;
 
@@ -42,26 +43,26 @@ fn detect_unix_toolchain(preferred: Option<CompilerType>) -> Result<Toolchain, T
     };
 
     for (cmd, compiler_type) in compilers {
-        if let Ok(output) = Command::new("which").arg(cmd).output() {
-            if output.status.success() {
-                let path_str = String::from_utf8_lossy(&output.stdout).trim().to_string();
-                let cxx_path = PathBuf::from(&path_str);
-
-                // Get version
-                let version = Command::new(cmd)
-                    .arg("--version")
-                    .output()
-                    .map(|o| {
-                        String::from_utf8_lossy(&o.stdout)
-                            .lines()
-                            .next()
-                            .unwrap_or("unknown")
-                            .to_string()
-                    })
-                    .unwrap_or_else(|_| "unknown".to_string());
-
-                return Ok(Toolchain::new_simple(compiler_type, cxx_path, version));
-            }
+        if let Ok(output) = Command::new("which").arg(cmd).output()
+            && output.status.success()
+        {
+            let path_str = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            let cxx_path = PathBuf::from(&path_str);
+
+            // Get version
+            let version = Command::new(cmd)
+                .arg("--version")
+                .output()
+                .map(|o| {
+                    String::from_utf8_lossy(&o.stdout)
+                        .lines()
+                        .next()
+                        .unwrap_or("unknown")
+                        .to_string()
+                })
+                .unwrap_or_else(|_| "unknown".to_string());
+
+            return Ok(Toolchain::new_simple(compiler_type, cxx_path, version));
         }
     }
 
@@ -84,6 +85,7 @@ pub fn get_or_detect_toolchain(
         // Parse the selection file to get compiler type, path, and source
         let mut selected_type: Option<CompilerType> = None;
         let mut selected_path: Option<PathBuf> = None;
+        #[cfg(windows)]
         let mut selected_source: Option<String> = None;
 
         for line in contents.lines() {
@@ -107,6 +109,7 @@ pub fn get_or_detect_toolchain(
                     selected_path = Some(PathBuf::from(&line[start + 1..end]));
                 }
             }
+            #[cfg(windows)]
             if line.starts_with("source") {
                 // Extract source from: source = "Visual Studio Build Tools 2026"
                 if let Some(start) = line.find('"')
@@ -177,7 +180,8 @@ pub fn get_or_detect_toolchain(
         && let Ok(cached) = toml::from_str::<Toolchain>(&contents)
     {
         // Verify the cached toolchain still exists AND matches preference
-        if cached.cxx_path.exists() {
+        // (older caches could hold a bogus C compiler path, e.g. "g" for "g++")
+        if cached.cxx_path.exists() && cached.cc_path.exists() {
             // If user has a preference, cache must match it
             let matches_preference = match &preferred {
                 None => true, // No preference, any cached toolchain is fine
@@ -11,7 +11,7 @@
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Supported compiler types
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -73,11 +73,7 @@ pub struct Toolchain {
 impl Toolchain {
     /// Creates a new toolchain with minimal info (for non-MSVC compilers)
     pub fn new_simple(compiler_type: CompilerType, cxx_path: PathBuf, version: String) -> Self {
-        let cc_path = if cxx_path.to_string_lossy().contains("++") {
-            PathBuf::from(cxx_path.to_string_lossy().replace("++", ""))
-        } else {
-            cxx_path.clone()
-        };
+        let cc_path = c_compiler_for(&cxx_path);
 
         Self {
             compiler_type,
@@ -125,6 +121,39 @@ impl Toolchain {
     }
 }
 
+/// Derive the C compiler driver that pairs with a C++ driver
+/// (`g++` → `gcc`, `clang++` → `clang`, `em++` → `emcc`, `c++` → `cc`).
+/// Cross prefixes, directories and `.exe` are kept; `cl`/`clang-cl` map to themselves.
+pub fn c_compiler_for(cxx_path: &Path) -> PathBuf {
+    let Some(file_name) = cxx_path
+        .file_name()
+        .map(|f| f.to_string_lossy().to_string())
+    else {
+        return cxx_path.to_path_buf();
+    };
+    let (stem, exe) = match file_name.strip_suffix(".exe") {
+        Some(stem) => (stem, ".exe"),
+        None => (file_name.as_str(), ""),
+    };
+
+    let cc_stem = [
+        ("clang++", "clang"),
+        ("g++", "gcc"),
+        ("em++", "emcc"),
+        ("c++", "cc"),
+    ]
+    .iter()
+    .find_map(|(cxx, cc)| {
+        stem.strip_suffix(cxx)
+            .map(|prefix| format!("{}{}", prefix, cc))
+    });
+
+    match cc_stem {
+        Some(cc_stem) => cxx_path.with_file_name(format!("{}{}", cc_stem, exe)),
+        None => cxx_path.to_path_buf(),
+    }
+}
+
 /// Visual Studio installation info from vswhere
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
@@ -170,3 +199,27 @@ impl From<std::io::Error> for ToolchainError {
         ToolchainError::IoError(e)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_c_compiler_for() {
+        assert_eq!(c_compiler_for(Path::new("g++")), PathBuf::from("gcc"));
+        assert_eq!(
+            c_compiler_for(Path::new("/usr/bin/clang++")),
+            PathBuf::from("/usr/bin/clang")
+        );
+        assert_eq!(
+            c_compiler_for(Path::new("aarch64-linux-gnu-g++")),
+            PathBuf::from("aarch64-linux-gnu-gcc")
+        );
+        assert_eq!(c_compiler_for(Path::new("em++")), PathBuf::from("emcc"));
+        assert_eq!(
+            c_compiler_for(Path::new("g++.exe")),
+            PathBuf::from("gcc.exe")
+        );
+        assert_eq!(c_compiler_for(Path::new("cl.exe")), PathBuf::from("cl.exe"));
+    }
+}
@@ -1,6 +1,7 @@
 //! Windows-specific toolchain discovery using vswhere and vcvars
 
 use super::types::{CompilerType, Toolchain, ToolchainError, VSInstallation};
+use crate::status;
 use colored::*;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
@@ -515,7 +516,7 @@ pub fn detect_toolchain(preferred: Option<CompilerType>) -> Result<Toolchain, To
             // If VS missing, can we fallback to GCC?
             if vs_installations.is_empty() {
                 if let Some(gxx) = mingw_path {
-                    println!(
+                    status!(
                         "{} Visual Studio not found, falling back to MinGW.",
                         "!".yellow()
                     );
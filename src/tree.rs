@@ -7,17 +7,31 @@
 //!
 //! ```text
 //! my-project v1.0.0
-//! ├── raylib (tag: 5.0)
 //! ├── json (tag: v3.11.2)
-//! └── fmt (git: https://github.com/fmtlib/fmt)
+//! ├── raylib (tag: 5.0)
+//! └── spdlog (tag: v1.12.0)
+//!     └── fmt (tag: 10.1.1)
 //! ```
+//!
+//! Sub-dependencies are read from the `cx.toml` of each path dependency and
+//! each fetched git dependency (in `vendor/` or the global cache), so run
+//! `cx build` or `cx sync` first.
 
 use crate::build::load_config;
+use crate::config::Dependency;
 use anyhow::Result;
 use colored::*;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Looks up a dependency's own `[dependencies]` and the directory they are
+/// relative to
+type SubDeps<'a> =
+    dyn Fn(&str, &Dependency, &Path) -> Option<(PathBuf, HashMap<String, Dependency>)> + 'a;
 
 pub fn print_tree() -> Result<()> {
-    let config = load_config()?;
+    let mut config = load_config()?;
+    crate::features::apply_project(&mut config)?;
 
     // Root
     println!(
@@ -26,56 +40,121 @@ pub fn print_tree() -> Result<()> {
         config.package.version
     );
 
-    if let Some(deps) = config.dependencies {
-        let count = deps.len();
-        for (i, (name, dep)) in deps.iter().enumerate() {
-            let is_last = i == count - 1;
-            let prefix = if is_last { "└──" } else { "├──" };
-
-            // Determine version or type
-            let info = match dep {
-                crate::config::Dependency::Simple(url) => format!("{}", url.dimmed()),
-                crate::config::Dependency::Complex {
-                    git,
-                    pkg,
-                    tag,
-                    branch,
-                    rev,
-                    ..
-                } => {
-                    if let Some(t) = tag {
-                        format!("tag: {}", t.green())
-                    } else if let Some(b) = branch {
-                        format!("branch: {}", b.yellow())
-                    } else if let Some(r) = rev {
-                        format!("rev: {:.7}", r.dimmed())
-                    } else if let Some(g) = git {
-                        format!("git: {}", g.dimmed())
-                    } else if let Some(p) = pkg {
-                        format!("pkg: {}", p.cyan())
-                    } else {
-                        "unknown".dimmed().to_string()
-                    }
-                }
+    match config.dependencies {
+        Some(deps) if !deps.is_empty() => {
+            // Sub-dependencies come from each dependency's own cx.toml
+            let sub_deps = |name: &str, dep: &Dependency, base: &Path| {
+                let dir = match dep {
+                    Dependency::Complex {
+                        path: Some(path), ..
+                    } => base.join(path),
+                    _ => crate::deps::dependency_dir(name)?,
+                };
+                let mut manifest = crate::deps::read_manifest(&dir)?;
+                // Invalid feature requests surface when building
+                let _ = crate::features::apply_dependency(&mut manifest, dep);
+                let deps = manifest.dependencies?;
+                Some((dir, deps))
             };
+            let mut lines = Vec::new();
+            let project_dir = std::env::current_dir()?;
+            render(
+                &deps,
+                &project_dir,
+                "",
+                &sub_deps,
+                &mut HashSet::new(),
+                &mut lines,
+            );
+            for line in lines {
+                println!("{}", line);
+            }
+        }
+        _ => println!("└── (no dependencies)"),
+    }
 
-            println!("{} {} ({})", prefix, name.bold(), info);
+    Ok(())
+}
 
-            // In a real sophisticated tree, we would recursively check lockfiles or
-            // query the registry for sub-dependencies.
-            // For now, Caxe is flat or only tracking top-level until we parse vendored deps properly.
-            // So we stop here.
+/// Version or source shown next to a dependency
+fn describe(dep: &Dependency) -> String {
+    match dep {
+        Dependency::Simple(url) => format!("{}", url.dimmed()),
+        Dependency::Complex {
+            git,
+            pkg,
+            path,
+            url,
+            tag,
+            branch,
+            rev,
+            ..
+        } => {
+            if let Some(t) = tag {
+                format!("tag: {}", t.green())
+            } else if let Some(b) = branch {
+                format!("branch: {}", b.yellow())
+            } else if let Some(r) = rev {
+                format!("rev: {:.7}", r.dimmed())
+            } else if let Some(g) = git {
+                format!("git: {}", g.dimmed())
+            } else if let Some(p) = pkg {
+                format!("pkg: {}", p.cyan())
+            } else if let Some(p) = path {
+                format!("path: {}", p.cyan())
+            } else if let Some(u) = url {
+                format!("url: {}", u.dimmed())
+            } else {
+                "unknown".dimmed().to_string()
+            }
         }
-    } else {
-        println!("└── (no dependencies)");
     }
+}
 
-    Ok(())
+/// Render `deps` and, recursively, their sub-dependencies. A package whose
+/// dependencies were already listed is marked `(*)` instead of repeated.
+fn render(
+    deps: &HashMap<String, Dependency>,
+    base: &Path,
+    indent: &str,
+    sub_deps: &SubDeps,
+    expanded: &mut HashSet<String>,
+    lines: &mut Vec<String>,
+) {
+    let mut names: Vec<&String> = deps.keys().collect();
+    names.sort();
+
+    let count = names.len();
+    for (i, name) in names.into_iter().enumerate() {
+        let is_last = i == count - 1;
+        let prefix = if is_last { "└──" } else { "├──" };
+        let children = sub_deps(name, &deps[name], base).filter(|(_, deps)| !deps.is_empty());
+
+        let mut line = format!(
+            "{}{} {} ({})",
+            indent,
+            prefix,
+            name.bold(),
+            describe(&deps[name])
+        );
+        if children.is_some() && expanded.contains(name) {
+            line.push_str(&format!(" {}", "(*)".dimmed()));
+            lines.push(line);
+            continue;
+        }
+        lines.push(line);
+
+        if let Some((dir, children)) = children {
+            expanded.insert(name.clone());
+            let child_indent = format!("{}{}", indent, if is_last { "    " } else { "│   " });
+            render(&children, &dir, &child_indent, sub_deps, expanded, lines);
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::config::Dependency;
+    use super::*;
 
     #[test]
     fn test_dependency_simple_format() {
@@ -88,15 +167,10 @@ mod tests {
 
     #[test]
     fn test_dependency_complex_with_tag() {
-        let dep = Dependency::Complex {
-            git: Some("https://github.com/nlohmann/json.git".to_string()),
-            pkg: None,
-            tag: Some("v3.11.2".to_string()),
-            branch: None,
-            rev: None,
-            build: None,
-            output: None,
-        };
+        let mut dep = Dependency::git("https://github.com/nlohmann/json.git");
+        if let Dependency::Complex { tag, .. } = &mut dep {
+            *tag = Some("v3.11.2".to_string());
+        }
 
         match dep {
             Dependency::Complex { tag, .. } => {
@@ -108,15 +182,10 @@ mod tests {
 
     #[test]
     fn test_dependency_complex_with_branch() {
-        let dep = Dependency::Complex {
-            git: Some("https://github.com/libsdl-org/SDL.git".to_string()),
-            pkg: None,
-            tag: None,
-            branch: Some("SDL2".to_string()),
-            rev: None,
-            build: None,
-            output: None,
-        };
+        let mut dep = Dependency::git("https://github.com/libsdl-org/SDL.git");
+        if let Dependency::Complex { branch, .. } = &mut dep {
+            *branch = Some("SDL2".to_string());
+        }
 
         match dep {
             Dependency::Complex { branch, .. } => {
@@ -128,15 +197,7 @@ mod tests {
 
     #[test]
     fn test_dependency_pkg_config() {
-        let dep = Dependency::Complex {
-            git: None,
-            pkg: Some("gtk+-3.0".to_string()),
-            tag: None,
-            branch: None,
-            rev: None,
-            build: None,
-            output: None,
-        };
+        let dep = Dependency::pkg("gtk+-3.0");
 
         match dep {
             Dependency::Complex { pkg, .. } => {
@@ -145,4 +206,49 @@ mod tests {
             _ => panic!("Expected Complex variant"),
         }
     }
+
+    #[test]
+    fn test_render_nested_and_repeated() {
+        let dep = |url: &str| Dependency::Simple(url.to_string());
+        let root: HashMap<String, Dependency> = [
+            ("app_core".to_string(), dep("https://example.com/app_core")),
+            (
+                "spdlog".to_string(),
+                dep("https://github.com/gabime/spdlog"),
+            ),
+        ]
+        .into();
+        let sub_deps = |name: &str, _: &Dependency, _: &Path| {
+            let deps: HashMap<String, Dependency> = match name {
+                "app_core" => Some(
+                    [
+                        (
+                            "spdlog".to_string(),
+                            dep("https://github.com/gabime/spdlog"),
+                        ),
+                        ("zlib".to_string(), dep("https://github.com/madler/zlib")),
+                    ]
+                    .into(),
+                ),
+                "spdlog" => {
+                    Some([("fmt".to_string(), dep("https://github.com/fmtlib/fmt"))].into())
+                }
+                _ => None,
+            }?;
+            Some((PathBuf::from(name), deps))
+        };
+
+        let mut lines = Vec::new();
+        let base = Path::new(".");
+        render(&root, base, "", &sub_deps, &mut HashSet::new(), &mut lines);
+
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].starts_with("├── ") && lines[0].contains("app_core"));
+        assert!(lines[1].starts_with("│   ├── ") && lines[1].contains("spdlog"));
+        assert!(lines[2].starts_with("│   │   └── ") && lines[2].contains("fmt"));
+        assert!(lines[3].starts_with("│   └── ") && lines[3].contains("zlib"));
+        // Already expanded under app_core
+        assert!(lines[4].starts_with("└── ") && lines[4].contains("spdlog"));
+        assert!(lines[4].contains("(*)"));
+    }
 }
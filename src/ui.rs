@@ -7,6 +7,7 @@
 //!
 //! - `Table` - Auto-sizing table with headers and rows
 
+use crate::status;
 use colored::*;
 use std::cmp;
 
@@ -116,25 +117,30 @@ impl Table {
         };
 
         // Print Top Border
-        println!("{}", make_sep(top_left, top_sep, top_right));
+        status!("{}", make_sep(top_left, top_sep, top_right));
 
         // Print Headers
-        print!("  {}", vertical);
+        let mut line = format!("  {}", vertical);
         for (i, header) in self.headers.iter().enumerate() {
             let width = col_widths[i];
             let truncated = truncate(header, width);
             let visible_len = truncated.chars().count();
             let padding = width.saturating_sub(visible_len);
-            print!(" {} {}{}", truncated.bold(), " ".repeat(padding), vertical);
+            line.push_str(&format!(
+                " {} {}{}",
+                truncated.bold(),
+                " ".repeat(padding),
+                vertical
+            ));
         }
-        println!();
+        status!("{}", line);
 
         // Print Separator
-        println!("{}", make_sep(mid_left, mid_sep, mid_right));
+        status!("{}", make_sep(mid_left, mid_sep, mid_right));
 
         // Print Rows
         for row in &self.rows {
-            print!("  {}", vertical);
+            let mut line = format!("  {}", vertical);
             for (i, cell) in row.iter().enumerate() {
                 let clean_raw = sanitize_content(cell);
                 let width = col_widths[i];
@@ -145,13 +151,18 @@ impl Table {
                 let visible_len = clean_trunc.chars().count();
                 let padding = width.saturating_sub(visible_len);
 
-                print!(" {} {}{}", truncated, " ".repeat(padding), vertical);
+                line.push_str(&format!(
+                    " {} {}{}",
+                    truncated,
+                    " ".repeat(padding),
+                    vertical
+                ));
             }
-            println!();
+            status!("{}", line);
         }
 
         // Print Bottom Border
-        println!("{}", make_sep(bottom_left, bottom_sep, bottom_right));
+        status!("{}", make_sep(bottom_left, bottom_sep, bottom_right));
     }
 }
 
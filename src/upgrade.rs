@@ -32,6 +32,7 @@ struct Asset {
 }
 
 pub fn check_and_upgrade() -> Result<()> {
+    crate::fetch_mode::ensure_online("check for caxe updates")?;
     println!("{} Checking for updates...", "🔍".blue());
 
     let current_ver = Version::parse(env!("CARGO_PKG_VERSION"))?;
@@ -65,7 +65,7 @@ fn get_cx_binary() -> PathBuf {
     let mut path = std::env::current_exe().expect("Failed to get current exe");
     path.pop(); // Remove exe name
     path.pop(); // Remove deps folder
-    path.join("cx.exe") // or "cx" on Unix
+    path.join(format!("cx{}", std::env::consts::EXE_SUFFIX))
 }
 
 #[test]
@@ -199,3 +199,108 @@ fn test_dry_run_mode() {
     // Cleanup
     fs::remove_dir_all(&project_dir).ok();
 }
+
+#[test]
+fn test_json_message_format_stdout() {
+    let project_dir = create_test_project("test_json_stdout", true);
+
+    let cx = get_cx_binary();
+    if !cx.exists() {
+        eprintln!("Skipping test: cx binary not found at {:?}", cx);
+        return;
+    }
+
+    // Scripts and verbose output are human text and must stay off stdout
+    let mut cx_toml = fs::read_to_string(project_dir.join("cx.toml")).unwrap();
+    cx_toml.push_str("\n[scripts]\npre_build = \"echo pre-build\"\n");
+    fs::write(project_dir.join("cx.toml"), cx_toml).unwrap();
+
+    let output = Command::new(&cx)
+        .args(["build", "--verbose", "--message-format=json"])
+        .current_dir(&project_dir)
+        .output()
+        .expect("Failed to execute cx build --message-format=json");
+
+    assert!(
+        output.status.success(),
+        "Build failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let records: Vec<serde_json::Value> = stdout
+        .lines()
+        .map(|line| {
+            serde_json::from_str(line)
+                .unwrap_or_else(|e| panic!("Not a JSON record ({}): {:?}", e, line))
+        })
+        .collect();
+    assert!(
+        records
+            .iter()
+            .any(|r| r["reason"] == "compiler-artifact" && r["kind"] == "bin")
+    );
+    assert_eq!(records.last().unwrap()["reason"], "build-finished");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("pre-build"));
+
+    // Cleanup
+    fs::remove_dir_all(&project_dir).ok();
+}
+
+#[test]
+fn test_removed_source_is_not_linked_into_tests() {
+    let project_dir = create_test_project("test_removed_source", true);
+
+    let cx = get_cx_binary();
+    if !cx.exists() {
+        eprintln!("Skipping test: cx binary not found at {:?}", cx);
+        return;
+    }
+
+    // Build every source under src/, then move helper() to another file
+    let cx_toml =
+        "[package]\nname = \"test_removed_source\"\nversion = \"0.1.0\"\nedition = \"c++17\"\n";
+    fs::write(project_dir.join("cx.toml"), cx_toml).unwrap();
+    let src = project_dir.join("src");
+    fs::write(src.join("old.cpp"), "int helper() { return 1; }\n").unwrap();
+    fs::create_dir_all(project_dir.join("tests")).unwrap();
+    fs::write(
+        project_dir.join("tests").join("test_helper.cpp"),
+        "int helper();\nint main() { return helper() == 2 ? 0 : 1; }\n",
+    )
+    .unwrap();
+
+    let build = || {
+        let output = Command::new(&cx)
+            .arg("build")
+            .current_dir(&project_dir)
+            .output()
+            .expect("Failed to execute cx build");
+        assert!(
+            output.status.success(),
+            "Build failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    };
+    build();
+    fs::remove_file(src.join("old.cpp")).unwrap();
+    fs::write(src.join("new.cpp"), "int helper() { return 2; }\n").unwrap();
+    build();
+
+    // old.cpp's object is still on disk, but linking it would define helper() twice
+    let output = Command::new(&cx)
+        .arg("test")
+        .current_dir(&project_dir)
+        .output()
+        .expect("Failed to execute cx test");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success() && stdout.contains("1/1 passed"),
+        "Tests failed: {}{}",
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // Cleanup
+    fs::remove_dir_all(&project_dir).ok();
+}